@@ -1,126 +1,317 @@
 // src/arbitrage.rs
-use crate::data::GLOBAL_DATA;
+
+// Полный обход всех цепочек (chains_4 / chains_5) на согласованном снимке графа.
+// Дополняет событийный путь роутера: роутер пересчитывает только цепочки, затронутые
+// обновлением пула, а полный обход находит то, что инкрементальный путь пропустил
+// (переполнение очереди симуляции, пропущенные уведомления, устаревший кеш).
+
+use std::time::{Duration, Instant};
+use hashbrown::{HashMap, HashSet};
+use log::{info, debug, warn};
+use petgraph::Graph;
+use solana_program::pubkey::Pubkey;
+use crate::data::{GLOBAL_DATA, PoolStateBase};
 use crate::graph::PoolEdge;
-use crate::config::START_END_TOKEN_FOR_CHAINS;
-use log::{info, debug};
-use petgraph::graph::NodeIndex;
-use petgraph::visit::EdgeRef;
-
-const SIMULATION_AMOUNT: u64 = 1_000_000_000; // 1 SOL
-
-pub struct ArbitrageOpportunity {
-    pub chain: Vec<String>,
-    pub total_return: f64,
-    pub expected_profit: f64,
-    pub pools: Vec<(Pubkey, f64)>, // (pool_address, amount_in)
+use crate::config::{SIMULATION_AMOUNT, FULL_SCAN_INTERVAL_MS};
+use crate::router::{RouterEngine, ArbitrageOpportunity, OpportunitySource};
+use crate::wallet::trade_amount;
+use crate::websocket::ws_data::DexType;
+use crate::websocket::ws_parser::PoolCommitment;
+
+// Полный обход идет по самому свежему состоянию
+const SCAN_COMMITMENT: PoolCommitment = PoolCommitment::Processed;
+
+/// Согласованный снимок графа, состояний и версий пулов на момент начала обхода
+pub struct GraphSnapshot {
+    pub commitment: PoolCommitment,
+    pub graph: Graph<String, PoolEdge>,
+    pub pool_states: HashMap<(DexType, Pubkey), (PoolStateBase, u64)>,
+    pub pool_versions: HashMap<Pubkey, u64>,
+    /// Пулы, обновившиеся во время снимка: их ребро и состояние могут относиться к разным версиям
+    pub unstable_pools: HashSet<Pubkey>,
+    pub chains: Vec<(usize, Vec<String>)>,
 }
 
-pub fn monitor_arbitrage_opportunities() {
-    // Получаем все цепочки длины 4 и 5
-    let chains_4 = GLOBAL_DATA.chains_4.iter().collect::<Vec<_>>();
-    let chains_5 = GLOBAL_DATA.chains_5.iter().collect::<Vec<_>>();
-
-    for chain in chains_4.iter().chain(chains_5.iter()) {
-        if let Some(opportunity) = simulate_chain(chain, SIMULATION_AMOUNT) {
-            if opportunity.expected_profit > 0.0 {
-                info!("Found arbitrage opportunity:");
-                info!("Chain: {:?}", opportunity.chain);
-                info!("Expected return: {} SOL", opportunity.total_return / 1e9);
-                info!("Expected profit: {} SOL", opportunity.expected_profit / 1e9);
+impl GraphSnapshot {
+    /// Снимает копию графа вместе с состояниями и версиями пулов.
+    ///
+    /// Обновление пула меняет состояние, ребро и версию под блокировкой состояний своего DEX,
+    /// поэтому версии читаются дважды: до копирования графа и после копирования состояний.
+    /// Пул с одинаковой версией в обоих чтениях не менялся за время снимка - его ребро и
+    /// состояние согласованы. Остальные пулы попадают в `unstable_pools`, цепочки через них
+    /// пропускаются до следующего обхода.
+    pub fn capture(commitment: PoolCommitment) -> Option<Self> {
+        let versions_before = Self::read_pool_versions(commitment);
+
+        let graph = GLOBAL_DATA.graph_for(commitment).get("main")?.clone();
+
+        let mut pool_states = HashMap::new();
+        for dex in [DexType::Orca, DexType::Raydium, DexType::Meteora] {
+            match commitment {
+                PoolCommitment::Processed => {
+                    if let Some(states) = GLOBAL_DATA.processed_pool_states.get(&dex) {
+                        for state in states.iter() {
+                            pool_states.insert((dex, *state.key()), (state.base.clone(), state.processed_slot));
+                        }
+                    }
+                }
+                PoolCommitment::Confirmed => {
+                    if let Some(states) = GLOBAL_DATA.confirmed_pool_states.get(&dex) {
+                        for state in states.iter() {
+                            pool_states.insert((dex, *state.key()), (state.base.clone(), state.confirmed_slot));
+                        }
+                    }
+                }
+                PoolCommitment::Finalized => {
+                    if let Some(states) = GLOBAL_DATA.finalized_pool_states.get(&dex) {
+                        for state in states.iter() {
+                            pool_states.insert((dex, *state.key()), (state.base.clone(), state.finalized_slot));
+                        }
+                    }
+                }
             }
         }
+
+        let pool_versions = Self::read_pool_versions(commitment);
+        let unstable_pools = pool_versions
+            .iter()
+            .filter(|(pool, version)| versions_before.get(*pool) != Some(*version))
+            .map(|(pool, _)| *pool)
+            .collect();
+
+        let total_chains = GLOBAL_DATA.chain_storage_4.len() + GLOBAL_DATA.chain_storage_5.len();
+        let chains = (0..total_chains)
+            .filter_map(|chain_id| GLOBAL_DATA.get_chain(chain_id).map(|chain| (chain_id, chain)))
+            .collect();
+
+        Some(Self {
+            commitment,
+            graph,
+            pool_states,
+            pool_versions,
+            unstable_pools,
+            chains,
+        })
+    }
+
+    // Текущие версии пулов commitment
+    fn read_pool_versions(commitment: PoolCommitment) -> HashMap<Pubkey, u64> {
+        GLOBAL_DATA.pool_versions
+            .iter()
+            .filter(|entry| entry.key().0 == commitment)
+            .map(|entry| (entry.key().1, *entry.value()))
+            .collect()
     }
 }
 
-fn simulate_chain(chain: &[String], amount_in: u64) -> Option<ArbitrageOpportunity> {
-    if let Some(graph) = GLOBAL_DATA.processed_graph.get("main") {
-        let mut current_amount = amount_in as f64;
-        let mut pools = Vec::new();
-
-        for (i, window) in chain.windows(2).enumerate() {
-            let token_in = &window[0];
-            let token_out = &window[1];
-            
-            let is_first_swap = i == 0;
-            let is_last_swap = i == chain.len() - 2;
-
-            let best_edge = find_best_edge(
-                &graph, 
-                token_in, 
-                token_out,
-                is_first_swap,
-                is_last_swap
-            )?;
-            
-            let amount_out = simulate_swap(
-                current_amount,
-                best_edge.price,
-                best_edge.fee_rate,
-                best_edge.liquidity
-            );
+/// Итог одного полного обхода
+#[derive(Debug, Default, Clone)]
+pub struct ScanReport {
+    pub chains_scanned: usize,
+    pub profitable: usize,
+    /// Возможности, которые роутер не опубликовал на тех же версиях пулов,
+    /// вместе с версиями пулов, на которых они посчитаны
+    pub missed_by_router: Vec<(ArbitrageOpportunity, Vec<(Pubkey, u64)>)>,
+    pub duration: Duration,
+}
+
+/// Обходит все цепочки снимка и возвращает отчет.
+pub fn scan_snapshot(snapshot: &GraphSnapshot) -> ScanReport {
+    let started = Instant::now();
+    let mut report = ScanReport::default();
 
-            pools.push((best_edge.pool_address, current_amount));
-            current_amount = amount_out;
+    for (chain_id, chain) in &snapshot.chains {
+        report.chains_scanned += 1;
+
+        let chain_result = match RouterEngine::recalc_chain_on(
+            &snapshot.graph,
+            chain,
+            snapshot.commitment,
+            |pool| snapshot.pool_versions.get(pool).copied().unwrap_or(0)
+        ) {
+            Some(r) => r,
+            None => continue,
+        };
+
+        // Ребро и состояние пула могли быть сняты на разных версиях
+        if chain_result.pool_versions.iter().any(|(pool, _)| snapshot.unstable_pools.contains(pool)) {
+            continue;
         }
 
-        let profit = current_amount - amount_in as f64;
-        
-        Some(ArbitrageOpportunity {
-            chain: chain.to_vec(),
-            total_return: current_amount,
-            expected_profit: profit,
-            pools,
-        })
-    } else {
-        None
+        let start_token = chain_result.chain_tokens.first().map(String::as_str).unwrap_or_default();
+        let amount_in = trade_amount(start_token, SIMULATION_AMOUNT);
+        if amount_in == 0 {
+            continue;
+        }
+
+        let opportunity = match RouterEngine::simulate_hops(
+            *chain_id,
+            &chain_result,
+            amount_in,
+            OpportunitySource::FullScan,
+            |dex, pool| snapshot.pool_states.get(&(dex, *pool)).cloned()
+        ) {
+            Some(o) => o,
+            None => continue,
+        };
+
+        if !RouterEngine::passes_profit_threshold(&opportunity) {
+            continue;
+        }
+        report.profitable += 1;
+
+        if !RouterEngine::was_published(*chain_id, snapshot.commitment, &chain_result.pool_versions) {
+            report.missed_by_router.push((opportunity, chain_result.pool_versions));
+        }
     }
+
+    report.duration = started.elapsed();
+    report
 }
 
-fn find_best_edge<'a>(
-    graph: &'a Graph<String, PoolEdge>,
-    token_in: &str,
-    token_out: &str,
-    is_first_swap: bool,
-    is_last_swap: bool
-) -> Option<&'a PoolEdge> {
-    // Находим индексы вершин
-    let start_idx = graph.node_indices()
-        .find(|&i| graph[i] == token_in)?;
-    let end_idx = graph.node_indices()
-        .find(|&i| graph[i] == token_out)?;
-
-    let edges: Vec<_> = graph.edges_connecting(start_idx, end_idx).collect();
-    
-    if edges.is_empty() {
-        return None;
-    }
+/// Периодический полный обход. Найденные и пропущенные роутером возможности
+/// публикуются в общий канал с источником `FullScan`.
+pub async fn monitor_arbitrage_opportunities() {
+    info!("Запуск полного обхода цепочек каждые {} мс", FULL_SCAN_INTERVAL_MS);
+    let mut interval = tokio::time::interval(Duration::from_millis(FULL_SCAN_INTERVAL_MS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+
+        // Обход - чисто вычислительная задача, не блокируем рантайм
+        let result = tokio::task::spawn_blocking(|| {
+            GraphSnapshot::capture(SCAN_COMMITMENT).map(|snapshot| scan_snapshot(&snapshot))
+        }).await;
+
+        let report = match result {
+            Ok(Some(report)) => report,
+            Ok(None) => {
+                debug!("Полный обход: граф 'main' еще не построен");
+                continue;
+            }
+            Err(e) => {
+                warn!("Полный обход завершился с ошибкой: {}", e);
+                continue;
+            }
+        };
 
-    // Для первого свопа и промежуточных - ищем максимальную цену
-    if is_first_swap || (!is_first_swap && !is_last_swap) {
-        edges.into_iter()
-            .max_by(|e1, e2| e1.weight().price.partial_cmp(&e2.weight().price).unwrap())
-            .map(|e| e.weight())
-    } 
-    // Для последнего свопа - ищем минимальную цену
-    else {
-        edges.into_iter()
-            .min_by(|e1, e2| e1.weight().price.partial_cmp(&e2.weight().price).unwrap())
-            .map(|e| e.weight())
+        debug!("Полный обход: цепочек={}, прибыльных={}, пропущено роутером={}, время={:?}",
+            report.chains_scanned, report.profitable, report.missed_by_router.len(), report.duration);
+
+        for (opportunity, pool_versions) in report.missed_by_router {
+            info!("Полный обход: возможность пропущена роутером [{}] {:?}: net_profit={}",
+                opportunity.chain_id, opportunity.chain, opportunity.net_profit);
+            RouterEngine::publish_chain_opportunity(opportunity, &pool_versions);
+        }
     }
 }
 
-fn simulate_swap(
-    amount_in: f64,
-    price: f64,
-    fee_rate: f64,
-    liquidity: f64
-) -> f64 {
-    let fee_amount = amount_in * fee_rate;
-    let net_amount = amount_in - fee_amount;
-    
-    // Учитываем проскальзывание на основе размера свопа и ликвидности
-    let slippage = (net_amount / liquidity).min(0.02); // max 2% slippage
-    let effective_price = price * (1.0 - slippage);
-    
-    net_amount * effective_price
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+    use crate::data::PoolState;
+    use crate::decoder::WhirlpoolData;
+    use crate::websocket::ws_parser::PoolData;
+
+    // Whirlpool с ценой token_b/token_a = `price`, без комиссии и с ликвидностью,
+    // при которой проскальзывание на SIMULATION_AMOUNT пренебрежимо
+    fn constant_price_pool(pool_address: Pubkey, token_a: Pubkey, token_b: Pubkey, price: u64) -> PoolStateBase {
+        let mut data = WhirlpoolData::zeroed();
+        data.token_mint_a = token_a;
+        data.token_mint_b = token_b;
+        data.tick_spacing = 64;
+        data.liquidity = 1_000_000_000_000_000_000_000_000;
+        data.sqrt_price = ((price as f64).sqrt() * 18_446_744_073_709_551_616.0) as u128;
+        PoolStateBase::from_pool_data(pool_address, &PoolData::Whirlpool(data))
+    }
+
+    // Снимок с одной цепочкой-циклом через три пула; цена первого пула задает прибыль цикла.
+    // Символы токенов уникальны для теста: GLOBAL_DATA общий для параллельных тестов.
+    fn cycle_snapshot(prefix: &str, chain_id: usize, first_price: u64) -> GraphSnapshot {
+        let symbols: Vec<String> = ["A", "B", "C"].iter().map(|s| format!("{}_{}", prefix, s)).collect();
+        let mints: Vec<Pubkey> = symbols.iter().map(|_| Pubkey::new_unique()).collect();
+        for (symbol, mint) in symbols.iter().zip(&mints) {
+            GLOBAL_DATA.add_token(symbol.clone(), *mint);
+        }
+
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = symbols.iter().map(|s| graph.add_node(s.clone())).collect();
+        let mut pool_states = HashMap::new();
+        let mut pool_versions = HashMap::new();
+
+        for i in 0..3 {
+            let (from, to) = (i, (i + 1) % 3);
+            let pool = Pubkey::new_unique();
+            let price = if i == 0 { first_price } else { 1 };
+            GLOBAL_DATA.add_pools(
+                symbols[from].clone(), symbols[to].clone(), pool,
+                mints[from], mints[to], 1_000_000.0, DexType::Orca,
+            );
+            graph.add_edge(nodes[from], nodes[to], PoolEdge::new(pool));
+            pool_states.insert((DexType::Orca, pool), (constant_price_pool(pool, mints[from], mints[to], price), 100));
+            pool_versions.insert(pool, 1);
+        }
+
+        let chain = vec![symbols[0].clone(), symbols[1].clone(), symbols[2].clone(), symbols[0].clone()];
+        GraphSnapshot {
+            commitment: PoolCommitment::Processed,
+            graph,
+            pool_states,
+            pool_versions,
+            unstable_pools: HashSet::new(),
+            chains: vec![(chain_id, chain)],
+        }
+    }
+
+    #[test]
+    fn scan_reports_profitable_cycle_missed_by_router() {
+        let snapshot = cycle_snapshot("SCAN_PROFIT", 900_001, 2);
+        let report = scan_snapshot(&snapshot);
+
+        assert_eq!(report.chains_scanned, 1);
+        assert_eq!(report.profitable, 1);
+        assert_eq!(report.missed_by_router.len(), 1);
+
+        let (opportunity, pool_versions) = &report.missed_by_router[0];
+        assert_eq!(opportunity.chain_id, 900_001);
+        assert_eq!(opportunity.source, OpportunitySource::FullScan);
+        assert_eq!(opportunity.hops.len(), 3);
+        assert!(opportunity.net_profit > 0);
+        assert_eq!(pool_versions.len(), 3);
+    }
+
+    #[test]
+    fn scan_skips_unprofitable_cycle() {
+        let snapshot = cycle_snapshot("SCAN_FLAT", 900_002, 1);
+        let report = scan_snapshot(&snapshot);
+
+        assert_eq!(report.chains_scanned, 1);
+        assert_eq!(report.profitable, 0);
+        assert!(report.missed_by_router.is_empty());
+    }
+
+    #[test]
+    fn scan_does_not_report_published_versions() {
+        let snapshot = cycle_snapshot("SCAN_PUBLISHED", 900_003, 2);
+        let (opportunity, pool_versions) = scan_snapshot(&snapshot).missed_by_router.remove(0);
+        RouterEngine::publish_chain_opportunity(opportunity, &pool_versions);
+
+        let report = scan_snapshot(&snapshot);
+        assert_eq!(report.profitable, 1);
+        assert!(report.missed_by_router.is_empty());
+    }
+
+    #[test]
+    fn scan_skips_chains_through_unstable_pools() {
+        let mut snapshot = cycle_snapshot("SCAN_UNSTABLE", 900_004, 2);
+        let pool = *snapshot.pool_versions.keys().next().unwrap();
+        snapshot.unstable_pools.insert(pool);
+
+        let report = scan_snapshot(&snapshot);
+        assert_eq!(report.chains_scanned, 1);
+        assert_eq!(report.profitable, 0);
+        assert!(report.missed_by_router.is_empty());
+    }
+}
@@ -0,0 +1,300 @@
+// src/compute_units.rs
+
+// Оценка лимита CU маршрута по истории исполнений.
+// Из логов симуляций и попавших в блок транзакций берется расход CU каждой
+// инструкции верхнего уровня ("Program <id> consumed N of M compute units" на глубине 1).
+// Инструкция свапа сопоставляется с хопом маршрута, замер сохраняется по ключу
+// (DEX, пересечения тиков / бинов) в скользящем окне CU_HISTORY_WINDOW.
+// Прогноз свапа - CU_PERCENTILE перцентиль окна с запасом CU_SAFETY_MARGIN_BPS;
+// пока замеров меньше CU_MIN_SAMPLES, используется статическая оценка программы пула (costs.rs).
+// Инструкция, упершаяся в лимит CU, дает только нижнюю границу расхода: она пишется
+// с надбавкой CU_BUDGET_EXCEEDED_BUMP_BPS и сразу поднимает прогноз своего ключа.
+
+use std::collections::VecDeque;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use solana_program::instruction::Instruction;
+use crate::config::{
+    CU_HISTORY_WINDOW, CU_MIN_SAMPLES, CU_PERCENTILE, CU_SAFETY_MARGIN_BPS, CU_MAX_CROSSINGS_BUCKET,
+    CU_BUDGET_EXCEEDED_BUMP_BPS,
+};
+use crate::costs::swap_compute_units;
+use crate::router::{ArbitrageOpportunity, SimulatedHop};
+use crate::websocket::ws_data::DexType;
+use crate::execution::simulation::pool_of_instruction;
+
+/// Ключ истории: DEX и корзина пересечений тиков / бинов
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CuKey {
+    pub dex: DexType,
+    pub crossings: u32,
+}
+
+impl CuKey {
+    pub fn new(dex: DexType, crossings: u32) -> Self {
+        Self { dex, crossings: crossings.min(CU_MAX_CROSSINGS_BUCKET) }
+    }
+
+    pub fn of_hop(hop: &SimulatedHop) -> Self {
+        Self::new(hop.dex, hop.crossings)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ComputeUnitHistory {
+    samples: DashMap<CuKey, VecDeque<u64>>,
+    // Прогноз с запасом, пересчитывается при каждом замере
+    predictions: DashMap<CuKey, u32>,
+}
+
+lazy_static! {
+    pub static ref COMPUTE_UNITS: ComputeUnitHistory = ComputeUnitHistory::default();
+}
+
+impl ComputeUnitHistory {
+    /// Замер расхода CU одной инструкции свапа
+    pub fn record(&self, key: CuKey, units: u64) {
+        let mut window = self.samples.entry(key).or_default();
+        if window.len() == CU_HISTORY_WINDOW {
+            window.pop_front();
+        }
+        window.push_back(units);
+
+        if window.len() >= CU_MIN_SAMPLES {
+            let mut sorted: Vec<u64> = window.iter().copied().collect();
+            sorted.sort_unstable();
+            let base = sorted[(sorted.len() - 1) * CU_PERCENTILE / 100];
+            let predicted = base + base * CU_SAFETY_MARGIN_BPS / 10_000;
+            self.predictions.insert(key, predicted.min(u32::MAX as u64) as u32);
+        }
+    }
+
+    /// Прогноз CU свапа; None, если замеров недостаточно
+    pub fn predict(&self, key: &CuKey) -> Option<u32> {
+        self.predictions.get(key).map(|p| *p)
+    }
+
+    /// Замеры по логам транзакции. `keys` - ключ каждой инструкции (None - не свап хопа),
+    /// `skip` - индекс инструкции, замер которой неполный (упавшая инструкция).
+    pub fn record_execution(&self, keys: &[Option<CuKey>], logs: &[String], skip: Option<usize>) {
+        for (i, units) in instruction_units(logs).into_iter().enumerate() {
+            if Some(i) == skip {
+                continue;
+            }
+            if let (Some(Some(key)), Some(units)) = (keys.get(i), units) {
+                self.record(*key, units);
+            }
+        }
+    }
+
+    /// Замеры транзакции, упавшей из-за нехватки CU на инструкции `failed`.
+    /// Ее расход - лишь нижняя граница: без надбавки замер тянул бы прогноз вниз
+    /// и следующая транзакция упала бы так же.
+    pub fn record_budget_exceeded(&self, keys: &[Option<CuKey>], logs: &[String], failed: Option<usize>) {
+        self.record_execution(keys, logs, failed);
+
+        let Some(i) = failed else { return };
+        let (Some(Some(key)), Some(Some(units))) = (keys.get(i), instruction_units(logs).get(i).copied()) else {
+            return;
+        };
+        let bumped = units + units * CU_BUDGET_EXCEEDED_BUMP_BPS / 10_000;
+        self.record(*key, bumped);
+
+        let bumped = bumped.min(u32::MAX as u64) as u32;
+        self.predictions
+            .entry(*key)
+            .and_modify(|p| *p = (*p).max(bumped))
+            .or_insert(bumped);
+    }
+}
+
+/// Прогноз CU свапа хопа: по истории или статическая оценка программы пула
+pub fn hop_compute_units(hop: &SimulatedHop) -> u32 {
+    COMPUTE_UNITS
+        .predict(&CuKey::of_hop(hop))
+        .unwrap_or_else(|| swap_compute_units(hop))
+}
+
+/// Ключи инструкций транзакции: свап, затрагивающий ровно один пул маршрута, - ключ его хопа
+pub fn instruction_keys(instructions: &[Instruction], opportunity: &ArbitrageOpportunity) -> Vec<Option<CuKey>> {
+    instructions
+        .iter()
+        .map(|ix| {
+            let pool = pool_of_instruction(ix, opportunity)?;
+            opportunity.hops.iter().find(|h| h.pool_address == pool).map(CuKey::of_hop)
+        })
+        .collect()
+}
+
+/// Расход CU каждой инструкции верхнего уровня по логам (None - программа не логирует расход,
+/// например встроенные ComputeBudget и System)
+pub fn instruction_units(logs: &[String]) -> Vec<Option<u64>> {
+    let mut units: Vec<Option<u64>> = Vec::new();
+    let mut depth = 0usize;
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else { continue };
+        // "Program log: ...", "Program data: ...", "Program return: ..." - вывод программы
+        let Some((program, event)) = rest.split_once(' ') else { continue };
+        if program.ends_with(':') {
+            continue;
+        }
+
+        if let Some(level) = event.strip_prefix("invoke [").and_then(|l| l.strip_suffix(']')) {
+            depth = level.parse().unwrap_or(depth + 1);
+            if depth == 1 {
+                units.push(None);
+            }
+        } else if let Some(consumed) = event.strip_prefix("consumed ") {
+            if depth == 1 {
+                if let (Some(last), Some(value)) = (units.last_mut(), consumed.split(' ').next()) {
+                    *last = value.parse().ok();
+                }
+            }
+        } else if event == "success" || event.starts_with("failed") {
+            depth = depth.saturating_sub(1);
+        }
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPUTE_BUDGET: &str = "ComputeBudget111111111111111111111111111111";
+    const TOKEN: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+    const WHIRLPOOL: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+    const CLMM: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
+
+    // Две инструкции ComputeBudget: встроенная программа расход не логирует
+    fn compute_budget_logs() -> Vec<String> {
+        vec![
+            format!("Program {} invoke [1]", COMPUTE_BUDGET),
+            format!("Program {} success", COMPUTE_BUDGET),
+            format!("Program {} invoke [1]", COMPUTE_BUDGET),
+            format!("Program {} success", COMPUTE_BUDGET),
+        ]
+    }
+
+    // Свап Whirlpool с двумя вложенными переводами токенов
+    fn whirlpool_swap_logs(consumed: u64, remaining: u64) -> Vec<String> {
+        vec![
+            format!("Program {} invoke [1]", WHIRLPOOL),
+            "Program log: Instruction: SwapV2".to_string(),
+            format!("Program {} invoke [2]", TOKEN),
+            "Program log: Instruction: TransferChecked".to_string(),
+            format!("Program {} consumed 6147 of 181502 compute units", TOKEN),
+            format!("Program {} success", TOKEN),
+            format!("Program {} invoke [2]", TOKEN),
+            "Program log: Instruction: TransferChecked".to_string(),
+            format!("Program {} consumed 6238 of 172318 compute units", TOKEN),
+            format!("Program {} success", TOKEN),
+            "Program data: 4fiq4bE3+0oBAAAAAAAAAA==".to_string(),
+            format!("Program {} consumed {} of {} compute units", WHIRLPOOL, consumed, remaining),
+            format!("Program {} success", WHIRLPOOL),
+        ]
+    }
+
+    fn orca_key() -> CuKey {
+        CuKey::new(DexType::Orca, 1)
+    }
+
+    fn clmm_key() -> CuKey {
+        CuKey::new(DexType::Raydium, 2)
+    }
+
+    #[test]
+    fn units_of_top_level_instructions_skip_nested_invokes() {
+        let mut lines = compute_budget_logs();
+        lines.extend(whirlpool_swap_logs(52_341, 199_700));
+        lines.extend(whirlpool_swap_logs(48_102, 147_359));
+
+        assert_eq!(instruction_units(&lines), vec![None, None, Some(52_341), Some(48_102)]);
+    }
+
+    #[test]
+    fn failed_instruction_is_not_recorded() {
+        let mut lines = compute_budget_logs();
+        lines.extend(whirlpool_swap_logs(52_341, 199_700));
+        lines.extend([
+            format!("Program {} invoke [1]", CLMM),
+            "Program log: Instruction: SwapV2".to_string(),
+            format!("Program {} invoke [2]", TOKEN),
+            "Program log: Instruction: TransferChecked".to_string(),
+            format!("Program {} consumed 6147 of 131025 compute units", TOKEN),
+            format!("Program {} success", TOKEN),
+            "Program log: AnchorError occurred. Error Code: TooLittleOutputReceived. Error Number: 6022. Error Message: Too little output received.".to_string(),
+            format!("Program {} consumed 28221 of 147359 compute units", CLMM),
+            format!("Program {} failed: custom program error: 0x1786", CLMM),
+        ]);
+
+        let units = instruction_units(&lines);
+        assert_eq!(units, vec![None, None, Some(52_341), Some(28_221)]);
+
+        let history = ComputeUnitHistory::default();
+        let keys = [None, None, Some(orca_key()), Some(clmm_key())];
+        history.record_execution(&keys, &lines, Some(3));
+
+        assert_eq!(history.samples.get(&orca_key()).unwrap().iter().copied().collect::<Vec<_>>(), vec![52_341]);
+        assert!(history.samples.get(&clmm_key()).is_none());
+    }
+
+    #[test]
+    fn budget_exceeded_raises_prediction_immediately() {
+        let mut lines = compute_budget_logs();
+        lines.extend(whirlpool_swap_logs(52_341, 199_700));
+        lines.extend([
+            format!("Program {} invoke [1]", CLMM),
+            "Program log: Instruction: SwapV2".to_string(),
+            format!("Program {} consumed 147359 of 147359 compute units", CLMM),
+            format!("Program {} failed: exceeded CUs meter at BPF instruction #2891", CLMM),
+        ]);
+
+        let history = ComputeUnitHistory::default();
+        let keys = [None, None, Some(orca_key()), Some(clmm_key())];
+        history.record_budget_exceeded(&keys, &lines, Some(3));
+
+        // 147_359 с надбавкой 50%, без ожидания CU_MIN_SAMPLES замеров
+        assert_eq!(history.predict(&clmm_key()), Some(221_038));
+        assert_eq!(history.predict(&orca_key()), None);
+        assert_eq!(history.samples.get(&orca_key()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn prediction_needs_min_samples_and_adds_margin() {
+        let history = ComputeUnitHistory::default();
+        let key = orca_key();
+
+        for i in 0..CU_MIN_SAMPLES as u64 - 1 {
+            history.record(key, 10_000 + i * 1_000);
+            assert_eq!(history.predict(&key), None);
+        }
+        history.record(key, 17_000);
+
+        // 95 перцентиль из 10_000..17_000 - 16_000, плюс 15%
+        assert_eq!(history.predict(&key), Some(18_400));
+    }
+
+    #[test]
+    fn prediction_follows_sliding_window() {
+        let history = ComputeUnitHistory::default();
+        let key = orca_key();
+
+        for _ in 0..CU_HISTORY_WINDOW {
+            history.record(key, 1_000_000);
+        }
+        for _ in 0..CU_HISTORY_WINDOW {
+            history.record(key, 10_000);
+        }
+
+        assert_eq!(history.samples.get(&key).unwrap().len(), CU_HISTORY_WINDOW);
+        assert_eq!(history.predict(&key), Some(11_500));
+    }
+
+    #[test]
+    fn crossings_above_bucket_share_key() {
+        assert_eq!(CuKey::new(DexType::Orca, CU_MAX_CROSSINGS_BUCKET + 5), CuKey::new(DexType::Orca, CU_MAX_CROSSINGS_BUCKET));
+        assert_ne!(CuKey::new(DexType::Orca, 1), CuKey::new(DexType::Meteora, 1));
+    }
+}
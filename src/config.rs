@@ -11,6 +11,7 @@ use log::{info, warn};
 use std::path::Path;
 use futures::future::join_all;
 use indicatif::{ProgressBar, ProgressStyle};
+use crate::websocket::ws_parser::PoolCommitment;
 
 // Статический HTTP клиент
 static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
@@ -29,6 +30,138 @@ pub const MAX_CHAIN_LENGTH: usize = 5;       // Максимальная дли
 pub const MIN_CHAIN_LENGTH: usize = 3;       // Минимальная длина цепочки включительно
 pub const START_END_TOKEN_FOR_CHAINS: [&str; 1] = ["SOL"]; // Начальный и конечный токен для построения цепочек
 
+// Константы для симуляции арбитража
+pub const SIMULATION_AMOUNT: u64 = 1_000_000_000; // Объем входа для симуляции цепочки 1 SOL в лампортах
+pub const MIN_PROFIT_LAMPORTS: i64 = 100_000;     // Минимальная абсолютная прибыль для публикации возможности
+pub const MIN_PROFIT_BPS: i64 = 5;                // Минимальная относительная прибыль в базисных пунктах
+pub const SIMULATION_QUEUE_SIZE: usize = 10_000;  // Размер очереди цепочек на симуляцию
+pub const OPPORTUNITY_CHANNEL_SIZE: usize = 1024; // Размер broadcast канала арбитражных возможностей
+pub const FULL_SCAN_INTERVAL_MS: u64 = 2_000;     // Период полного обхода всех цепочек
+pub const MAX_EXECUTION_BATCH_SIZE: usize = 4;    // Максимум непересекающихся возможностей на исполнение за слот
+pub const BOOK_SLOT_POLL_MS: u64 = 20;            // Период проверки смены слота книгой возможностей
+pub const EXECUTION_BATCH_QUEUE_SIZE: usize = 1;  // Пакетов в очереди исполнителя; более старый пакет вытесняется новым
+
+// Константы модели затрат на исполнение
+pub const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;             // Базовая комиссия за одну подпись
+pub const DEFAULT_CU_PRICE_MICRO_LAMPORTS: u64 = 10_000;   // Цена CU по умолчанию до первой оценки комиссий
+pub const DEFAULT_JITO_TIP_LAMPORTS: u64 = 10_000;         // Чаевые Jito по умолчанию
+pub const USE_JITO_BUNDLES: bool = true;                   // Отправка через бандлы Jito (учитываем чаевые)
+pub const ATA_RENT_LAMPORTS: u64 = 2_039_280;              // Рента за токен-аккаунт (165 байт)
+pub const COMPUTE_BUDGET_OVERHEAD_CU: u32 = 20_000;        // Compute budget, перевод чаевых и запас на транзакцию
+pub const ATA_CREATE_CU: u32 = 25_000;                     // Создание ATA (idempotent)
+pub const ORCA_SWAP_CU: u32 = 90_000;                      // Whirlpool swap_v2
+pub const RAYDIUM_V4_SWAP_CU: u32 = 50_000;                // Raydium AMM V4 swap_base_in
+pub const RAYDIUM_CLMM_SWAP_CU: u32 = 100_000;             // Raydium CLMM swap_v2
+pub const METEORA_SWAP_CU: u32 = 80_000;                   // Meteora DLMM swap
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;              // Лимит CU на транзакцию
+
+// Константы оценки CU по истории исполнений (compute_units.rs)
+pub const CU_HISTORY_WINDOW: usize = 256;                  // Последних замеров на (DEX, пересечения тиков)
+pub const CU_MIN_SAMPLES: usize = 8;                       // Меньше замеров - используем статическую оценку DEX
+pub const CU_PERCENTILE: usize = 95;                       // Перцентиль замеров для прогноза
+pub const CU_SAFETY_MARGIN_BPS: u64 = 1_500;               // Запас сверх прогноза
+pub const CU_BUDGET_EXCEEDED_BUMP_BPS: u64 = 5_000;        // Надбавка к расходу инструкции, упершейся в лимит CU
+pub const CU_MAX_CROSSINGS_BUCKET: u32 = 16;               // Пересечения сверх этого учитываются в одной корзине
+
+// Константы оценщика приоритетных комиссий (priority_fees.rs)
+pub const PRIORITY_FEE_POLL_MS: u64 = 2_000;               // Период опроса getRecentPrioritizationFees
+pub const PRIORITY_FEE_WINDOW_SLOTS: u64 = 150;            // Глубина скользящего окна комиссий по слотам
+pub const PRIORITY_FEE_PERCENTILE: usize = 75;             // Перцентиль комиссий пула для рекомендации цены CU
+pub const PRIORITY_FEE_POOL_TTL_SECS: u64 = 120;           // Пул без возможностей дольше этого перестаем опрашивать
+pub const PRIORITY_FEE_CONCURRENCY: usize = 8;             // Одновременных запросов комиссий
+pub const MAX_PRIORITY_FEE_PROFIT_BPS: u64 = 3_000;        // Приоритетная комиссия не больше этой доли прибыли
+
+// Константы сборки транзакций
+pub const SWAP_SLIPPAGE_BPS: u64 = 50;                     // Допустимое проскальзывание на каждом хопе
+pub const MAX_TRANSACTION_SIZE: usize = 1232;              // Лимит размера сериализованной транзакции (PACKET_DATA_SIZE)
+
+// Константы кеша blockhash (execution/blockhash.rs)
+pub const BLOCKHASH_REFRESH_MS: u64 = 2_000;               // Обновление blockhash по таймеру
+pub const BLOCKHASH_REFRESH_SLOTS: u64 = 4;                // Обновление после стольких новых слотов
+pub const BLOCKHASH_SLOT_POLL_MS: u64 = 50;                // Период проверки текущего слота
+pub const BLOCKHASH_MIN_REMAINING_BLOCKS: u64 = 40;        // Меньший запас действительности - транзакцию не собираем
+
+// Константы Jito block engine
+pub const DEFAULT_JITO_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf";
+pub const JITO_STATUS_POLL_MS: u64 = 1_000;                // Период опроса статусов бандлов
+pub const JITO_BUNDLE_DROP_SECS: u64 = 60;                 // Бандл без результата дольше этого считается потерянным
+pub const JITO_BUNDLE_RETENTION_SECS: u64 = 120;           // Сколько хранить бандл с итогом (трекер отправок читает итог до истечения транзакции)
+pub const JITO_TIP_ACCOUNTS_REFRESH_SECS: u64 = 600;       // Период обновления списка tip-аккаунтов
+
+// Константы рассылки транзакций по нескольким endpoint (execution/sender.rs)
+pub const FANOUT_RETRY_MS: u64 = 500;                      // Период повторной отправки до подтверждения или истечения
+pub const FANOUT_MAX_DURATION_SECS: u64 = 60;              // Предел повторов, если blockhash неизвестен кешу
+pub const FANOUT_INCLUDE_JITO: bool = true;                // Отправлять также в sendTransaction block engine
+
+// Константы предварительной симуляции
+pub const SIMULATION_FAILURE_LIMIT: u32 = 3;               // Ошибок симуляции подряд до временного отключения пула / маршрута
+pub const SIMULATION_FAILURE_COOLDOWN_SECS: u64 = 30;      // Время отключения пула / маршрута
+pub const SIMULATION_SLOT_ATTEMPTS: u32 = 3;                // Попыток прочитать баланс до симуляции на том же слоте, что и симуляция
+
+// Константы исполнения через локальный Jupiter API (/quote + /swap-instructions)
+pub const USE_JUPITER_FALLBACK: bool = true;               // Исполнять через Jupiter, если нативная сборка не удалась
+pub const JUPITER_CROSS_CHECK: bool = true;                // Сверять выход нашей модели с котировкой Jupiter
+pub const JUPITER_DEXES: &str = "Whirlpool,Raydium,Raydium CLMM,Meteora DLMM"; // Только DEX, которые мы моделируем
+pub const JUPITER_MAX_ACCOUNTS: usize = 48;                // Ограничение аккаунтов маршрута, чтобы уложиться в размер транзакции
+pub const JUPITER_DEVIATION_WARN_BPS: i64 = 100;           // Расхождение котировок Jupiter и нашей модели для предупреждения
+
+// Константы сканера круговых котировок Jupiter (quote.rs)
+pub const QUOTE_SCAN_INTERVAL_MS: u64 = 1_000;             // Период опроса круговых котировок
+pub const QUOTE_SCAN_AMOUNTS: [u64; 4] = [100_000_000, 500_000_000, 1_000_000_000, 5_000_000_000]; // Объемы входа в единицах стартового токена
+pub const QUOTE_SCAN_CONCURRENCY: usize = 4;               // Одновременных запросов к Jupiter
+pub const QUOTE_LATENCY_WINDOW: usize = 512;               // Последних запросов для перцентилей задержки
+pub const QUOTE_REPORT_INTERVAL_SECS: u64 = 60;            // Период отчета о задержке и точности котировщиков
+
+// Константы кошелька
+pub const NATIVE_SOL_RESERVE_LAMPORTS: u64 = 50_000_000;   // SOL вне wSOL на комиссии, чаевые и ренту ATA
+pub const MIN_WRAP_LAMPORTS: u64 = 10_000_000;             // Меньшие излишки SOL не оборачиваем
+pub const WALLET_REBALANCE_INTERVAL_SECS: u64 = 30;        // Период проверки баланса SOL / wSOL
+pub const WALLET_RECONNECT_DELAY_SECS: u64 = 5;            // Пауза перед переподключением подписок кошелька
+
+// Константы отслеживания отправленных транзакций и журнала сделок
+pub const LANDING_POLL_MS: u64 = 1_000;                    // Период опроса статусов отправленных транзакций
+pub const LANDING_EXPIRY_SECS: u64 = 90;                   // Транзакция без статуса дольше этого истекла (blockhash ~150 слотов)
+pub const LEDGER_FILE: &str = "trade_ledger.jsonl";        // Журнал сделок, по записи JSON в строке
+pub const LEDGER_RETENTION_SECS: u64 = 86_400;             // Сколько записей держим в памяти для сводок
+pub const LEDGER_REPORT_INTERVAL_SECS: u64 = 300;          // Период вывода сводки PnL
+
+// Константы риск-лимитов исполнителя (execution/risk.rs)
+pub const MAX_TRADE_NOTIONAL: u64 = 10_000_000_000;        // Максимальный вход сделки в единицах стартового токена (10 SOL)
+pub const MAX_TRADES_PER_MINUTE: usize = 30;               // Отправок за скользящую минуту
+pub const DAILY_LOSS_LIMIT: i64 = 1_000_000_000;           // Фактический убыток за сутки, после которого исполнение останавливается (1 SOL)
+pub const MAX_CONSECUTIVE_SEND_FAILURES: u32 = 5;          // Неудачных отправок подряд до остановки исполнения
+pub const MAX_CONSECUTIVE_EXPIRIES: u32 = 50;              // Истекших (не попавших в блок) транзакций подряд до остановки
+pub const RISK_POOL_COOLDOWN_SECS: u64 = 60;               // Пауза отправок через пул после неудачи
+pub const KILL_SWITCH_FILE: &str = "KILL_SWITCH";          // Наличие файла останавливает исполнение
+pub const KILL_SWITCH_POLL_SECS: u64 = 1;                  // Период проверки файла kill switch
+
+// Константы режима paper trading (PAPER_TRADING в .env)
+pub const PAPER_LEDGER_FILE: &str = "paper_ledger.jsonl";  // Журнал сделок в режиме paper trading
+pub const PAPER_MARK_POLL_MS: u64 = 200;                   // Период проверки обновлений пулов неоцененных сделок
+pub const PAPER_MARK_TIMEOUT_SECS: u64 = 10;               // Без обновлений пулов дольше этого оцениваем по текущему состоянию
+
+// Константы переподключения и контроля WebSocket (websocket/ws_runner.rs)
+pub const WS_RECONNECT_BASE_MS: u64 = 500;                 // Первая пауза перед переподключением
+pub const WS_RECONNECT_MAX_MS: u64 = 30_000;               // Предел экспоненциальной паузы
+pub const WS_RECONNECT_RESET_SECS: u64 = 60;               // Соединение живет дольше - пауза сбрасывается к начальной
+pub const WS_BACKFILL_RETRIES: u32 = 3;                    // Попыток дозагрузки пулов после переподключения
+pub const WS_PING_INTERVAL_SECS: u64 = 10;                 // Период ping; без pong до следующего ping поток считается мертвым
+pub const WS_SLOT_SILENCE_SECS: u64 = 5;                   // Слоты идут каждые ~400 мс: дольше без уведомлений - поток устарел
+pub const WS_PROGRAM_SILENCE_SECS: u64 = 30;               // То же для подписок на программы DEX
+pub const WS_UNSUBSCRIBE_TIMEOUT_MS: u64 = 2000;           // Ожидание подтверждений unsubscribe при остановке
+pub const WS_SUBSCRIBE_CONFIRM_SECS: u64 = 10;             // Подписка без подтверждения дольше - соединение переподключается
+pub const WS_ACCOUNT_SUBSCRIBE_MAX_POOLS: usize = 50;      // Пулов DEX не больше - accountSubscribe на каждый вместо programSubscribe
+
+// Уровни commitment состояний пулов (COMMITMENT_TIERS)
+pub const DEFAULT_COMMITMENT_TIERS: &str = "processed,confirmed,finalized";
+
+// Константы address lookup tables
+pub const LOOKUP_TABLES_FILE: &str = "lookup_tables.json"; // Адреса созданных таблиц
+pub const LOOKUP_TABLE_SYNC_INTERVAL_SECS: u64 = 60;       // Период синхронизации таблиц с dex_pools
+pub const LOOKUP_TABLE_EXTEND_BATCH: usize = 30;           // Адресов в одной транзакции extend
+pub const MAX_LOOKUP_TABLES: usize = 32;                   // Предел числа собственных таблиц (рента)
+pub const MAX_ROUTE_LOOKUP_TABLES: usize = 4;              // Таблиц в одной транзакции маршрута
+
 // Добавим константы для URL скачивания пулов   
 pub const METEORA_POOLS_URL: &str = "https://dlmm-api.meteora.ag/pair/all";
 pub const ORCA_POOLS_URL: &str = "https://api.mainnet.orca.so/v1/whirlpool/list";
@@ -40,6 +173,12 @@ pub const ORCA_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
 pub const METEORA_PROGRAM_ID: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
 pub const RAYDIUM_CLMM_PROGRAM_ID: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
 pub const RAYDIUM_V4_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+pub const RAYDIUM_V4_AUTHORITY: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
+pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+pub const MEMO_PROGRAM_ID: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
 
 // Добавим структуру для описания пула
 struct PoolFile {
@@ -74,6 +213,9 @@ pub struct Config {
     pub helius_yellowstone_auth_token: String,
     pub dest_ip_ports: String,
     pub jito_udp_port: String,
+    pub jito_block_engine_url: String,
+    pub paper_trading: bool,
+    pub commitment_tiers: Vec<PoolCommitment>,
 }
 
 // Глобальная конфигурация
@@ -106,10 +248,39 @@ lazy_static! {
                 .expect("DEST_IP_PORTS must be set"),
             jito_udp_port: env::var("JITO_UDP_PORT")
                 .expect("JITO_UDP_PORT must be set"),
+            // Необязательный: для тестов можно указать локальный mock-сервер
+            jito_block_engine_url: env::var("JITO_BLOCK_ENGINE_URL")
+                .unwrap_or_else(|_| DEFAULT_JITO_BLOCK_ENGINE_URL.to_string()),
+            // Необязательный: PAPER_TRADING=true - полный конвейер без отправки транзакций
+            paper_trading: env::var("PAPER_TRADING")
+                .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            // Необязательный: активные уровни commitment через запятую, например "processed,confirmed"
+            commitment_tiers: parse_commitment_tiers(
+                &env::var("COMMITMENT_TIERS").unwrap_or_else(|_| DEFAULT_COMMITMENT_TIERS.to_string())
+            ),
         }
     };
 }
 
+// Разбор COMMITMENT_TIERS. Processed обязателен: по нему котируются и исполняются сделки
+fn parse_commitment_tiers(value: &str) -> Vec<PoolCommitment> {
+    let tiers: Vec<PoolCommitment> = value
+        .split(',')
+        .map(|tier| tier.trim().to_lowercase())
+        .filter(|tier| !tier.is_empty())
+        .map(|tier| PoolCommitment::from_param(&tier)
+            .unwrap_or_else(|| panic!("COMMITMENT_TIERS: unknown commitment '{}'", tier)))
+        .collect();
+
+    if !tiers.contains(&PoolCommitment::Processed) {
+        panic!("COMMITMENT_TIERS must include processed");
+    }
+
+    // Порядок от processed к finalized, без повторов
+    PoolCommitment::ALL.into_iter().filter(|c| tiers.contains(c)).collect()
+}
+
 pub fn initialize_http_client() -> &'static Client {
     HTTP_CLIENT.get_or_init(|| {
         let mut headers = header::HeaderMap::new();
@@ -0,0 +1,301 @@
+// src/costs.rs
+
+// Модель затрат на исполнение арбитражной возможности.
+// Валовая прибыль (выход минус вход) не учитывает:
+// - комиссию за подпись транзакции;
+// - приоритетную комиссию (цена CU * лимит CU);
+// - чаевые Jito при отправке бандлом;
+// - ренту за создание отсутствующих ATA для промежуточных токенов.
+// Все суммы в лампортах: цепочки начинаются и заканчиваются в SOL (START_END_TOKEN_FOR_CHAINS).
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use dashmap::DashSet;
+use lazy_static::lazy_static;
+use solana_program::pubkey::Pubkey;
+use crate::router::SimulatedHop;
+use crate::priority_fees::PRIORITY_FEES;
+use crate::compute_units::hop_compute_units;
+use crate::websocket::ws_data::DexType;
+use crate::execution::pool_keys::{cached_pool_keys, PoolKeys};
+use crate::config::{
+    INITIAL_TOKENS,
+    SIGNATURE_FEE_LAMPORTS,
+    DEFAULT_CU_PRICE_MICRO_LAMPORTS,
+    DEFAULT_JITO_TIP_LAMPORTS,
+    USE_JITO_BUNDLES,
+    ATA_RENT_LAMPORTS,
+    COMPUTE_BUDGET_OVERHEAD_CU,
+    ATA_CREATE_CU,
+    ORCA_SWAP_CU,
+    RAYDIUM_V4_SWAP_CU,
+    RAYDIUM_CLMM_SWAP_CU,
+    METEORA_SWAP_CU,
+    MAX_COMPUTE_UNITS,
+};
+
+/// Оценка затрат на исполнение одной возможности
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionCosts {
+    pub signature_fee: u64,
+    pub priority_fee: u64,
+    pub jito_tip: u64,
+    pub ata_rent: u64,
+    /// Лимит CU, под который посчитана приоритетная комиссия
+    pub compute_units: u32,
+    /// Цена CU в микролампортах
+    pub cu_price_micro_lamports: u64,
+    /// Количество ATA, которые придется создать
+    pub missing_token_accounts: usize,
+}
+
+impl ExecutionCosts {
+    pub fn total(&self) -> u64 {
+        self.signature_fee
+            .saturating_add(self.priority_fee)
+            .saturating_add(self.jito_tip)
+            .saturating_add(self.ata_rent)
+    }
+}
+
+/// Текущие уровни комиссий. Обновляются оценщиком комиссий и клиентом Jito.
+#[derive(Debug)]
+pub struct FeeLevels {
+    cu_price_micro_lamports: AtomicU64,
+    jito_tip_lamports: AtomicU64,
+    use_jito: AtomicBool,
+}
+
+impl Default for FeeLevels {
+    fn default() -> Self {
+        Self {
+            cu_price_micro_lamports: AtomicU64::new(DEFAULT_CU_PRICE_MICRO_LAMPORTS),
+            jito_tip_lamports: AtomicU64::new(DEFAULT_JITO_TIP_LAMPORTS),
+            use_jito: AtomicBool::new(USE_JITO_BUNDLES),
+        }
+    }
+}
+
+impl FeeLevels {
+    pub fn cu_price(&self) -> u64 {
+        self.cu_price_micro_lamports.load(Ordering::Relaxed)
+    }
+
+    pub fn set_cu_price(&self, micro_lamports: u64) {
+        self.cu_price_micro_lamports.store(micro_lamports, Ordering::Relaxed);
+    }
+
+    pub fn jito_tip(&self) -> u64 {
+        if self.use_jito.load(Ordering::Relaxed) {
+            self.jito_tip_lamports.load(Ordering::Relaxed)
+        } else {
+            0
+        }
+    }
+}
+
+lazy_static! {
+    pub static ref FEE_LEVELS: FeeLevels = FeeLevels::default();
+
+    // Минты, для которых у кошелька уже есть ATA
+    static ref TOKEN_ACCOUNTS: DashSet<Pubkey> = DashSet::new();
+    // Список ATA загружен из кошелька; до этого считаем, что ATA есть только для INITIAL_TOKENS
+    static ref TOKEN_ACCOUNTS_LOADED: AtomicBool = AtomicBool::new(false);
+}
+
+/// Регистрирует существующие ATA кошелька (вызывается менеджером кошелька).
+pub fn set_token_accounts(mints: impl IntoIterator<Item = Pubkey>) {
+    TOKEN_ACCOUNTS.clear();
+    for mint in mints {
+        TOKEN_ACCOUNTS.insert(mint);
+    }
+    TOKEN_ACCOUNTS_LOADED.store(true, Ordering::Relaxed);
+}
+
+/// Отмечает, что ATA для минта создан
+pub fn mark_token_account(mint: Pubkey) {
+    TOKEN_ACCOUNTS.insert(mint);
+}
+
+/// Есть ли у кошелька ATA для минта
+pub fn has_token_account(mint: &Pubkey, symbol: &str) -> bool {
+    if TOKEN_ACCOUNTS_LOADED.load(Ordering::Relaxed) {
+        TOKEN_ACCOUNTS.contains(mint)
+    } else {
+        INITIAL_TOKENS.contains(&symbol)
+    }
+}
+
+/// Статическая оценка CU свапа хопа по программе пула.
+/// Программу Raydium (AMM V4 / CLMM) знают разрешенные ключи пула; пока их нет,
+/// берем оценку CLMM как более дорогую.
+pub fn swap_compute_units(hop: &SimulatedHop) -> u32 {
+    match hop.dex {
+        DexType::Orca => ORCA_SWAP_CU,
+        DexType::Raydium => match cached_pool_keys(&hop.pool_address) {
+            Some(PoolKeys::RaydiumAmm(_)) => RAYDIUM_V4_SWAP_CU,
+            _ => RAYDIUM_CLMM_SWAP_CU,
+        },
+        DexType::Meteora => METEORA_SWAP_CU,
+    }
+}
+
+/// Количество ATA, которых не хватает для промежуточных токенов маршрута
+pub fn missing_token_accounts(hops: &[SimulatedHop]) -> usize {
+    let mut missing: Vec<Pubkey> = Vec::new();
+    for hop in hops {
+        if !has_token_account(&hop.output_mint, &hop.to_token) && !missing.contains(&hop.output_mint) {
+            missing.push(hop.output_mint);
+        }
+    }
+    missing.len()
+}
+
+/// Оценка лимита CU для маршрута: свапы по истории исполнений (compute_units.rs)
+pub fn estimate_compute_units(hops: &[SimulatedHop], missing_accounts: usize) -> u32 {
+    let swaps: u32 = hops.iter().map(hop_compute_units).sum();
+    let total = COMPUTE_BUDGET_OVERHEAD_CU
+        .saturating_add(swaps)
+        .saturating_add(ATA_CREATE_CU.saturating_mul(missing_accounts as u32));
+    total.min(MAX_COMPUTE_UNITS)
+}
+
+/// Оценка затрат на исполнение маршрута при текущих уровнях комиссий.
+/// `expected_profit` - валовая прибыль маршрута: цена CU подбирается оценщиком
+/// комиссий (priority_fees.rs) так, чтобы приоритетная комиссия не превышала
+/// допустимую долю прибыли.
+pub fn estimate_costs(hops: &[SimulatedHop], expected_profit: i64) -> ExecutionCosts {
+    let missing = missing_token_accounts(hops);
+    let compute_units = estimate_compute_units(hops, missing);
+    let jito_tip = FEE_LEVELS.jito_tip();
+    let ata_rent = ATA_RENT_LAMPORTS.saturating_mul(missing as u64);
+
+    let pools: Vec<Pubkey> = hops.iter().map(|h| h.pool_address).collect();
+    let profit_before_priority = expected_profit
+        .saturating_sub((SIGNATURE_FEE_LAMPORTS + jito_tip + ata_rent) as i64);
+    let cu_price = PRIORITY_FEES.recommend_cu_price(&pools, compute_units, profit_before_priority);
+
+    // Приоритетная комиссия: микролампорты за CU, округляем вверх до лампорта
+    let priority_fee = (compute_units as u128 * cu_price as u128).div_ceil(1_000_000) as u64;
+
+    ExecutionCosts {
+        signature_fee: SIGNATURE_FEE_LAMPORTS,
+        priority_fee,
+        jito_tip,
+        ata_rent,
+        compute_units,
+        cu_price_micro_lamports: cu_price,
+        missing_token_accounts: missing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::fixtures::{amm_v4_keys, clmm_keys};
+    use crate::execution::ix_raydium::{AmmV4Keys, ClmmKeys};
+    use crate::execution::pool_keys::POOL_KEYS;
+
+    fn hop(dex: DexType, pool_address: Pubkey, to_token: &str, output_mint: Pubkey) -> SimulatedHop {
+        SimulatedHop {
+            dex,
+            pool_address,
+            from_token: String::new(),
+            to_token: to_token.to_string(),
+            input_mint: Pubkey::default(),
+            output_mint,
+            amount_in: 1_000_000_000,
+            amount_out: 1_000_000_000,
+            pool_slot: 0,
+            crossings: 0,
+        }
+    }
+
+    // Пул Raydium с ключами программы в кеше
+    fn cached_raydium_pool(keys: impl FnOnce(Pubkey) -> PoolKeys) -> Pubkey {
+        let pool = Pubkey::new_unique();
+        POOL_KEYS.insert(pool, keys(pool));
+        pool
+    }
+
+    // Цикл SOL -> USDC -> SOL: ATA для INITIAL_TOKENS считаются существующими
+    fn sol_usdc_cycle(dex: DexType, first_pool: Pubkey) -> Vec<SimulatedHop> {
+        vec![
+            hop(dex, first_pool, "USDC", Pubkey::new_unique()),
+            hop(DexType::Orca, Pubkey::new_unique(), "SOL", Pubkey::new_unique()),
+        ]
+    }
+
+    #[test]
+    fn swap_units_by_pool_program() {
+        let orca = hop(DexType::Orca, Pubkey::new_unique(), "USDC", Pubkey::new_unique());
+        assert_eq!(swap_compute_units(&orca), ORCA_SWAP_CU);
+        let meteora = hop(DexType::Meteora, Pubkey::new_unique(), "USDC", Pubkey::new_unique());
+        assert_eq!(swap_compute_units(&meteora), METEORA_SWAP_CU);
+
+        let amm_v4 = cached_raydium_pool(|pool| PoolKeys::RaydiumAmm(AmmV4Keys { pool, ..amm_v4_keys() }));
+        assert_eq!(swap_compute_units(&hop(DexType::Raydium, amm_v4, "USDC", Pubkey::new_unique())), RAYDIUM_V4_SWAP_CU);
+        let clmm = cached_raydium_pool(|pool| PoolKeys::RaydiumClmm(ClmmKeys { pool, ..clmm_keys() }));
+        assert_eq!(swap_compute_units(&hop(DexType::Raydium, clmm, "USDC", Pubkey::new_unique())), RAYDIUM_CLMM_SWAP_CU);
+
+        // Ключи не разрешены: программа неизвестна, берем более дорогую CLMM
+        let unknown = hop(DexType::Raydium, Pubkey::new_unique(), "USDC", Pubkey::new_unique());
+        assert_eq!(swap_compute_units(&unknown), RAYDIUM_CLMM_SWAP_CU);
+    }
+
+    #[test]
+    fn costs_components_without_missing_accounts() {
+        let hops = sol_usdc_cycle(DexType::Orca, Pubkey::new_unique());
+        let costs = estimate_costs(&hops, 1_000_000_000);
+
+        let compute_units = COMPUTE_BUDGET_OVERHEAD_CU + 2 * ORCA_SWAP_CU;
+        assert_eq!(costs.compute_units, compute_units);
+        assert_eq!(costs.signature_fee, SIGNATURE_FEE_LAMPORTS);
+        assert_eq!(costs.jito_tip, DEFAULT_JITO_TIP_LAMPORTS);
+        assert_eq!((costs.ata_rent, costs.missing_token_accounts), (0, 0));
+        assert_eq!(costs.cu_price_micro_lamports, DEFAULT_CU_PRICE_MICRO_LAMPORTS);
+        assert_eq!(costs.priority_fee, compute_units as u64 * DEFAULT_CU_PRICE_MICRO_LAMPORTS / 1_000_000);
+        assert_eq!(costs.total(), costs.signature_fee + costs.priority_fee + costs.jito_tip);
+    }
+
+    #[test]
+    fn costs_include_rent_and_units_for_missing_accounts() {
+        // Оба хопа выходят в один и тот же неизвестный токен: ATA создается один раз
+        let mint = Pubkey::new_unique();
+        let hops = vec![
+            hop(DexType::Orca, Pubkey::new_unique(), "COSTS_TEST_MISSING", mint),
+            hop(DexType::Orca, Pubkey::new_unique(), "COSTS_TEST_MISSING", mint),
+        ];
+        let costs = estimate_costs(&hops, 1_000_000_000);
+
+        assert_eq!(costs.missing_token_accounts, 1);
+        assert_eq!(costs.ata_rent, ATA_RENT_LAMPORTS);
+        assert_eq!(costs.compute_units, COMPUTE_BUDGET_OVERHEAD_CU + 2 * ORCA_SWAP_CU + ATA_CREATE_CU);
+    }
+
+    #[test]
+    fn priority_fee_is_capped_by_profit() {
+        let hops = sol_usdc_cycle(DexType::Orca, Pubkey::new_unique());
+        // После подписи и чаевых остается 5_000 лампортов, приоритетной комиссии - не больше 30%
+        let expected_profit = (SIGNATURE_FEE_LAMPORTS + DEFAULT_JITO_TIP_LAMPORTS + 5_000) as i64;
+        let costs = estimate_costs(&hops, expected_profit);
+
+        assert_eq!(costs.priority_fee, 1_500);
+        assert!(costs.cu_price_micro_lamports < DEFAULT_CU_PRICE_MICRO_LAMPORTS);
+
+        // Убыточный маршрут не платит приоритетную комиссию
+        assert_eq!(estimate_costs(&hops, 0).priority_fee, 0);
+    }
+
+    #[test]
+    fn raydium_units_follow_pool_program() {
+        let amm_v4 = cached_raydium_pool(|pool| PoolKeys::RaydiumAmm(AmmV4Keys { pool, ..amm_v4_keys() }));
+        let clmm = cached_raydium_pool(|pool| PoolKeys::RaydiumClmm(ClmmKeys { pool, ..clmm_keys() }));
+
+        let v4_costs = estimate_costs(&sol_usdc_cycle(DexType::Raydium, amm_v4), 1_000_000_000);
+        let clmm_costs = estimate_costs(&sol_usdc_cycle(DexType::Raydium, clmm), 1_000_000_000);
+
+        assert_eq!(v4_costs.compute_units, COMPUTE_BUDGET_OVERHEAD_CU + RAYDIUM_V4_SWAP_CU + ORCA_SWAP_CU);
+        assert_eq!(clmm_costs.compute_units, COMPUTE_BUDGET_OVERHEAD_CU + RAYDIUM_CLMM_SWAP_CU + ORCA_SWAP_CU);
+        assert!(v4_costs.priority_fee < clmm_costs.priority_fee);
+    }
+}
@@ -8,7 +8,7 @@ use log::{debug, info, warn, error};
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::sync::Arc;
 use crate::websocket::ws_data::{DexType, SlotInfo};
-use crate::websocket::ws_parser::PoolData;
+use crate::websocket::ws_parser::{PoolData, PoolCommitment};
 use crate::decoder::{WhirlpoolData, RaydiumData, MeteoraData};
 use bitvec::prelude::*;
 use hashbrown::HashMap;
@@ -17,7 +17,9 @@ use petgraph::Graph;
 use crate::graph::PoolEdge;
 use crate::math::calculators;
 use crate::math::weight_calculators::{calculate_orca_weight, calculate_raydium_weight, calculate_meteora_weight};
-use crate::router::RouterEngine; 
+use crate::router::{RouterEngine, ChainResult}; 
+use crate::opportunity_book::OPPORTUNITY_BOOK;
+use crate::config::CONFIG;
 
 // Структура для хранения информации о токене
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
@@ -195,6 +197,102 @@ impl PoolStateBase {
     }
 }
 
+// Обновление ребра пула в графе своего commitment по новому состоянию,
+// фиксация новой версии пула и пересчет затронутых цепочек.
+// Каждый уровень commitment меняет только свой граф.
+fn update_pool_edges(commitment: PoolCommitment, base: &PoolStateBase, slot: u64) {
+    let pool_address = base.get_address();
+    if let Some(mut g) = GLOBAL_DATA.graph_for(commitment).get_mut("main") {
+        if let Some(edge_idx) = g.edge_indices()
+            .find(|&e| g[e].pool_address == pool_address) {
+            
+            let edge = &mut g[edge_idx];
+            
+            // Обновляем метрики в зависимости от типа DEX
+            match base {
+                PoolStateBase::Orca(state) => {
+                    let price = calculators::calculate_orca_price(state.sqrt_price);
+                    let fee_rate = state.fee_rate as f64 / 10_000.0;
+                    let liquidity = state.liquidity as f64;
+                    let weight = calculate_orca_weight(
+                        price,
+                        fee_rate,
+                        liquidity,
+                        state.tick_spacing
+                    );
+
+                    edge.update_metrics(
+                        price,
+                        fee_rate,
+                        liquidity,
+                        weight,
+                        state.is_active,
+                        slot
+                    );
+
+                    debug!("Обновлены метрики для Orca пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
+                        pool_address, price, fee_rate, liquidity, weight);
+                },
+                PoolStateBase::Raydium(state) => {
+                    let price = calculators::calculate_raydium_price(state.min_price, state.max_price);
+                    let fee_rate = calculators::calculate_raydium_fee(
+                        state.fee_numerator,
+                        state.fee_denominator
+                    );
+                    let liquidity = state.total_lp as f64;
+                    let weight = calculate_raydium_weight(
+                        price,
+                        fee_rate,
+                        liquidity,
+                        state.orders_num,
+                        state.depth
+                    );
+
+                    edge.update_metrics(
+                        price,
+                        fee_rate,
+                        liquidity,
+                        weight,
+                        state.status != 0 && state.pool_state != 0,
+                        slot
+                    );
+
+                    debug!("Обновлены метрики для Raydium пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
+                        pool_address, price, fee_rate, liquidity, weight);
+                },
+                PoolStateBase::Meteora(state) => {
+                    let price = calculators::calculate_meteora_price(state.sqrt_price);
+                    let fee_rate = state.fee_rate as f64 / 10_000.0;
+                    let liquidity = state.liquidity as f64;
+                    let weight = calculate_meteora_weight(
+                        price,
+                        fee_rate,
+                        liquidity,
+                        state.liquidity_multiplier
+                    );
+
+                    edge.update_metrics(
+                        price,
+                        fee_rate,
+                        liquidity,
+                        weight,
+                        state.dynamic_liquidity_mode != 0 && state.liquidity_cap > 0,
+                        slot
+                    );
+
+                    debug!("Обновлены метрики для Meteora пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
+                        pool_address, price, fee_rate, liquidity, weight);
+                }
+            }
+        }
+    }
+    // Фиксируем новую версию состояния пула для кеша цепочек
+    GLOBAL_DATA.bump_pool_version(commitment, pool_address);
+
+    // Пересчитываем цепочки этого commitment, использующие пул
+    RouterEngine::update_affected_chains(pool_address, commitment);
+}
+
 // Состояние для Processed данных
 #[derive(Debug, Clone)]
 pub struct ProcessedPoolState {
@@ -208,99 +306,57 @@ impl ProcessedPoolState {
         let mut updated = false;
         let pool_address = self.base.get_address();
 
+        // Уведомление старее сохраненного состояния (например, пришедшее из очереди
+        // после backfill) не должно его откатывать
+        if slot < self.processed_slot {
+            return false;
+        }
+
         // 1. Обновляем базовое состояние
         if self.base.update(pool_data) {
             self.processed_slot = slot;
             self.last_update_time = unix_timestamp();
             updated = true;
 
-            // 2. Обновляем ребра в обоих графах
-            for graph in [&GLOBAL_DATA.processed_graph, &GLOBAL_DATA.finalized_graph] {
-                if let Some(mut g) = graph.get_mut("main") {
-                    if let Some(edge_idx) = g.edge_indices()
-                        .find(|&e| g[e].pool_address == pool_address) {
-                        
-                        let edge = &mut g[edge_idx];
-                        
-                        // Обновляем метрики в зависимости от типа DEX
-                        match &self.base {
-                            PoolStateBase::Orca(state) => {
-                                let price = calculators::calculate_orca_price(state.sqrt_price);
-                                let fee_rate = state.fee_rate as f64 / 10_000.0;
-                                let liquidity = state.liquidity as f64;
-                                let weight = calculate_orca_weight(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    state.tick_spacing
-                                );
-
-                                edge.update_metrics(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    weight,
-                                    state.is_active,
-                                    slot
-                                );
-
-                                debug!("Обновлены метрики для Orca пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
-                                    pool_address, price, fee_rate, liquidity, weight);
-                            },
-                            PoolStateBase::Raydium(state) => {
-                                let price = calculators::calculate_raydium_price(state.min_price, state.max_price);
-                                let fee_rate = calculators::calculate_raydium_fee(
-                                    state.fee_numerator,
-                                    state.fee_denominator
-                                );
-                                let liquidity = state.total_lp as f64;
-                                let weight = calculate_raydium_weight(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    state.orders_num,
-                                    state.depth
-                                );
-
-                                edge.update_metrics(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    weight,
-                                    state.status != 0 && state.pool_state != 0,
-                                    slot
-                                );
-
-                                debug!("Обновлены метрики для Raydium пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
-                                    pool_address, price, fee_rate, liquidity, weight);
-                            },
-                            PoolStateBase::Meteora(state) => {
-                                let price = calculators::calculate_meteora_price(state.sqrt_price);
-                                let fee_rate = state.fee_rate as f64 / 10_000.0;
-                                let liquidity = state.liquidity as f64;
-                                let weight = calculate_meteora_weight(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    state.liquidity_multiplier
-                                );
-
-                                edge.update_metrics(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    weight,
-                                    state.dynamic_liquidity_mode != 0 && state.liquidity_cap > 0,
-                                    slot
-                                );
-
-                                debug!("Обновлены метрики для Meteora пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
-                                    pool_address, price, fee_rate, liquidity, weight);
-                            }
-                        }
-                    }
-                }
-            }
+            // 2. Обновляем ребро в графе processed
+            update_pool_edges(PoolCommitment::Processed, &self.base, slot);
+
+            // Снимаем из книги возможности, посчитанные на прежнем слоте пула
+            OPPORTUNITY_BOOK.on_pool_update(PoolCommitment::Processed, pool_address, slot);
+        }
+
+        updated
+    }
+}
+
+// Состояние для Confirmed данных
+#[derive(Debug, Clone)]
+pub struct ConfirmedPoolState {
+    pub base: PoolStateBase,
+    pub confirmed_slot: u64,
+    pub last_update_time: u64,
+}
+
+impl ConfirmedPoolState {
+    pub fn update(&mut self, pool_data: &PoolData, slot: u64) -> bool {
+        let mut updated = false;
+        let pool_address = self.base.get_address();
+
+        // Более старый слот не откатывает состояние
+        if slot < self.confirmed_slot {
+            return false;
+        }
+
+        if self.base.update(pool_data) {
+            self.confirmed_slot = slot;
+            self.last_update_time = unix_timestamp();
+            updated = true;
+
+            // Обновляем ребра в графах
+            update_pool_edges(PoolCommitment::Confirmed, &self.base, slot);
+
+            // Снимаем из книги возможности, посчитанные на прежнем слоте пула
+            OPPORTUNITY_BOOK.on_pool_update(PoolCommitment::Confirmed, pool_address, slot);
         }
 
         updated
@@ -320,98 +376,21 @@ impl FinalizedPoolState {
         let mut updated = false;
         let pool_address = self.base.get_address();
 
+        // Более старый слот не откатывает состояние
+        if slot < self.finalized_slot {
+            return false;
+        }
+
         if self.base.update(pool_data) {
             self.finalized_slot = slot;
             self.last_update_time = unix_timestamp();
             updated = true;
 
-            // Обновляем ребра в обоих графах
-            for graph in [&GLOBAL_DATA.processed_graph, &GLOBAL_DATA.finalized_graph] {
-                if let Some(mut g) = graph.get_mut("main") {
-                    if let Some(edge_idx) = g.edge_indices()
-                        .find(|&e| g[e].pool_address == pool_address) {
-                        
-                        let edge = &mut g[edge_idx];
-                        
-                        // Обновляем метрики в зависимости от типа DEX
-                        match &self.base {
-                            PoolStateBase::Orca(state) => {
-                                let price = calculators::calculate_orca_price(state.sqrt_price);
-                                let fee_rate = state.fee_rate as f64 / 10_000.0;
-                                let liquidity = state.liquidity as f64;
-                                let weight = calculate_orca_weight(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    state.tick_spacing
-                                );
-
-                                edge.update_metrics(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    weight,
-                                    state.is_active,
-                                    slot
-                                );
-
-                                debug!("Обновлены метрики для Orca пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
-                                    pool_address, price, fee_rate, liquidity, weight);
-                            },
-                            PoolStateBase::Raydium(state) => {
-                                let price = calculators::calculate_raydium_price(state.min_price, state.max_price);
-                                let fee_rate = calculators::calculate_raydium_fee(
-                                    state.fee_numerator,
-                                    state.fee_denominator
-                                );
-                                let liquidity = state.total_lp as f64;
-                                let weight = calculate_raydium_weight(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    state.orders_num,
-                                    state.depth
-                                );
-
-                                edge.update_metrics(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    weight,
-                                    state.status != 0 && state.pool_state != 0,
-                                    slot
-                                );
-
-                                debug!("Обновлены метрики для Raydium пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
-                                    pool_address, price, fee_rate, liquidity, weight);
-                            },
-                            PoolStateBase::Meteora(state) => {
-                                let price = calculators::calculate_meteora_price(state.sqrt_price);
-                                let fee_rate = state.fee_rate as f64 / 10_000.0;
-                                let liquidity = state.liquidity as f64;
-                                let weight = calculate_meteora_weight(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    state.liquidity_multiplier
-                                );
-
-                                edge.update_metrics(
-                                    price,
-                                    fee_rate,
-                                    liquidity,
-                                    weight,
-                                    state.dynamic_liquidity_mode != 0 && state.liquidity_cap > 0,
-                                    slot
-                                );
-
-                                debug!("Обновлены метрики для Meteora пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
-                                    pool_address, price, fee_rate, liquidity, weight);
-                            }
-                        }
-                    }
-                }
-            }
+            // Обновляем ребра в графах
+            update_pool_edges(PoolCommitment::Finalized, &self.base, slot);
+
+            // Снимаем из книги возможности, посчитанные на прежнем слоте пула
+            OPPORTUNITY_BOOK.on_pool_update(PoolCommitment::Finalized, pool_address, slot);
         }
 
         updated
@@ -427,6 +406,18 @@ pub struct NetworkState {
     pub root_slot: u64,
     pub last_processed_slot: u64,
     pub last_update_time: u64,
+    /// Здоровье потоков WebSocket по имени соединения (websocket/ws_runner.rs)
+    pub feeds: HashMap<String, FeedHealth>,
+}
+
+// Здоровье одного потока WebSocket
+#[derive(Debug, Clone)]
+pub struct FeedHealth {
+    pub healthy: bool,
+    /// Причина последней потери потока
+    pub last_failure: Option<String>,
+    pub failures: u64,
+    pub last_change_time: u64,
 }
 
 impl NetworkState {
@@ -437,8 +428,14 @@ impl NetworkState {
             root_slot: 0,
             last_processed_slot: 0,
             last_update_time: unix_timestamp(),
+            feeds: HashMap::new(),
         }
     }
+
+    // Все известные потоки живы
+    pub fn feeds_healthy(&self) -> bool {
+        self.feeds.values().all(|feed| feed.healthy)
+    }
 }
 
 // Структура для быстрого поиска индекса по адресу пула
@@ -482,6 +479,15 @@ impl PoolLookupTable {
         self.lookup.insert((dex, pool_address), idx);
     }
 
+    // Адреса существующих пулов DEX
+    pub fn pools(&self, dex: DexType) -> Vec<Pubkey> {
+        self.lookup
+            .iter()
+            .filter(|((pool_dex, _), &idx)| *pool_dex == dex && self.existence_mask[idx as usize])
+            .map(|((_, pool_address), _)| *pool_address)
+            .collect()
+    }
+
     // Удаление пула из таблицы
     #[allow(dead_code)]
     #[inline]
@@ -492,6 +498,9 @@ impl PoolLookupTable {
     }
 }
 
+// Хранилище графов одного commitment по имени графа
+pub type GraphStore = Arc<DashMap<String, Graph<String, PoolEdge>>>;
+
 // Глобальная структура данных
 #[allow(dead_code)]
 #[derive(Debug, Default)]
@@ -504,6 +513,7 @@ pub struct GlobalData {
     // DEX-специфичные данные основного хранилища данных
     pub dex_pools: Arc<DashMap<DexType, DashMap<TokenPair, Vec<BasePoolInfo>>>>,
     pub processed_pool_states: Arc<DashMap<DexType, DashMap<Pubkey, ProcessedPoolState>>>,
+    pub confirmed_pool_states: Arc<DashMap<DexType, DashMap<Pubkey, ConfirmedPoolState>>>,
     pub finalized_pool_states: Arc<DashMap<DexType, DashMap<Pubkey, FinalizedPoolState>>>,
 
     // Общие данные для сети по ключу DexType (не понятно чем отличаются
@@ -521,8 +531,9 @@ pub struct GlobalData {
     pub chains_4: Arc<DashSet<Vec<String>>>,
     pub chains_5: Arc<DashSet<Vec<String>>>,
 
-    // Графы для processed и finalized состояний
+    // Графы для processed, confirmed и finalized состояний
     pub processed_graph: Arc<DashMap<String, Graph<String, PoolEdge>>>,
+    pub confirmed_graph: Arc<DashMap<String, Graph<String, PoolEdge>>>,
     pub finalized_graph: Arc<DashMap<String, Graph<String, PoolEdge>>>,
 
     // Быстрый поиск цепочек по адресу пула
@@ -531,6 +542,12 @@ pub struct GlobalData {
     // Индексированное хранилище цепочек
     pub chain_storage_4: Arc<DashMap<usize, Vec<String>>>,
     pub chain_storage_5: Arc<DashMap<usize, Vec<String>>>,
+
+    // Версии состояний пулов по commitment (увеличиваются при каждом обновлении ребра)
+    pub pool_versions: Arc<DashMap<(PoolCommitment, Pubkey), u64>>,
+
+    // Кеш результатов цепочек по (ID цепочки, commitment)
+    pub chain_results: Arc<DashMap<(usize, PoolCommitment), ChainResult>>,
 }
 
 // Глобальный экземпляр данных
@@ -541,6 +558,7 @@ lazy_static! {
         token_pairs: Arc::new(DashSet::new()),
         dex_pools: Arc::new(DashMap::new()),
         processed_pool_states: Arc::new(DashMap::new()),
+        confirmed_pool_states: Arc::new(DashMap::new()),
         finalized_pool_states: Arc::new(DashMap::new()),
         network_states: Arc::new(DashMap::new()),
         liquidity_edges: Arc::new(DashMap::new()),
@@ -549,10 +567,13 @@ lazy_static! {
         chains_4: Arc::new(DashSet::new()),
         chains_5: Arc::new(DashSet::new()),
         processed_graph: Arc::new(DashMap::new()),
+        confirmed_graph: Arc::new(DashMap::new()),
         finalized_graph: Arc::new(DashMap::new()),
         chain_references: Arc::new(DashMap::new()),
         chain_storage_4: Arc::new(DashMap::new()),
         chain_storage_5: Arc::new(DashMap::new()),
+        pool_versions: Arc::new(DashMap::new()),
+        chain_results: Arc::new(DashMap::new()),
     };
 }
 
@@ -573,6 +594,12 @@ impl GlobalData {
             info!("Initialized finalized pool states for {:?}", dex);
         }
 
+        // Инициализируем структуры для confirmed состояний если их еще нет
+        if !self.confirmed_pool_states.contains_key(&dex) {
+            self.confirmed_pool_states.insert(dex, DashMap::new());
+            info!("Initialized confirmed pool states for {:?}", dex);
+        }
+
         // Инициализируем структуры для processed состояний если их еще нет
         if !self.processed_pool_states.contains_key(&dex) {
             self.processed_pool_states.insert(dex, DashMap::new());
@@ -687,6 +714,14 @@ impl GlobalData {
         }
     }
 
+    // Адреса отслеживаемых пулов DEX
+    pub fn pool_addresses(&self, dex: DexType) -> Vec<Pubkey> {
+        self.pool_lookup
+            .get(&dex)
+            .map(|lookup_table| lookup_table.pools(dex))
+            .unwrap_or_default()
+    }
+
     // Обновление состояния сети
     pub fn update_network_state(&self, slot_info: SlotInfo) {
         // Проверяем задержку обновлений
@@ -722,6 +757,33 @@ impl GlobalData {
         state.last_update_time = current_time;
     }
 
+    // Событие здоровья потока WebSocket: поток ожил или потерян (с причиной)
+    pub fn update_feed_health(&self, feed: &str, healthy: bool, failure: Option<String>) {
+        let mut state = self.network_state
+            .entry("current".to_string())
+            .or_insert_with(NetworkState::new);
+
+        let entry = state.feeds.entry(feed.to_string()).or_insert_with(|| FeedHealth {
+            healthy,
+            last_failure: None,
+            failures: 0,
+            last_change_time: unix_timestamp(),
+        });
+        if healthy {
+            if !entry.healthy {
+                info!("Feed {} is healthy again", feed);
+            }
+        } else {
+            warn!("Feed {} is unhealthy: {}", feed, failure.as_deref().unwrap_or("unknown"));
+            entry.failures += 1;
+            entry.last_failure = failure;
+        }
+        if entry.healthy != healthy {
+            entry.last_change_time = unix_timestamp();
+        }
+        entry.healthy = healthy;
+    }
+
     // Метод проверки актуальности данных
     // TODO: Добавить реализацию и логику для регулирования
     pub fn validate_slot_consistency(&self, update_slot: u64) -> bool {
@@ -810,9 +872,72 @@ impl GlobalData {
         None
     }
 
+    // Граф для конкретного commitment
+    pub fn graph_for(&self, commitment: PoolCommitment) -> &GraphStore {
+        match commitment {
+            PoolCommitment::Processed => &self.processed_graph,
+            PoolCommitment::Confirmed => &self.confirmed_graph,
+            PoolCommitment::Finalized => &self.finalized_graph,
+        }
+    }
+
+    // Активные уровни commitment (COMMITMENT_TIERS)
+    pub fn active_commitments(&self) -> &'static [PoolCommitment] {
+        &CONFIG.commitment_tiers
+    }
+
+    // Графы активных commitment
+    pub fn commitment_graphs(&self) -> Vec<(PoolCommitment, &GraphStore)> {
+        self.active_commitments()
+            .iter()
+            .map(|&commitment| (commitment, self.graph_for(commitment)))
+            .collect()
+    }
+
+    // Увеличивает версию состояния пула для commitment
+    pub fn bump_pool_version(&self, commitment: PoolCommitment, pool_address: Pubkey) {
+        *self.pool_versions
+            .entry((commitment, pool_address))
+            .or_insert(0) += 1;
+    }
+
+    // Пометка ребер пулов в графе commitment устаревшими (или снятие пометки).
+    // Версии пулов увеличиваются, чтобы кеш цепочек пересчитался с учетом пометки.
+    pub fn set_pools_stale(&self, commitment: PoolCommitment, pools: &[Pubkey], stale: bool) {
+        let pool_set: hashbrown::HashSet<&Pubkey> = pools.iter().collect();
+        if let Some(mut g) = self.graph_for(commitment).get_mut("main") {
+            for edge in g.edge_weights_mut() {
+                if pool_set.contains(&edge.pool_address) {
+                    edge.stale = stale;
+                }
+            }
+        }
+        for pool_address in pools {
+            self.bump_pool_version(commitment, *pool_address);
+        }
+    }
+
+    // Текущая версия состояния пула (0 - обновлений еще не было)
+    pub fn pool_version(&self, commitment: PoolCommitment, pool_address: &Pubkey) -> u64 {
+        self.pool_versions
+            .get(&(commitment, *pool_address))
+            .map(|v| *v)
+            .unwrap_or(0)
+    }
+
+    // Получение цепочки по ее ID (цепочки длины 5 идут со смещением после цепочек длины 4)
+    pub fn get_chain(&self, chain_id: usize) -> Option<Vec<String>> {
+        let offset_4 = self.chain_storage_4.len();
+        if chain_id < offset_4 {
+            self.chain_storage_4.get(&chain_id).map(|c| c.value().clone())
+        } else {
+            self.chain_storage_5.get(&(chain_id - offset_4)).map(|c| c.value().clone())
+        }
+    }
+
     // Функция проверки и валидация графов
     pub fn validate_graphs(&self) -> bool {
-        for graph in [&self.processed_graph, &self.finalized_graph] {
+        for (_, graph) in self.commitment_graphs() {
             if let Some(g) = graph.get("main") {
                 info!("Валидация графа: {} вершин, {} ребер", 
                       g.node_count(), g.edge_count());
@@ -990,48 +1115,6 @@ impl OrcaPoolStateBase {
             updated = true;
         }
 
-        if updated {
-            // Обновляем ребро в обоих графах
-            for graph in [&GLOBAL_DATA.processed_graph, &GLOBAL_DATA.finalized_graph] {
-                if let Some(mut g) = graph.get_mut("main") {
-                    if let Some(edge_idx) = g.edge_indices()
-                        .find(|&e| g[e].pool_address == self.pool_address) {
-                                                
-                        let edge = &mut g[edge_idx];
-                        let price = calculators::calculate_orca_price(self.sqrt_price);
-                        let fee_rate = self.fee_rate as f64 / 10_000.0;
-                        let liquidity = self.liquidity as f64;
-                        let weight = calculate_orca_weight(
-                            price,
-                            fee_rate,
-                            liquidity,
-                            self.tick_spacing
-                        );
-
-                        edge.update_metrics(
-                            price,
-                            fee_rate,
-                            liquidity,
-                            weight,
-                            self.is_active,
-                            GLOBAL_DATA.network_state
-                                .get("current")
-                                .map(|s| s.current_slot)
-                                .unwrap_or(0)
-                        );
-
-                        debug!("Обновлены метрики ребер для Orca пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
-                            self.pool_address, price, fee_rate, liquidity, weight);
-                    }
-                }
-            }
-            // Обновляем все цепочки, использующие этот пул
-            // TODO: ВЫЗОВ СТАРОЙ ФУНКЦИИ. ДОБАВИТЬ ТРИГГЕР ПЕРЕСЧЕТА ЦЕПОЧЕК
-            // GLOBAL_DATA.update_affected_chains(self.pool_address);
-
-            // НОВЫЙ ВЫЗОВ:
-            RouterEngine::update_affected_chains(self.pool_address);
-        }
         
         updated
     }
@@ -1178,52 +1261,6 @@ impl RaydiumPoolStateBase {
             updated = true;
         }
 
-        if updated {
-            // Обновляем ребро в обоих графах
-            for graph in [&GLOBAL_DATA.processed_graph, &GLOBAL_DATA.finalized_graph] {
-                if let Some(mut g) = graph.get_mut("main") {
-                    if let Some(edge_idx) = g.edge_indices()
-                        .find(|&e| g[e].pool_address == self.pool_address) {
-                        
-                        let edge = &mut g[edge_idx];
-                        let price = calculators::calculate_raydium_price(self.min_price, self.max_price);
-                        let fee_rate = calculators::calculate_raydium_fee(
-                            self.fee_numerator,
-                            self.fee_denominator
-                        );
-                        let liquidity = self.total_lp as f64;
-                        let weight = calculate_raydium_weight(
-                            price,
-                            fee_rate,
-                            liquidity,
-                            self.orders_num,
-                            self.depth
-                        );
-
-                        edge.update_metrics(
-                            price,
-                            fee_rate,
-                            liquidity,
-                            weight,
-                            self.status != 0 && self.pool_state != 0,
-                            GLOBAL_DATA.network_state
-                                .get("current")
-                                .map(|s| s.current_slot)
-                                .unwrap_or(0)
-                        );
-
-                        debug!("Обновлены метрики ребер для Raydium пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
-                            self.pool_address, price, fee_rate, liquidity, weight);
-                    }
-                }
-            }
-            // Обновляем все цепочки, использующие этот пул
-            // TODO: ВЫЗОВ СТАРОЙ ФУНКЦИИ. ДОБАВИТЬ ТРИГГЕР ПЕРЕСЧЕТА ЦЕПОЧЕК
-            // GLOBAL_DATA.update_affected_chains(self.pool_address);
-
-            // НОВЫЙ ВЫЗОВ:
-            RouterEngine::update_affected_chains(self.pool_address);
-        }
         
         updated
     }
@@ -1414,47 +1451,6 @@ impl MeteoraPoolStateBase {
             updated = true;
         }
 
-        if updated {
-            for graph in [&GLOBAL_DATA.processed_graph, &GLOBAL_DATA.finalized_graph] {
-                if let Some(mut g) = graph.get_mut("main") {
-                    if let Some(edge_idx) = g.edge_indices()
-                        .find(|&e| g[e].pool_address == self.pool_address) {
-                        
-                        let edge = &mut g[edge_idx];
-                        let price = calculators::calculate_meteora_price(self.sqrt_price);
-                        let fee_rate = self.fee_rate as f64 / 10_000.0;
-                        let liquidity = self.liquidity as f64;
-                        let weight = calculate_meteora_weight(
-                            price,
-                            fee_rate,
-                            liquidity,
-                            self.liquidity_multiplier
-                        );
-
-                        edge.update_metrics(
-                            price,
-                            fee_rate,
-                            liquidity,
-                            weight,
-                            self.dynamic_liquidity_mode != 0 && self.liquidity_cap > 0,
-                            GLOBAL_DATA.network_state
-                                .get("current")
-                                .map(|s| s.current_slot)
-                                .unwrap_or(0)
-                        );
-
-                        debug!("Обновлены метрики ребер для Meteora пула {}: price={}, fee_rate={}, liquidity={}, weight={}", 
-                            self.pool_address, price, fee_rate, liquidity, weight);
-                    }
-                }
-            }
-            // Обновляем все цепочки, использующие этот пул
-            // TODO: ВЫЗОВ СТАРОЙ ФУНКЦИИ. ДОБАВИТЬ ТРИГГЕР ПЕРЕСЧЕТА ЦЕПОЧЕК
-            // GLOBAL_DATA.update_affected_chains(self.pool_address);
-
-            // НОВЫЙ ВЫЗОВ:
-            RouterEngine::update_affected_chains(self.pool_address);
-        }
         
         updated
     }
@@ -1493,4 +1489,54 @@ impl MeteoraPoolStateBase {
             fees_24h: data.fees_24h,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+    use crate::websocket::ws_parser::update_pool_state;
+
+    fn raydium_account(price: u64) -> RaydiumData {
+        let mut data = RaydiumData::zeroed();
+        data.status = 1;
+        data.min_price = price;
+        data.max_price = price;
+        data
+    }
+
+    #[test]
+    fn notification_older_than_backfill_is_ignored() {
+        GLOBAL_DATA.initialize_pool_states(DexType::Raydium);
+        let pool = Pubkey::new_unique();
+
+        // Backfill на слоте N, затем уведомление из очереди со слота N - 1
+        let backfilled = raydium_account(200);
+        update_pool_state(1_000, pool, bytemuck::bytes_of(&backfilled), PoolCommitment::Processed, DexType::Raydium);
+        let queued = raydium_account(100);
+        update_pool_state(999, pool, bytemuck::bytes_of(&queued), PoolCommitment::Processed, DexType::Raydium);
+
+        let states = GLOBAL_DATA.processed_pool_states.get(&DexType::Raydium).unwrap();
+        let state = states.get(&pool).unwrap();
+        assert_eq!(state.processed_slot, 1_000);
+        match &state.base {
+            PoolStateBase::Raydium(base) => assert_eq!(base.min_price, 200),
+            other => panic!("unexpected pool state {:?}", other),
+        }
+        assert_eq!(GLOBAL_DATA.pool_version(PoolCommitment::Processed, &pool), 0);
+    }
+
+    #[test]
+    fn older_slot_does_not_update_any_commitment() {
+        let pool = Pubkey::new_unique();
+        let base = PoolStateBase::from_pool_data(pool, &PoolData::Raydium(raydium_account(200)));
+        let stale = PoolData::Raydium(raydium_account(100));
+
+        let mut confirmed = ConfirmedPoolState { base: base.clone(), confirmed_slot: 50, last_update_time: 0 };
+        assert!(!confirmed.update(&stale, 49));
+        assert_eq!(confirmed.confirmed_slot, 50);
+
+        let mut finalized = FinalizedPoolState { base, finalized_slot: 50, last_update_time: 0 };
+        assert!(!finalized.update(&stale, 49));
+        assert_eq!(finalized.finalized_slot, 50);
+    }
+}
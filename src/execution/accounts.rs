@@ -0,0 +1,99 @@
+// src/execution/accounts.rs
+
+// Общие адреса программ, вывод ATA и чтение полей из сырых данных аккаунтов
+
+use std::str::FromStr;
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use solana_program::pubkey::Pubkey;
+use solana_program::instruction::{AccountMeta, Instruction};
+#[allow(deprecated)]
+use solana_program::system_program;
+use crate::config::{
+    TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID, MEMO_PROGRAM_ID,
+    ORCA_PROGRAM_ID, METEORA_PROGRAM_ID, RAYDIUM_CLMM_PROGRAM_ID, RAYDIUM_V4_PROGRAM_ID,
+    RAYDIUM_V4_AUTHORITY, WSOL_MINT,
+};
+
+lazy_static! {
+    pub static ref TOKEN_PROGRAM: Pubkey = Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap();
+    pub static ref TOKEN_2022_PROGRAM: Pubkey = Pubkey::from_str(TOKEN_2022_PROGRAM_ID).unwrap();
+    pub static ref ASSOCIATED_TOKEN_PROGRAM: Pubkey = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).unwrap();
+    pub static ref MEMO_PROGRAM: Pubkey = Pubkey::from_str(MEMO_PROGRAM_ID).unwrap();
+    pub static ref ORCA_PROGRAM: Pubkey = Pubkey::from_str(ORCA_PROGRAM_ID).unwrap();
+    pub static ref METEORA_PROGRAM: Pubkey = Pubkey::from_str(METEORA_PROGRAM_ID).unwrap();
+    pub static ref RAYDIUM_CLMM_PROGRAM: Pubkey = Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID).unwrap();
+    pub static ref RAYDIUM_V4_PROGRAM: Pubkey = Pubkey::from_str(RAYDIUM_V4_PROGRAM_ID).unwrap();
+    pub static ref RAYDIUM_V4_AUTHORITY_KEY: Pubkey = Pubkey::from_str(RAYDIUM_V4_AUTHORITY).unwrap();
+    pub static ref WSOL: Pubkey = Pubkey::from_str(WSOL_MINT).unwrap();
+}
+
+/// Адрес ATA владельца для минта с учетом токен-программы (Token / Token-2022)
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM,
+    ).0
+}
+
+/// Инструкция CreateIdempotent программы ATA: не падает, если аккаунт уже существует
+pub fn create_ata_idempotent_ix(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *ASSOCIATED_TOKEN_PROGRAM,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint, token_program), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![1],
+    }
+}
+
+/// Инструкция SyncNative программы Token: баланс wSOL аккаунта догоняет его лампорты
+pub fn sync_native_ix(token_account: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *TOKEN_PROGRAM,
+        accounts: vec![AccountMeta::new(*token_account, false)],
+        data: vec![17],
+    }
+}
+
+/// Инструкция CloseAccount программы Token: лампорты (для wSOL - весь баланс) уходят `destination`
+pub fn close_account_ix(token_account: &Pubkey, destination: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *TOKEN_PROGRAM,
+        accounts: vec![
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![9],
+    }
+}
+
+// Чтение полей по смещению из данных аккаунта
+
+pub fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .and_then(|slice| slice.try_into().ok())
+        .ok_or_else(|| anyhow!("данные аккаунта короче {} байт", offset + N))
+}
+
+pub fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read_bytes::<32>(data, offset)?))
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    Ok(u16::from_le_bytes(read_bytes(data, offset)?))
+}
+
+pub fn read_i32(data: &[u8], offset: usize) -> Result<i32> {
+    Ok(i32::from_le_bytes(read_bytes(data, offset)?))
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(data, offset)?))
+}
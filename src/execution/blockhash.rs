@@ -0,0 +1,161 @@
+// src/execution/blockhash.rs
+
+// Кеш последнего blockhash, чтобы отправка не ждала getLatestBlockhash.
+// Обновляется в фоне по таймеру (BLOCKHASH_REFRESH_MS) и по новым слотам из
+// slotSubscribe (GLOBAL_DATA.network_state), каждые BLOCKHASH_REFRESH_SLOTS слотов.
+// Высота блока между обновлениями оценивается по прошедшим слотам: слот без блока
+// только завышает оценку, поэтому проверка срока действия при сборке консервативна,
+// а окончательное решение об истечении принимается по getBlockHeight (`confirm_expired`).
+// Транзакции с blockhash, которому осталось меньше BLOCKHASH_MIN_REMAINING_BLOCKS,
+// не собираются: они скорее истекут, чем попадут в блок.
+
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{info, debug, warn};
+use solana_sdk::hash::Hash;
+use crate::config::{
+    CONFIG, BLOCKHASH_REFRESH_MS, BLOCKHASH_REFRESH_SLOTS, BLOCKHASH_SLOT_POLL_MS, BLOCKHASH_MIN_REMAINING_BLOCKS,
+};
+use crate::data::GLOBAL_DATA;
+use crate::rpc::{get_block_height, get_latest_blockhash};
+
+/// Blockhash с высотой, до которой он действителен
+#[derive(Debug, Clone, Copy)]
+pub struct CachedBlockhash {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+    /// Высота блока и слот на момент получения
+    pub block_height: u64,
+    pub slot: u64,
+    pub fetched_at: Instant,
+}
+
+#[derive(Default)]
+pub struct BlockhashCache {
+    latest: RwLock<Option<CachedBlockhash>>,
+    // Недавние blockhash -> последняя действительная высота (для проверки собранных транзакций)
+    recent: DashMap<Hash, u64>,
+}
+
+lazy_static! {
+    pub static ref BLOCKHASH_CACHE: BlockhashCache = BlockhashCache::default();
+}
+
+// Текущий слот по slotSubscribe (0 - еще не получен)
+fn current_slot() -> u64 {
+    GLOBAL_DATA.network_state
+        .get("current")
+        .map(|s| s.current_slot)
+        .unwrap_or(0)
+}
+
+impl BlockhashCache {
+    pub fn latest(&self) -> Option<CachedBlockhash> {
+        *self.latest.read().unwrap()
+    }
+
+    /// Оценка текущей высоты блока: высота при получении плюс прошедшие слоты
+    pub fn estimated_block_height(&self) -> Option<u64> {
+        let cached = self.latest()?;
+        // Слот при получении неизвестен (slotSubscribe еще не прислал данных) - без поправки
+        let elapsed_slots = if cached.slot == 0 { 0 } else { current_slot().saturating_sub(cached.slot) };
+        Some(cached.block_height + elapsed_slots)
+    }
+
+    /// Сколько блоков еще действителен blockhash (None - blockhash неизвестен кешу)
+    pub fn remaining_blocks(&self, blockhash: &Hash) -> Option<u64> {
+        let last_valid = *self.recent.get(blockhash)?;
+        Some(last_valid.saturating_sub(self.estimated_block_height()?))
+    }
+
+    /// Истек ли blockhash по оценке высоты блока (None - неизвестен кешу)
+    pub fn is_expired(&self, blockhash: &Hash) -> Option<bool> {
+        self.remaining_blocks(blockhash).map(|remaining| remaining == 0)
+    }
+
+    /// Истек ли blockhash по фактической высоте блока (None - неизвестен кешу).
+    /// Кеш забывает blockhash только после его истечения, но не узнает о чужих.
+    pub async fn confirm_expired(&self, rpc_url: &str, blockhash: &Hash) -> Result<Option<bool>> {
+        let Some(last_valid) = self.recent.get(blockhash).map(|v| *v) else {
+            return Ok(None);
+        };
+        let block_height = get_block_height(rpc_url, "confirmed").await?;
+        Ok(Some(block_height > last_valid))
+    }
+
+    /// Ошибка, если blockhash истечет раньше вероятного включения транзакции в блок
+    pub fn ensure_valid(&self, blockhash: &Hash) -> Result<()> {
+        match self.remaining_blocks(blockhash) {
+            Some(remaining) if remaining < BLOCKHASH_MIN_REMAINING_BLOCKS => Err(anyhow!(
+                "blockhash {} действителен еще {} блоков, меньше {}", blockhash, remaining, BLOCKHASH_MIN_REMAINING_BLOCKS
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Самый свежий действительный blockhash; без него - запрос через RPC
+    pub async fn get(&self, rpc_url: &str) -> Result<CachedBlockhash> {
+        if let Some(cached) = self.latest() {
+            if self.ensure_valid(&cached.blockhash).is_ok() {
+                return Ok(cached);
+            }
+        }
+        self.refresh(rpc_url).await
+    }
+
+    /// Получение нового blockhash
+    pub async fn refresh(&self, rpc_url: &str) -> Result<CachedBlockhash> {
+        let slot = current_slot();
+        let ((blockhash, last_valid_block_height), block_height) = tokio::try_join!(
+            get_latest_blockhash(rpc_url),
+            get_block_height(rpc_url, "confirmed"),
+        )?;
+
+        let cached = CachedBlockhash {
+            blockhash,
+            last_valid_block_height,
+            block_height,
+            slot,
+            fetched_at: Instant::now(),
+        };
+        *self.latest.write().unwrap() = Some(cached);
+        self.recent.insert(blockhash, last_valid_block_height);
+        self.recent.retain(|_, last_valid| *last_valid >= block_height);
+        Ok(cached)
+    }
+}
+
+/// Запуск фонового обновления blockhash
+pub async fn run_blockhash_cache() {
+    let rpc_url = CONFIG.solana_rpc_url.clone();
+    info!("blockhash: запуск кеша blockhash");
+
+    let refresh_period = Duration::from_millis(BLOCKHASH_REFRESH_MS);
+    let mut interval = tokio::time::interval(Duration::from_millis(BLOCKHASH_SLOT_POLL_MS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        let due = match BLOCKHASH_CACHE.latest() {
+            Some(cached) => cached.fetched_at.elapsed() >= refresh_period
+                || current_slot().saturating_sub(cached.slot) >= BLOCKHASH_REFRESH_SLOTS,
+            None => true,
+        };
+        if !due {
+            continue;
+        }
+
+        match BLOCKHASH_CACHE.refresh(&rpc_url).await {
+            Ok(cached) => debug!("blockhash: {} до высоты {} (высота {}, слот {})",
+                cached.blockhash, cached.last_valid_block_height, cached.block_height, cached.slot),
+            Err(e) => {
+                warn!("blockhash: не удалось обновить blockhash: {}", e);
+                tokio::time::sleep(refresh_period).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// src/execution/executor.rs
+
+// Исполнитель: получает пакеты непересекающихся возможностей из книги
+// (opportunity_book::execution_batches), собирает транзакции маршрутов,
+// прогоняет каждую через предварительную симуляцию (simulation.rs) и только
+// прошедшие отправляет: бандлом Jito, если в транзакции есть чаевые, иначе
+// рассылкой по нескольким endpoint (sender.rs).
+// Если нативная сборка не удалась, маршрут исполняется через локальный Jupiter
+// (jupiter.rs) по тому же пути симуляции и отправки.
+// Отправленные транзакции передаются трекеру (landing.rs), в режиме paper trading
+// отправка - no-op, а сделки оцениваются в paper.rs.
+// Перед каждой отправкой проверяются риск-лимиты (risk.rs).
+
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use futures::future::join_all;
+use log::{info, debug, warn, error};
+use solana_program::pubkey::Pubkey;
+use solana_program::instruction::Instruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+use crate::compute_units::instruction_keys;
+use crate::config::{CONFIG, USE_JUPITER_FALLBACK, JUPITER_CROSS_CHECK};
+use crate::opportunity_book::execution_batches;
+use crate::quote::JUPITER_CHAIN_ID;
+use crate::router::ArbitrageOpportunity;
+use crate::wallet::PAYER;
+use crate::execution::accounts::{associated_token_address, TOKEN_PROGRAM, WSOL};
+use crate::execution::blockhash::BLOCKHASH_CACHE;
+use crate::execution::health::HEALTH;
+use crate::execution::jito::JITO;
+use crate::execution::landing::LANDING;
+use crate::execution::paper::PAPER;
+use crate::execution::risk::RISK;
+use crate::execution::sender::FANOUT;
+use crate::execution::jupiter::{build_jupiter_route, cross_check};
+use crate::execution::pool_keys::{cached_pool_keys, resolve_pool_keys, PoolKeys};
+use crate::execution::simulation::{simulate_gate, GateVerdict};
+use crate::execution::tx_builder::{build_route_instructions, compile_with_lookup_tables, BuildParams};
+
+/// Ключи пулов маршрута: из кеша, а недостающие - через RPC
+async fn route_pool_keys(rpc_url: &str, pools: &[Pubkey]) -> Result<Vec<PoolKeys>> {
+    let cached: Vec<PoolKeys> = pools.iter().filter_map(cached_pool_keys).collect();
+    if cached.len() == pools.len() {
+        return Ok(cached);
+    }
+    resolve_pool_keys(rpc_url, pools).await
+}
+
+// Нативная сборка: инструкции, транзакция и токен-аккаунт стартового токена
+async fn build_native(
+    rpc_url: &str,
+    payer: &Keypair,
+    opportunity: &ArbitrageOpportunity,
+    tip_account: Pubkey,
+    recent_blockhash: Hash,
+) -> Result<(Vec<Instruction>, VersionedTransaction, Pubkey)> {
+    let pools: Vec<Pubkey> = opportunity.hops.iter().map(|h| h.pool_address).collect();
+    let keys = route_pool_keys(rpc_url, &pools).await?;
+    let owner = payer.pubkey();
+
+    let params = BuildParams::for_opportunity(opportunity).with_tip(tip_account, opportunity.costs.jito_tip);
+    let instructions = build_route_instructions(opportunity, &keys, &owner, params)?;
+    let tx = compile_with_lookup_tables(payer, &instructions, recent_blockhash)?;
+
+    // Прибыль считаем по токен-аккаунту стартового токена
+    let first_hop = &opportunity.hops[0];
+    let first_keys = keys.iter()
+        .find(|k| k.pool() == first_hop.pool_address)
+        .ok_or_else(|| anyhow!("нет ключей пула {}", first_hop.pool_address))?;
+    let profit_account = associated_token_address(
+        &owner, &first_hop.input_mint, &first_keys.token_program_for(&first_hop.input_mint)
+    );
+
+    Ok((instructions, tx, profit_account))
+}
+
+// Отправленная транзакция - трекеру, в режиме paper trading - на оценку по следующему состоянию пулов
+fn track_sent(
+    tx: &VersionedTransaction,
+    bundle_id: Option<String>,
+    opportunity: &ArbitrageOpportunity,
+    instructions: &[Instruction],
+    simulated_profit: i64,
+) {
+    if CONFIG.paper_trading {
+        PAPER.record(tx.signatures[0].to_string(), bundle_id, opportunity, simulated_profit);
+    } else {
+        let cu_keys = instruction_keys(instructions, opportunity);
+        LANDING.track(tx, bundle_id, opportunity, simulated_profit, cu_keys);
+    }
+}
+
+/// Предварительная симуляция и отправка собранной транзакции (общая для нативной сборки и Jupiter)
+pub async fn simulate_and_send(
+    rpc_url: &str,
+    opportunity: &ArbitrageOpportunity,
+    instructions: &[Instruction],
+    tx: VersionedTransaction,
+    profit_account: &Pubkey,
+) -> Result<()> {
+    let report = match simulate_gate(rpc_url, opportunity, instructions, &tx, profit_account).await? {
+        GateVerdict::Pass(report) => report,
+        GateVerdict::Reject(rejection) => {
+            debug!("executor: маршрут [{}] отклонен симуляцией: {:?} (пул {:?}): {}",
+                opportunity.chain_id, rejection.failure, rejection.pool, rejection.detail);
+            return Ok(());
+        }
+    };
+
+    let pools: Vec<Pubkey> = opportunity.hops.iter().map(|h| h.pool_address).collect();
+    if let Err(rejection) = RISK.check(opportunity, &pools) {
+        debug!("executor: маршрут [{}] не отправлен, риск-лимит: {:?}", opportunity.chain_id, rejection);
+        return Ok(());
+    }
+
+    let tip = opportunity.costs.jito_tip;
+    if tip > 0 {
+        let bundle_id = JITO.send_bundle(std::slice::from_ref(&tx), Some(opportunity.chain_id), tip).await
+            .inspect_err(|_| RISK.record_failure(&pools))?;
+        info!("executor: маршрут [{}] ({:?}) отправлен бандлом {} (прибыль по симуляции {}, CU {:?})",
+            opportunity.chain_id, opportunity.source, bundle_id, report.net_profit, report.units_consumed);
+        track_sent(&tx, Some(bundle_id), opportunity, instructions, report.net_profit);
+    } else {
+        let signature = FANOUT.send(&tx).await
+            .inspect_err(|_| RISK.record_failure(&pools))?;
+        info!("executor: маршрут [{}] ({:?}) отправлен транзакцией {} (прибыль по симуляции {}, CU {:?})",
+            opportunity.chain_id, opportunity.source, signature, report.net_profit, report.units_consumed);
+        track_sent(&tx, None, opportunity, instructions, report.net_profit);
+    }
+    Ok(())
+}
+
+// Исполнение через Jupiter на тот же вход, что и у возможности. Jupiter строит свой
+// маршрут, поэтому результат учитывается под JUPITER_CHAIN_ID, а не под цепочкой возможности
+async fn execute_via_jupiter(
+    rpc_url: &str,
+    payer: &Keypair,
+    opportunity: &ArbitrageOpportunity,
+    tip_account: Pubkey,
+    recent_blockhash: Hash,
+) -> Result<()> {
+    let (jupiter_opportunity, instructions, tx) = build_jupiter_route(
+        rpc_url, payer, JUPITER_CHAIN_ID, opportunity.amount_in, tip_account, recent_blockhash
+    ).await?;
+
+    let profit_account = associated_token_address(&payer.pubkey(), &WSOL, &TOKEN_PROGRAM);
+    simulate_and_send(rpc_url, &jupiter_opportunity, &instructions, tx, &profit_account).await
+}
+
+/// Сборка, симуляция и отправка одной возможности.
+/// Если нативная сборка не удалась, маршрут исполняется через Jupiter.
+pub async fn execute_opportunity(
+    rpc_url: &str,
+    payer: &Keypair,
+    opportunity: &ArbitrageOpportunity,
+    recent_blockhash: Hash,
+) -> Result<()> {
+    let pools: Vec<Pubkey> = opportunity.hops.iter().map(|h| h.pool_address).collect();
+    if !HEALTH.is_route_healthy(opportunity.chain_id, &pools) {
+        debug!("executor: маршрут [{}] временно отключен", opportunity.chain_id);
+        return Ok(());
+    }
+    if let Err(e) = BLOCKHASH_CACHE.ensure_valid(&recent_blockhash) {
+        debug!("executor: маршрут [{}] не собран: {}", opportunity.chain_id, e);
+        return Ok(());
+    }
+
+    let tip_account = JITO.tip_account();
+    match build_native(rpc_url, payer, opportunity, tip_account, recent_blockhash).await {
+        Ok((instructions, tx, profit_account)) => {
+            if JUPITER_CROSS_CHECK {
+                // Сверка с Jupiter не задерживает отправку
+                let opportunity = opportunity.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = cross_check(&opportunity).await {
+                        debug!("executor: сверка маршрута [{}] с Jupiter не удалась: {}", opportunity.chain_id, e);
+                    }
+                });
+            }
+            simulate_and_send(rpc_url, opportunity, &instructions, tx, &profit_account).await
+        }
+        Err(e) if USE_JUPITER_FALLBACK => {
+            debug!("executor: нативная сборка маршрута [{}] не удалась ({}), исполняем через Jupiter", opportunity.chain_id, e);
+            execute_via_jupiter(rpc_url, payer, opportunity, tip_account, recent_blockhash).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Запуск исполнителя
+pub async fn run_executor() {
+    let rpc_url = CONFIG.solana_rpc_url.clone();
+    let payer: Arc<Keypair> = PAYER.clone();
+    let batches = execution_batches();
+
+    info!("executor: запуск исполнителя, кошелек {}", payer.pubkey());
+
+    while let Ok(batch) = batches.recv_async().await {
+        // Kill switch: книга продолжает работать, пакеты просто не исполняются
+        if RISK.is_killed() {
+            debug!("executor: исполнение остановлено ({:?}), пакет из {} возможностей пропущен", RISK.kill_reason(), batch.len());
+            continue;
+        }
+
+        let recent_blockhash = match BLOCKHASH_CACHE.get(&rpc_url).await {
+            Ok(cached) => cached.blockhash,
+            Err(e) => {
+                warn!("executor: не удалось получить blockhash, пакет из {} возможностей пропущен: {}", batch.len(), e);
+                continue;
+            }
+        };
+
+        let results = join_all(
+            batch.iter().map(|opportunity| execute_opportunity(&rpc_url, &payer, opportunity, recent_blockhash))
+        ).await;
+
+        for (opportunity, result) in batch.iter().zip(results) {
+            if let Err(e) = result {
+                error!("executor: маршрут [{}]: {}", opportunity.chain_id, e);
+            }
+        }
+    }
+}
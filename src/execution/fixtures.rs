@@ -0,0 +1,174 @@
+// src/execution/fixtures.rs
+
+// Общие данные для тестов сборки инструкций и транзакций:
+// фиксированные ключи пулов, подписант, blockhash и сверка байтов с эталонами
+// из tests/fixtures/execution/*.hex.
+//
+// Эталоны перегенерируются запуском тестов с UPDATE_FIXTURES=1; изменения
+// эталонов проходят ревью как обычный код.
+
+use std::path::PathBuf;
+use solana_program::pubkey::Pubkey;
+use solana_program::instruction::Instruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Keypair;
+use crate::execution::accounts::{TOKEN_PROGRAM, TOKEN_2022_PROGRAM};
+use crate::execution::ix_orca::WhirlpoolKeys;
+use crate::execution::ix_raydium::{AmmV4Keys, ClmmKeys};
+use crate::execution::ix_meteora::DlmmKeys;
+
+// Минты маршрута A -> B -> C -> D -> A
+pub fn mint_a() -> Pubkey { key(1) }
+pub fn mint_b() -> Pubkey { key(2) }
+pub fn mint_c() -> Pubkey { key(3) }
+pub fn mint_d() -> Pubkey { key(4) }
+
+/// Детерминированный ключ: все 32 байта равны `n`
+pub fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+/// Подписант с фиксированным секретным ключом
+pub fn payer() -> Keypair {
+    Keypair::new_from_array([7; 32])
+}
+
+pub fn blockhash() -> Hash {
+    Hash::new_from_array([9; 32])
+}
+
+/// Whirlpool A/B
+pub fn whirlpool_keys() -> WhirlpoolKeys {
+    WhirlpoolKeys {
+        pool: key(10),
+        mint_a: mint_a(),
+        mint_b: mint_b(),
+        vault_a: key(11),
+        vault_b: key(12),
+        token_program_a: *TOKEN_PROGRAM,
+        token_program_b: *TOKEN_PROGRAM,
+        tick_spacing: 64,
+        tick_current_index: -18_000,
+    }
+}
+
+/// Raydium AMM V4 B/C
+pub fn amm_v4_keys() -> AmmV4Keys {
+    AmmV4Keys {
+        pool: key(20),
+        coin_mint: mint_b(),
+        pc_mint: mint_c(),
+        coin_vault: key(21),
+        pc_vault: key(22),
+        open_orders: key(23),
+        target_orders: key(24),
+        market_program: key(25),
+        market: key(26),
+        market_bids: key(27),
+        market_asks: key(28),
+        market_event_queue: key(29),
+        market_coin_vault: key(30),
+        market_pc_vault: key(31),
+        market_vault_signer: key(32),
+    }
+}
+
+/// Raydium CLMM C/D, D на Token-2022
+pub fn clmm_keys() -> ClmmKeys {
+    ClmmKeys {
+        pool: key(40),
+        amm_config: key(41),
+        mint_0: mint_c(),
+        mint_1: mint_d(),
+        vault_0: key(42),
+        vault_1: key(43),
+        observation: key(44),
+        token_program_0: *TOKEN_PROGRAM,
+        token_program_1: *TOKEN_2022_PROGRAM,
+        tick_spacing: 10,
+        tick_current: 1_234,
+        tick_arrays_zero_for_one: vec![key(45), key(46)],
+        tick_arrays_one_for_zero: vec![key(47)],
+    }
+}
+
+/// Meteora DLMM A/D (x = A, y = D)
+pub fn dlmm_keys() -> DlmmKeys {
+    DlmmKeys {
+        pair: key(50),
+        mint_x: mint_a(),
+        mint_y: mint_d(),
+        reserve_x: key(51),
+        reserve_y: key(52),
+        oracle: key(53),
+        token_program_x: *TOKEN_PROGRAM,
+        token_program_y: *TOKEN_2022_PROGRAM,
+        active_id: -512,
+        bin_step: 25,
+        bitmap_extension: Some(key(54)),
+        bin_arrays_x_to_y: vec![key(55), key(56)],
+        bin_arrays_y_to_x: vec![key(57)],
+    }
+}
+
+/// Байты инструкции: program_id, число аккаунтов (u16 LE),
+/// для каждого аккаунта pubkey + is_signer + is_writable, затем данные
+pub fn instruction_bytes(ix: &Instruction) -> Vec<u8> {
+    let mut out = Vec::with_capacity(32 + 2 + ix.accounts.len() * 34 + ix.data.len());
+    out.extend_from_slice(ix.program_id.as_ref());
+    out.extend_from_slice(&(ix.accounts.len() as u16).to_le_bytes());
+    for meta in &ix.accounts {
+        out.extend_from_slice(meta.pubkey.as_ref());
+        out.push(meta.is_signer as u8);
+        out.push(meta.is_writable as u8);
+    }
+    out.extend_from_slice(&ix.data);
+    out
+}
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/execution")
+        .join(format!("{}.hex", name))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2 + bytes.len() / 32 + 1);
+    for line in bytes.chunks(32) {
+        for byte in line {
+            out.push_str(&format!("{:02x}", byte));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn from_hex(text: &str) -> Vec<u8> {
+    let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    assert!(digits.len().is_multiple_of(2), "нечетное число hex-символов");
+    digits.chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+        .collect()
+}
+
+/// Сверка байтов с эталоном `name`. С UPDATE_FIXTURES=1 эталон перезаписывается.
+pub fn assert_fixture(name: &str, bytes: &[u8]) {
+    let path = fixture_path(name);
+
+    if std::env::var_os("UPDATE_FIXTURES").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, to_hex(bytes)).unwrap();
+        return;
+    }
+
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("эталон {}: {} (UPDATE_FIXTURES=1 для создания)", path.display(), e));
+    let expected = from_hex(&text);
+
+    if expected != bytes {
+        let first_diff = expected.iter().zip(bytes).position(|(a, b)| a != b)
+            .unwrap_or(expected.len().min(bytes.len()));
+        panic!("{}: расхождение с эталоном с байта {} (эталон {} байт, получено {})",
+            name, first_diff, expected.len(), bytes.len());
+    }
+}
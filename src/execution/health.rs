@@ -0,0 +1,98 @@
+// src/execution/health.rs
+
+// Здоровье пулов и маршрутов по результатам предварительной симуляции.
+// Каждая ошибка симуляции классифицирована (simulation.rs); ошибки, за которые
+// отвечает пул или маршрут, считаются подряд, и после SIMULATION_FAILURE_LIMIT
+// пул / маршрут отключается на SIMULATION_FAILURE_COOLDOWN_SECS.
+// Успешная симуляция сбрасывает счетчик подряд идущих ошибок.
+
+use std::collections::HashMap;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::warn;
+use solana_program::pubkey::Pubkey;
+use crate::config::{SIMULATION_FAILURE_LIMIT, SIMULATION_FAILURE_COOLDOWN_SECS};
+use crate::data::unix_timestamp;
+use crate::execution::simulation::SimulationFailure;
+
+/// Статистика симуляций пула или маршрута
+#[derive(Debug, Clone, Default)]
+pub struct HealthRecord {
+    pub successes: u64,
+    pub failures: HashMap<SimulationFailure, u64>,
+    pub consecutive_failures: u32,
+    pub last_failure: Option<SimulationFailure>,
+    /// До какого момента (unix, сек) пул / маршрут отключен
+    pub disabled_until: u64,
+}
+
+impl HealthRecord {
+    fn record_success(&mut self) {
+        self.successes += 1;
+        self.consecutive_failures = 0;
+    }
+
+    // Возвращает true, если запись только что отключена
+    fn record_failure(&mut self, failure: SimulationFailure, now: u64) -> bool {
+        *self.failures.entry(failure).or_insert(0) += 1;
+        self.last_failure = Some(failure);
+        self.consecutive_failures += 1;
+
+        if self.consecutive_failures >= SIMULATION_FAILURE_LIMIT {
+            self.consecutive_failures = 0;
+            self.disabled_until = now + SIMULATION_FAILURE_COOLDOWN_SECS;
+            return true;
+        }
+        false
+    }
+
+    pub fn is_disabled(&self, now: u64) -> bool {
+        now < self.disabled_until
+    }
+}
+
+#[derive(Default)]
+pub struct HealthTracker {
+    pools: DashMap<Pubkey, HealthRecord>,
+    routes: DashMap<usize, HealthRecord>,
+}
+
+lazy_static! {
+    pub static ref HEALTH: HealthTracker = HealthTracker::default();
+}
+
+impl HealthTracker {
+    /// Успешная симуляция маршрута
+    pub fn record_success(&self, chain_id: usize, pools: &[Pubkey]) {
+        self.routes.entry(chain_id).or_default().record_success();
+        for pool in pools {
+            self.pools.entry(*pool).or_default().record_success();
+        }
+    }
+
+    /// Ошибка симуляции. `pool` - пул инструкции, на которой упала транзакция, если известен.
+    pub fn record_failure(&self, chain_id: usize, pool: Option<Pubkey>, failure: SimulationFailure) {
+        let now = unix_timestamp();
+
+        if failure.penalizes_route()
+            && self.routes.entry(chain_id).or_default().record_failure(failure, now) {
+            warn!("health: маршрут [{}] отключен на {} с после ошибок {:?}", chain_id, SIMULATION_FAILURE_COOLDOWN_SECS, failure);
+        }
+
+        if let Some(pool) = pool {
+            if failure.penalizes_pool()
+                && self.pools.entry(pool).or_default().record_failure(failure, now) {
+                warn!("health: пул {} отключен на {} с после ошибок {:?}", pool, SIMULATION_FAILURE_COOLDOWN_SECS, failure);
+            }
+        }
+    }
+
+    /// Можно ли исполнять маршрут: ни он, ни его пулы не отключены
+    pub fn is_route_healthy(&self, chain_id: usize, pools: &[Pubkey]) -> bool {
+        let now = unix_timestamp();
+        if self.routes.get(&chain_id).is_some_and(|r| r.is_disabled(now)) {
+            return false;
+        }
+        !pools.iter().any(|p| self.pools.get(p).is_some_and(|r| r.is_disabled(now)))
+    }
+}
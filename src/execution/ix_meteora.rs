@@ -0,0 +1,165 @@
+// src/execution/ix_meteora.rs
+
+// Инструкция swap программы Meteora DLMM
+
+use anyhow::{ensure, Result};
+use solana_program::pubkey::Pubkey;
+use solana_program::instruction::{AccountMeta, Instruction};
+use crate::execution::accounts::{
+    METEORA_PROGRAM, TOKEN_PROGRAM,
+    read_pubkey, read_u16, read_i32,
+};
+
+// sha256("global:swap")[..8]
+const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+const MAX_BIN_PER_ARRAY: i32 = 70;
+
+// Смещения полей LbPair
+const ACTIVE_ID_OFFSET: usize = 76;
+const BIN_STEP_OFFSET: usize = 80;
+const TOKEN_X_MINT_OFFSET: usize = 88;
+const TOKEN_Y_MINT_OFFSET: usize = 120;
+const RESERVE_X_OFFSET: usize = 152;
+const RESERVE_Y_OFFSET: usize = 184;
+const ORACLE_OFFSET: usize = 552;
+const LB_PAIR_MIN_LEN: usize = 584;
+
+/// Аккаунты пары Meteora DLMM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DlmmKeys {
+    pub pair: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub oracle: Pubkey,
+    pub token_program_x: Pubkey,
+    pub token_program_y: Pubkey,
+    pub active_id: i32,
+    pub bin_step: u16,
+    /// Расширение битовой карты bin arrays, если создано
+    pub bitmap_extension: Option<Pubkey>,
+    /// Существующие bin arrays по направлению свапа (заполняет резолвер)
+    pub bin_arrays_x_to_y: Vec<Pubkey>,
+    pub bin_arrays_y_to_x: Vec<Pubkey>,
+}
+
+pub fn parse_lb_pair(pair: Pubkey, data: &[u8]) -> Result<DlmmKeys> {
+    ensure!(data.len() >= LB_PAIR_MIN_LEN, "LbPair {}: {} байт, ожидалось {}", pair, data.len(), LB_PAIR_MIN_LEN);
+
+    Ok(DlmmKeys {
+        pair,
+        mint_x: read_pubkey(data, TOKEN_X_MINT_OFFSET)?,
+        mint_y: read_pubkey(data, TOKEN_Y_MINT_OFFSET)?,
+        reserve_x: read_pubkey(data, RESERVE_X_OFFSET)?,
+        reserve_y: read_pubkey(data, RESERVE_Y_OFFSET)?,
+        oracle: read_pubkey(data, ORACLE_OFFSET)?,
+        token_program_x: *TOKEN_PROGRAM,
+        token_program_y: *TOKEN_PROGRAM,
+        active_id: read_i32(data, ACTIVE_ID_OFFSET)?,
+        bin_step: read_u16(data, BIN_STEP_OFFSET)?,
+        bitmap_extension: None,
+        bin_arrays_x_to_y: Vec::new(),
+        bin_arrays_y_to_x: Vec::new(),
+    })
+}
+
+/// Индекс bin array, содержащего бин
+pub fn bin_array_index(bin_id: i32) -> i64 {
+    bin_id.div_euclid(MAX_BIN_PER_ARRAY) as i64
+}
+
+pub fn bin_array_address(pair: &Pubkey, index: i64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"bin_array", pair.as_ref(), &index.to_le_bytes()],
+        &METEORA_PROGRAM,
+    ).0
+}
+
+pub fn bitmap_extension_address(pair: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bitmap", pair.as_ref()], &METEORA_PROGRAM).0
+}
+
+pub fn event_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &METEORA_PROGRAM).0
+}
+
+/// Кандидаты bin arrays от активного в направлении свапа (резолвер оставляет существующие).
+/// Свап x->y сдвигает активный бин вниз, y->x - вверх.
+pub fn bin_array_candidates(keys: &DlmmKeys, swap_for_y: bool, count: i64) -> Vec<Pubkey> {
+    let step = if swap_for_y { -1 } else { 1 };
+    let active = bin_array_index(keys.active_id);
+    (0..count)
+        .map(|i| bin_array_address(&keys.pair, active + i * step))
+        .collect()
+}
+
+/// swap с точным входом
+pub fn swap_ix(
+    keys: &DlmmKeys,
+    owner: &Pubkey,
+    user_token_in: &Pubkey,
+    user_token_out: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+    swap_for_y: bool,
+) -> Instruction {
+    let bin_arrays = if swap_for_y {
+        &keys.bin_arrays_x_to_y
+    } else {
+        &keys.bin_arrays_y_to_x
+    };
+
+    let mut data = Vec::with_capacity(8 + 8 + 8);
+    data.extend_from_slice(&SWAP_DISCRIMINATOR);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+    // Необязательные аккаунты Anchor передаются адресом программы
+    let bitmap_extension = keys.bitmap_extension.unwrap_or(*METEORA_PROGRAM);
+
+    let mut accounts = vec![
+        AccountMeta::new(keys.pair, false),
+        AccountMeta::new_readonly(bitmap_extension, false),
+        AccountMeta::new(keys.reserve_x, false),
+        AccountMeta::new(keys.reserve_y, false),
+        AccountMeta::new(*user_token_in, false),
+        AccountMeta::new(*user_token_out, false),
+        AccountMeta::new_readonly(keys.mint_x, false),
+        AccountMeta::new_readonly(keys.mint_y, false),
+        AccountMeta::new(keys.oracle, false),
+        AccountMeta::new_readonly(*METEORA_PROGRAM, false), // host_fee_in: None
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(keys.token_program_x, false),
+        AccountMeta::new_readonly(keys.token_program_y, false),
+        AccountMeta::new_readonly(event_authority_address(), false),
+        AccountMeta::new_readonly(*METEORA_PROGRAM, false),
+    ];
+    accounts.extend(bin_arrays.iter().map(|ba| AccountMeta::new(*ba, false)));
+
+    Instruction {
+        program_id: *METEORA_PROGRAM,
+        accounts,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::fixtures::{assert_fixture, instruction_bytes, key, dlmm_keys};
+
+    #[test]
+    fn swap_x_to_y_matches_fixture() {
+        let ix = swap_ix(&dlmm_keys(), &key(100), &key(101), &key(102), 1_000_000, 990_000, true);
+        assert_fixture("meteora_dlmm_swap_x_to_y", &instruction_bytes(&ix));
+    }
+
+    #[test]
+    fn swap_y_to_x_without_bitmap_extension_matches_fixture() {
+        let keys = DlmmKeys { bitmap_extension: None, ..dlmm_keys() };
+        let ix = swap_ix(&keys, &key(100), &key(102), &key(101), 2_500_000, 2_400_000, false);
+        assert_fixture("meteora_dlmm_swap_y_to_x", &instruction_bytes(&ix));
+    }
+}
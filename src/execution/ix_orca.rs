@@ -0,0 +1,154 @@
+// src/execution/ix_orca.rs
+
+// Инструкция swap_v2 программы Orca Whirlpool
+
+use anyhow::{ensure, Result};
+use solana_program::pubkey::Pubkey;
+use solana_program::instruction::{AccountMeta, Instruction};
+use crate::execution::accounts::{
+    ORCA_PROGRAM, TOKEN_PROGRAM, MEMO_PROGRAM,
+    read_pubkey, read_u16, read_i32,
+};
+
+// sha256("global:swap_v2")[..8]
+const SWAP_V2_DISCRIMINATOR: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+
+const TICK_ARRAY_SIZE: i32 = 88;
+// swap_v2 принимает ровно три tick array
+const SWAP_TICK_ARRAYS: i32 = 3;
+const MIN_SQRT_PRICE_X64: u128 = 4_295_048_016;
+const MAX_SQRT_PRICE_X64: u128 = 79_226_673_515_401_279_992_447_579_055;
+
+// Смещения полей аккаунта Whirlpool (с учетом 8 байт дискриминатора Anchor)
+const TICK_SPACING_OFFSET: usize = 41;
+const TICK_CURRENT_INDEX_OFFSET: usize = 81;
+const TOKEN_MINT_A_OFFSET: usize = 101;
+const TOKEN_VAULT_A_OFFSET: usize = 133;
+const TOKEN_MINT_B_OFFSET: usize = 181;
+const TOKEN_VAULT_B_OFFSET: usize = 213;
+const WHIRLPOOL_MIN_LEN: usize = 653;
+
+/// Аккаунты пула Whirlpool, необходимые для свапа
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhirlpoolKeys {
+    pub pool: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub token_program_a: Pubkey,
+    pub token_program_b: Pubkey,
+    pub tick_spacing: u16,
+    pub tick_current_index: i32,
+}
+
+/// Разбор аккаунта Whirlpool. Токен-программы минтов уточняет резолвер.
+pub fn parse_whirlpool(pool: Pubkey, data: &[u8]) -> Result<WhirlpoolKeys> {
+    ensure!(data.len() >= WHIRLPOOL_MIN_LEN, "Whirlpool {}: {} байт, ожидалось {}", pool, data.len(), WHIRLPOOL_MIN_LEN);
+
+    Ok(WhirlpoolKeys {
+        pool,
+        mint_a: read_pubkey(data, TOKEN_MINT_A_OFFSET)?,
+        mint_b: read_pubkey(data, TOKEN_MINT_B_OFFSET)?,
+        vault_a: read_pubkey(data, TOKEN_VAULT_A_OFFSET)?,
+        vault_b: read_pubkey(data, TOKEN_VAULT_B_OFFSET)?,
+        token_program_a: *TOKEN_PROGRAM,
+        token_program_b: *TOKEN_PROGRAM,
+        tick_spacing: read_u16(data, TICK_SPACING_OFFSET)?,
+        tick_current_index: read_i32(data, TICK_CURRENT_INDEX_OFFSET)?,
+    })
+}
+
+/// Стартовый тик tick array, содержащего `tick`, со смещением на `offset` массивов
+pub fn tick_array_start_index(tick: i32, tick_spacing: u16, offset: i32) -> i32 {
+    let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+    (tick.div_euclid(ticks_in_array) + offset) * ticks_in_array
+}
+
+pub fn tick_array_address(pool: &Pubkey, start_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"tick_array", pool.as_ref(), start_index.to_string().as_bytes()],
+        &ORCA_PROGRAM,
+    ).0
+}
+
+pub fn oracle_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"oracle", pool.as_ref()], &ORCA_PROGRAM).0
+}
+
+/// Tick arrays в направлении свапа. Для b->a текущий тик сдвигается на tick_spacing,
+/// как в SDK Orca, чтобы не пропустить массив на границе.
+pub fn tick_arrays_for_swap(keys: &WhirlpoolKeys, a_to_b: bool) -> Vec<Pubkey> {
+    let shift = if a_to_b { 0 } else { keys.tick_spacing as i32 };
+    let step = if a_to_b { -1 } else { 1 };
+
+    (0..SWAP_TICK_ARRAYS)
+        .map(|i| {
+            let start = tick_array_start_index(keys.tick_current_index + shift, keys.tick_spacing, i * step);
+            tick_array_address(&keys.pool, start)
+        })
+        .collect()
+}
+
+/// swap_v2 с точным входом
+pub fn swap_v2_ix(
+    keys: &WhirlpoolKeys,
+    owner: &Pubkey,
+    owner_account_a: &Pubkey,
+    owner_account_b: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+    a_to_b: bool,
+) -> Instruction {
+    let tick_arrays = tick_arrays_for_swap(keys, a_to_b);
+    let sqrt_price_limit = if a_to_b { MIN_SQRT_PRICE_X64 } else { MAX_SQRT_PRICE_X64 };
+
+    let mut data = Vec::with_capacity(8 + 8 + 8 + 16 + 1 + 1 + 1);
+    data.extend_from_slice(&SWAP_V2_DISCRIMINATOR);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+    data.extend_from_slice(&sqrt_price_limit.to_le_bytes());
+    data.push(1); // amount_specified_is_input
+    data.push(a_to_b as u8);
+    data.push(0); // remaining_accounts_info: None
+
+    Instruction {
+        program_id: *ORCA_PROGRAM,
+        accounts: vec![
+            AccountMeta::new_readonly(keys.token_program_a, false),
+            AccountMeta::new_readonly(keys.token_program_b, false),
+            AccountMeta::new_readonly(*MEMO_PROGRAM, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(keys.pool, false),
+            AccountMeta::new_readonly(keys.mint_a, false),
+            AccountMeta::new_readonly(keys.mint_b, false),
+            AccountMeta::new(*owner_account_a, false),
+            AccountMeta::new(keys.vault_a, false),
+            AccountMeta::new(*owner_account_b, false),
+            AccountMeta::new(keys.vault_b, false),
+            AccountMeta::new(tick_arrays[0], false),
+            AccountMeta::new(tick_arrays[1], false),
+            AccountMeta::new(tick_arrays[2], false),
+            AccountMeta::new(oracle_address(&keys.pool), false),
+        ],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::fixtures::{assert_fixture, instruction_bytes, key, whirlpool_keys};
+
+    #[test]
+    fn swap_v2_a_to_b_matches_fixture() {
+        let ix = swap_v2_ix(&whirlpool_keys(), &key(100), &key(101), &key(102), 1_000_000, 990_000, true);
+        assert_fixture("orca_swap_v2_a_to_b", &instruction_bytes(&ix));
+    }
+
+    #[test]
+    fn swap_v2_b_to_a_matches_fixture() {
+        let ix = swap_v2_ix(&whirlpool_keys(), &key(100), &key(101), &key(102), 2_500_000, 2_400_000, false);
+        assert_fixture("orca_swap_v2_b_to_a", &instruction_bytes(&ix));
+    }
+}
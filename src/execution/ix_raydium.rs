@@ -0,0 +1,303 @@
+// src/execution/ix_raydium.rs
+
+// Инструкции свапа Raydium:
+// - AMM V4 swap_base_in (нативная программа, аккаунты рынка OpenBook);
+// - CLMM swap_v2 (Anchor, tick arrays в remaining accounts).
+
+use anyhow::{ensure, Result};
+use solana_program::pubkey::Pubkey;
+use solana_program::instruction::{AccountMeta, Instruction};
+use crate::execution::accounts::{
+    RAYDIUM_V4_PROGRAM, RAYDIUM_V4_AUTHORITY_KEY, RAYDIUM_CLMM_PROGRAM,
+    TOKEN_PROGRAM, TOKEN_2022_PROGRAM, MEMO_PROGRAM,
+    read_pubkey, read_u16, read_i32, read_u64,
+};
+
+// ---------------- AMM V4 ----------------
+
+const SWAP_BASE_IN_TAG: u8 = 9;
+
+// Смещения полей AmmInfo
+const AMM_COIN_VAULT_OFFSET: usize = 336;
+const AMM_PC_VAULT_OFFSET: usize = 368;
+const AMM_COIN_MINT_OFFSET: usize = 400;
+const AMM_PC_MINT_OFFSET: usize = 432;
+const AMM_OPEN_ORDERS_OFFSET: usize = 496;
+const AMM_MARKET_OFFSET: usize = 528;
+const AMM_MARKET_PROGRAM_OFFSET: usize = 560;
+const AMM_TARGET_ORDERS_OFFSET: usize = 592;
+pub const AMM_INFO_LEN: usize = 752;
+
+// Смещения полей рынка OpenBook (с 5 байтами префикса "serum")
+const MARKET_VAULT_SIGNER_NONCE_OFFSET: usize = 45;
+const MARKET_COIN_VAULT_OFFSET: usize = 117;
+const MARKET_PC_VAULT_OFFSET: usize = 165;
+const MARKET_EVENT_QUEUE_OFFSET: usize = 253;
+const MARKET_BIDS_OFFSET: usize = 285;
+const MARKET_ASKS_OFFSET: usize = 317;
+const MARKET_MIN_LEN: usize = 388;
+
+/// Аккаунты пула Raydium AMM V4 и его рынка OpenBook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmmV4Keys {
+    pub pool: Pubkey,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub open_orders: Pubkey,
+    pub target_orders: Pubkey,
+    pub market_program: Pubkey,
+    pub market: Pubkey,
+    pub market_bids: Pubkey,
+    pub market_asks: Pubkey,
+    pub market_event_queue: Pubkey,
+    pub market_coin_vault: Pubkey,
+    pub market_pc_vault: Pubkey,
+    pub market_vault_signer: Pubkey,
+}
+
+/// Разбор AmmInfo. Аккаунты рынка заполняются отдельно из данных рынка (`apply_market`).
+pub fn parse_amm_v4(pool: Pubkey, data: &[u8]) -> Result<AmmV4Keys> {
+    ensure!(data.len() >= AMM_INFO_LEN, "AMM V4 {}: {} байт, ожидалось {}", pool, data.len(), AMM_INFO_LEN);
+
+    Ok(AmmV4Keys {
+        pool,
+        coin_mint: read_pubkey(data, AMM_COIN_MINT_OFFSET)?,
+        pc_mint: read_pubkey(data, AMM_PC_MINT_OFFSET)?,
+        coin_vault: read_pubkey(data, AMM_COIN_VAULT_OFFSET)?,
+        pc_vault: read_pubkey(data, AMM_PC_VAULT_OFFSET)?,
+        open_orders: read_pubkey(data, AMM_OPEN_ORDERS_OFFSET)?,
+        target_orders: read_pubkey(data, AMM_TARGET_ORDERS_OFFSET)?,
+        market_program: read_pubkey(data, AMM_MARKET_PROGRAM_OFFSET)?,
+        market: read_pubkey(data, AMM_MARKET_OFFSET)?,
+        market_bids: Pubkey::default(),
+        market_asks: Pubkey::default(),
+        market_event_queue: Pubkey::default(),
+        market_coin_vault: Pubkey::default(),
+        market_pc_vault: Pubkey::default(),
+        market_vault_signer: Pubkey::default(),
+    })
+}
+
+/// Дополняет ключи пула аккаунтами рынка OpenBook
+pub fn apply_market(keys: &mut AmmV4Keys, market_data: &[u8]) -> Result<()> {
+    ensure!(market_data.len() >= MARKET_MIN_LEN, "рынок {}: {} байт, ожидалось {}",
+        keys.market, market_data.len(), MARKET_MIN_LEN);
+
+    let nonce = read_u64(market_data, MARKET_VAULT_SIGNER_NONCE_OFFSET)?;
+    keys.market_bids = read_pubkey(market_data, MARKET_BIDS_OFFSET)?;
+    keys.market_asks = read_pubkey(market_data, MARKET_ASKS_OFFSET)?;
+    keys.market_event_queue = read_pubkey(market_data, MARKET_EVENT_QUEUE_OFFSET)?;
+    keys.market_coin_vault = read_pubkey(market_data, MARKET_COIN_VAULT_OFFSET)?;
+    keys.market_pc_vault = read_pubkey(market_data, MARKET_PC_VAULT_OFFSET)?;
+    keys.market_vault_signer = Pubkey::create_program_address(
+        &[keys.market.as_ref(), &nonce.to_le_bytes()],
+        &keys.market_program,
+    )?;
+    Ok(())
+}
+
+/// swap_base_in: направление задается тем, какой из аккаунтов пользователя источник
+pub fn swap_base_in_ix(
+    keys: &AmmV4Keys,
+    owner: &Pubkey,
+    user_source: &Pubkey,
+    user_destination: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(17);
+    data.push(SWAP_BASE_IN_TAG);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+    Instruction {
+        program_id: *RAYDIUM_V4_PROGRAM,
+        accounts: vec![
+            AccountMeta::new_readonly(*TOKEN_PROGRAM, false),
+            AccountMeta::new(keys.pool, false),
+            AccountMeta::new_readonly(*RAYDIUM_V4_AUTHORITY_KEY, false),
+            AccountMeta::new(keys.open_orders, false),
+            AccountMeta::new(keys.target_orders, false),
+            AccountMeta::new(keys.coin_vault, false),
+            AccountMeta::new(keys.pc_vault, false),
+            AccountMeta::new_readonly(keys.market_program, false),
+            AccountMeta::new(keys.market, false),
+            AccountMeta::new(keys.market_bids, false),
+            AccountMeta::new(keys.market_asks, false),
+            AccountMeta::new(keys.market_event_queue, false),
+            AccountMeta::new(keys.market_coin_vault, false),
+            AccountMeta::new(keys.market_pc_vault, false),
+            AccountMeta::new_readonly(keys.market_vault_signer, false),
+            AccountMeta::new(*user_source, false),
+            AccountMeta::new(*user_destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data,
+    }
+}
+
+// ---------------- CLMM ----------------
+
+// sha256("global:swap_v2")[..8]
+const CLMM_SWAP_V2_DISCRIMINATOR: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+
+const CLMM_TICK_ARRAY_SIZE: i32 = 60;
+
+// Смещения полей PoolState
+const CLMM_AMM_CONFIG_OFFSET: usize = 9;
+const CLMM_TOKEN_MINT_0_OFFSET: usize = 73;
+const CLMM_TOKEN_MINT_1_OFFSET: usize = 105;
+const CLMM_TOKEN_VAULT_0_OFFSET: usize = 137;
+const CLMM_TOKEN_VAULT_1_OFFSET: usize = 169;
+const CLMM_OBSERVATION_OFFSET: usize = 201;
+const CLMM_TICK_SPACING_OFFSET: usize = 235;
+const CLMM_TICK_CURRENT_OFFSET: usize = 269;
+const CLMM_POOL_MIN_LEN: usize = 273;
+
+/// Аккаунты пула Raydium CLMM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClmmKeys {
+    pub pool: Pubkey,
+    pub amm_config: Pubkey,
+    pub mint_0: Pubkey,
+    pub mint_1: Pubkey,
+    pub vault_0: Pubkey,
+    pub vault_1: Pubkey,
+    pub observation: Pubkey,
+    pub token_program_0: Pubkey,
+    pub token_program_1: Pubkey,
+    pub tick_spacing: u16,
+    pub tick_current: i32,
+    /// Инициализированные tick arrays по направлению свапа (заполняет резолвер)
+    pub tick_arrays_zero_for_one: Vec<Pubkey>,
+    pub tick_arrays_one_for_zero: Vec<Pubkey>,
+}
+
+pub fn parse_clmm_pool(pool: Pubkey, data: &[u8]) -> Result<ClmmKeys> {
+    ensure!(data.len() >= CLMM_POOL_MIN_LEN, "CLMM {}: {} байт, ожидалось {}", pool, data.len(), CLMM_POOL_MIN_LEN);
+
+    Ok(ClmmKeys {
+        pool,
+        amm_config: read_pubkey(data, CLMM_AMM_CONFIG_OFFSET)?,
+        mint_0: read_pubkey(data, CLMM_TOKEN_MINT_0_OFFSET)?,
+        mint_1: read_pubkey(data, CLMM_TOKEN_MINT_1_OFFSET)?,
+        vault_0: read_pubkey(data, CLMM_TOKEN_VAULT_0_OFFSET)?,
+        vault_1: read_pubkey(data, CLMM_TOKEN_VAULT_1_OFFSET)?,
+        observation: read_pubkey(data, CLMM_OBSERVATION_OFFSET)?,
+        token_program_0: *TOKEN_PROGRAM,
+        token_program_1: *TOKEN_PROGRAM,
+        tick_spacing: read_u16(data, CLMM_TICK_SPACING_OFFSET)?,
+        tick_current: read_i32(data, CLMM_TICK_CURRENT_OFFSET)?,
+        tick_arrays_zero_for_one: Vec::new(),
+        tick_arrays_one_for_zero: Vec::new(),
+    })
+}
+
+pub fn clmm_tick_array_start_index(tick: i32, tick_spacing: u16, offset: i32) -> i32 {
+    let ticks_in_array = CLMM_TICK_ARRAY_SIZE * tick_spacing as i32;
+    (tick.div_euclid(ticks_in_array) + offset) * ticks_in_array
+}
+
+pub fn clmm_tick_array_address(pool: &Pubkey, start_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"tick_array", pool.as_ref(), &start_index.to_be_bytes()],
+        &RAYDIUM_CLMM_PROGRAM,
+    ).0
+}
+
+pub fn clmm_bitmap_extension_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"pool_tick_array_bitmap_extension", pool.as_ref()],
+        &RAYDIUM_CLMM_PROGRAM,
+    ).0
+}
+
+/// Кандидаты tick arrays от текущего в направлении свапа (резолвер оставляет существующие)
+pub fn clmm_tick_array_candidates(keys: &ClmmKeys, zero_for_one: bool, count: i32) -> Vec<Pubkey> {
+    let step = if zero_for_one { -1 } else { 1 };
+    (0..count)
+        .map(|i| {
+            let start = clmm_tick_array_start_index(keys.tick_current, keys.tick_spacing, i * step);
+            clmm_tick_array_address(&keys.pool, start)
+        })
+        .collect()
+}
+
+/// swap_v2 с точным входом. sqrt_price_limit = 0: программа подставляет крайнюю цену.
+pub fn clmm_swap_v2_ix(
+    keys: &ClmmKeys,
+    owner: &Pubkey,
+    input_account: &Pubkey,
+    output_account: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+    zero_for_one: bool,
+) -> Instruction {
+    let (input_vault, output_vault, input_mint, output_mint) = if zero_for_one {
+        (keys.vault_0, keys.vault_1, keys.mint_0, keys.mint_1)
+    } else {
+        (keys.vault_1, keys.vault_0, keys.mint_1, keys.mint_0)
+    };
+    let tick_arrays = if zero_for_one {
+        &keys.tick_arrays_zero_for_one
+    } else {
+        &keys.tick_arrays_one_for_zero
+    };
+
+    let mut data = Vec::with_capacity(8 + 8 + 8 + 16 + 1);
+    data.extend_from_slice(&CLMM_SWAP_V2_DISCRIMINATOR);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+    data.extend_from_slice(&0u128.to_le_bytes());
+    data.push(1); // is_base_input
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(keys.amm_config, false),
+        AccountMeta::new(keys.pool, false),
+        AccountMeta::new(*input_account, false),
+        AccountMeta::new(*output_account, false),
+        AccountMeta::new(input_vault, false),
+        AccountMeta::new(output_vault, false),
+        AccountMeta::new(keys.observation, false),
+        AccountMeta::new_readonly(*TOKEN_PROGRAM, false),
+        AccountMeta::new_readonly(*TOKEN_2022_PROGRAM, false),
+        AccountMeta::new_readonly(*MEMO_PROGRAM, false),
+        AccountMeta::new_readonly(input_mint, false),
+        AccountMeta::new_readonly(output_mint, false),
+        AccountMeta::new(clmm_bitmap_extension_address(&keys.pool), false),
+    ];
+    accounts.extend(tick_arrays.iter().map(|ta| AccountMeta::new(*ta, false)));
+
+    Instruction {
+        program_id: *RAYDIUM_CLMM_PROGRAM,
+        accounts,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::fixtures::{assert_fixture, instruction_bytes, key, amm_v4_keys, clmm_keys};
+
+    #[test]
+    fn swap_base_in_matches_fixture() {
+        let ix = swap_base_in_ix(&amm_v4_keys(), &key(100), &key(101), &key(102), 1_000_000, 990_000);
+        assert_fixture("raydium_amm_swap_base_in", &instruction_bytes(&ix));
+    }
+
+    #[test]
+    fn clmm_swap_v2_zero_for_one_matches_fixture() {
+        let ix = clmm_swap_v2_ix(&clmm_keys(), &key(100), &key(101), &key(102), 1_000_000, 990_000, true);
+        assert_fixture("raydium_clmm_swap_v2_zero_for_one", &instruction_bytes(&ix));
+    }
+
+    #[test]
+    fn clmm_swap_v2_one_for_zero_matches_fixture() {
+        let ix = clmm_swap_v2_ix(&clmm_keys(), &key(100), &key(102), &key(101), 2_500_000, 2_400_000, false);
+        assert_fixture("raydium_clmm_swap_v2_one_for_zero", &instruction_bytes(&ix));
+    }
+}
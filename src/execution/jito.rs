@@ -0,0 +1,278 @@
+// src/execution/jito.rs
+
+// Клиент block engine Jito: отправка бандлов и отслеживание их статусов.
+// Арбитражная транзакция уже содержит перевод чаевых на tip-аккаунт последней
+// инструкцией (BuildParams::with_tip), бандл из одной такой транзакции
+// отправляется через sendBundle, затем статус опрашивается через
+// getInflightBundleStatuses до Landed / Failed или до потери (Dropped).
+// Записи с итогом хранятся JITO_BUNDLE_RETENTION_SECS: этого хватает трекеру
+// отправок (landing.rs), который читает итог бандла до истечения транзакции.
+//
+// Адрес block engine задается JITO_BLOCK_ENGINE_URL, поэтому клиент можно
+// направить на локальный mock-сервер.
+
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{info, debug, warn};
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use crate::config::{
+    CONFIG, JITO_STATUS_POLL_MS, JITO_BUNDLE_DROP_SECS, JITO_BUNDLE_RETENTION_SECS, JITO_TIP_ACCOUNTS_REFRESH_SECS,
+};
+use crate::data::unix_timestamp;
+use crate::rpc::rpc_call;
+use crate::execution::tx_builder::serialize_transaction;
+
+// Tip-аккаунты mainnet на случай, если getTipAccounts недоступен
+const DEFAULT_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+// getInflightBundleStatuses принимает не более 5 идентификаторов
+const MAX_STATUS_BATCH: usize = 5;
+
+/// Итог бандла
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleOutcome {
+    Pending,
+    Landed { slot: u64 },
+    Failed,
+    /// Block engine больше не знает о бандле, а в блок он не попал
+    Dropped,
+}
+
+/// Запись об отправленном бандле
+#[derive(Debug, Clone)]
+pub struct BundleRecord {
+    pub bundle_id: String,
+    pub signatures: Vec<String>,
+    pub chain_id: Option<usize>,
+    pub tip_lamports: u64,
+    pub submitted_at: u64,
+    pub outcome: BundleOutcome,
+    pub resolved_at: Option<u64>,
+}
+
+pub struct JitoClient {
+    base_url: String,
+    tip_accounts: RwLock<Vec<Pubkey>>,
+    next_tip: AtomicUsize,
+    bundles: DashMap<String, BundleRecord>,
+}
+
+lazy_static! {
+    pub static ref JITO: JitoClient = JitoClient::new(&CONFIG.jito_block_engine_url);
+}
+
+impl JitoClient {
+    pub fn new(base_url: &str) -> Self {
+        let tip_accounts = DEFAULT_TIP_ACCOUNTS
+            .iter()
+            .filter_map(|a| Pubkey::from_str(a).ok())
+            .collect();
+
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            tip_accounts: RwLock::new(tip_accounts),
+            next_tip: AtomicUsize::new(0),
+            bundles: DashMap::new(),
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/api/v1/{}", self.base_url, path)
+    }
+
+    /// JSON-RPC endpoint block engine для одиночных транзакций (sendTransaction)
+    pub fn transactions_url(&self) -> String {
+        self.endpoint("transactions")
+    }
+
+    /// Обновление списка tip-аккаунтов из block engine
+    pub async fn refresh_tip_accounts(&self) -> Result<()> {
+        let result = rpc_call(&self.endpoint("getTipAccounts"), "getTipAccounts", json!([])).await?;
+        let accounts: Vec<Pubkey> = result
+            .as_array()
+            .ok_or_else(|| anyhow!("getTipAccounts: неожиданный формат ответа"))?
+            .iter()
+            .filter_map(|v| v.as_str().and_then(|s| Pubkey::from_str(s).ok()))
+            .collect();
+
+        if accounts.is_empty() {
+            return Err(anyhow!("getTipAccounts: пустой список"));
+        }
+        *self.tip_accounts.write().unwrap() = accounts;
+        Ok(())
+    }
+
+    /// Tip-аккаунт по кругу, чтобы не упираться в блокировку записи одного аккаунта
+    pub fn tip_account(&self) -> Pubkey {
+        let accounts = self.tip_accounts.read().unwrap();
+        let i = self.next_tip.fetch_add(1, Ordering::Relaxed) % accounts.len();
+        accounts[i]
+    }
+
+    /// Отправка бандла. Возвращает идентификатор бандла.
+    /// В режиме paper trading бандл не отправляется и не отслеживается.
+    pub async fn send_bundle(
+        &self,
+        transactions: &[VersionedTransaction],
+        chain_id: Option<usize>,
+        tip_lamports: u64,
+    ) -> Result<String> {
+        #[allow(deprecated)]
+        let encoded: Vec<String> = transactions
+            .iter()
+            .map(|tx| base64::encode(serialize_transaction(tx)))
+            .collect();
+
+        let signatures: Vec<String> = transactions
+            .iter()
+            .filter_map(|tx| tx.signatures.first().map(|s| s.to_string()))
+            .collect();
+
+        if CONFIG.paper_trading {
+            let bundle_id = format!("paper-{}", signatures.first().cloned().unwrap_or_default());
+            debug!("jito: бандл {} отправлен (цепочка {:?}, чаевые {})", bundle_id, chain_id, tip_lamports);
+            return Ok(bundle_id);
+        }
+
+        let result = rpc_call(
+            &self.endpoint("bundles"),
+            "sendBundle",
+            json!([encoded, { "encoding": "base64" }]),
+        ).await?;
+
+        let bundle_id = result
+            .as_str()
+            .ok_or_else(|| anyhow!("sendBundle: неожиданный формат ответа"))?
+            .to_string();
+
+        self.bundles.insert(bundle_id.clone(), BundleRecord {
+            bundle_id: bundle_id.clone(),
+            signatures,
+            chain_id,
+            tip_lamports,
+            submitted_at: unix_timestamp(),
+            outcome: BundleOutcome::Pending,
+            resolved_at: None,
+        });
+
+        debug!("jito: бандл {} отправлен (цепочка {:?}, чаевые {})", bundle_id, chain_id, tip_lamports);
+        Ok(bundle_id)
+    }
+
+    // Итог по статусу из getInflightBundleStatuses
+    fn outcome_from_status(status: &Value, submitted_at: u64, now: u64) -> BundleOutcome {
+        match status["status"].as_str() {
+            Some("Landed") => BundleOutcome::Landed {
+                slot: status["landed_slot"].as_u64().unwrap_or(0),
+            },
+            Some("Failed") => BundleOutcome::Failed,
+            // Pending или Invalid (бандл неизвестен / вышел из окна block engine) дольше таймаута
+            _ if now.saturating_sub(submitted_at) >= JITO_BUNDLE_DROP_SECS => BundleOutcome::Dropped,
+            _ => BundleOutcome::Pending,
+        }
+    }
+
+    /// Один проход опроса статусов ожидающих бандлов
+    pub async fn poll_bundle_statuses(&self) -> Result<()> {
+        let pending: Vec<(String, u64)> = self.bundles
+            .iter()
+            .filter(|r| r.outcome == BundleOutcome::Pending)
+            .map(|r| (r.bundle_id.clone(), r.submitted_at))
+            .collect();
+
+        for batch in pending.chunks(MAX_STATUS_BATCH) {
+            let ids: Vec<&str> = batch.iter().map(|(id, _)| id.as_str()).collect();
+            let result = rpc_call(
+                &self.endpoint("getInflightBundleStatuses"),
+                "getInflightBundleStatuses",
+                json!([ids]),
+            ).await?;
+
+            let statuses = result["value"].as_array().cloned().unwrap_or_default();
+            let now = unix_timestamp();
+
+            for (bundle_id, submitted_at) in batch {
+                let status = statuses
+                    .iter()
+                    .find(|s| s["bundle_id"].as_str() == Some(bundle_id.as_str()))
+                    .cloned()
+                    .unwrap_or(Value::Null);
+
+                let outcome = Self::outcome_from_status(&status, *submitted_at, now);
+                if outcome == BundleOutcome::Pending {
+                    continue;
+                }
+
+                if let Some(mut record) = self.bundles.get_mut(bundle_id) {
+                    match &outcome {
+                        BundleOutcome::Landed { slot } => info!("jito: бандл {} (цепочка {:?}, чаевые {}) попал в слот {}",
+                            bundle_id, record.chain_id, record.tip_lamports, slot),
+                        BundleOutcome::Failed => warn!("jito: бандл {} (цепочка {:?}) завершился ошибкой, транзакции {:?}",
+                            bundle_id, record.chain_id, record.signatures),
+                        BundleOutcome::Dropped => warn!("jito: бандл {} (цепочка {:?}) потерян, транзакции {:?}",
+                            bundle_id, record.chain_id, record.signatures),
+                        BundleOutcome::Pending => {}
+                    }
+                    record.outcome = outcome;
+                    record.resolved_at = Some(now);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn bundle(&self, bundle_id: &str) -> Option<BundleRecord> {
+        self.bundles.get(bundle_id).map(|r| r.clone())
+    }
+
+    /// Удаление записей, итог которых известен дольше JITO_BUNDLE_RETENTION_SECS.
+    /// Бандл без итога (опрос статусов недоступен) удаляется через столько же после
+    /// JITO_BUNDLE_DROP_SECS, когда он в любом случае считался бы потерянным.
+    pub fn prune_resolved(&self) {
+        let oldest = unix_timestamp().saturating_sub(JITO_BUNDLE_RETENTION_SECS);
+        self.bundles.retain(|_, record| match record.resolved_at {
+            Some(resolved_at) => resolved_at >= oldest,
+            None => record.submitted_at + JITO_BUNDLE_DROP_SECS >= oldest,
+        });
+    }
+}
+
+/// Фоновое обновление tip-аккаунтов и опрос статусов бандлов
+pub async fn run_bundle_tracker() {
+    info!("jito: block engine {}", JITO.base_url);
+
+    let mut status_interval = tokio::time::interval(Duration::from_millis(JITO_STATUS_POLL_MS));
+    let mut tips_interval = tokio::time::interval(Duration::from_secs(JITO_TIP_ACCOUNTS_REFRESH_SECS));
+
+    loop {
+        tokio::select! {
+            _ = tips_interval.tick() => {
+                if let Err(e) = JITO.refresh_tip_accounts().await {
+                    warn!("jito: не удалось обновить tip-аккаунты: {}", e);
+                }
+            }
+            _ = status_interval.tick() => {
+                if let Err(e) = JITO.poll_bundle_statuses().await {
+                    debug!("jito: ошибка опроса статусов бандлов: {}", e);
+                }
+                JITO.prune_resolved();
+            }
+        }
+    }
+}
@@ -0,0 +1,392 @@
+// src/execution/jupiter.rs
+
+// Исполнение через локальный jupiter-swap-api (ALLOW_CIRCULAR_ARBITRAGE, см. jupiter-swap-api/README.md).
+// Круговая котировка SOL -> SOL по нашим DEX (/quote), затем /swap-instructions.
+// Из ответа берем только инструкции подготовки, свапа и очистки: compute budget
+// и чаевые ставим свои, как в нативной сборке (tx_builder.rs). Программа Jupiter
+// проверяет выход по quotedOutAmount и slippageBps, поэтому slippageBps котировки
+// снижается так, чтобы минимальный выход покрывал вход плюс затраты: убыточное
+// исполнение откатится.
+//
+// Используется как запасной путь, если нативная сборка маршрута не удалась,
+// и как сверка нашей модели котировок с Jupiter: каждый хоп маршрута котируется
+// прямым свапом на своем DEX, сравниваются только хопы через тот же пул.
+
+use std::str::FromStr;
+use anyhow::{anyhow, ensure, Result};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use serde_json::{json, Value};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::AddressLookupTableAccount;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+#[allow(deprecated)]
+use solana_sdk::system_instruction;
+use crate::config::{
+    CONFIG, JUPITER_DEXES, JUPITER_MAX_ACCOUNTS, JUPITER_DEVIATION_WARN_BPS, SWAP_SLIPPAGE_BPS,
+    MAX_TRANSACTION_SIZE, get_http_client,
+};
+use crate::costs::estimate_costs;
+use crate::data::GLOBAL_DATA;
+use crate::router::{ArbitrageOpportunity, OpportunitySource, RouterEngine, SimulatedHop, estimate_crossings};
+use crate::rpc::get_multiple_accounts;
+use crate::websocket::ws_data::DexType;
+use crate::websocket::ws_parser::PoolCommitment;
+use crate::execution::accounts::WSOL;
+use crate::execution::lookup_tables::parse_lookup_table;
+use crate::execution::tx_builder::{compile_transaction, serialize_transaction, BuildParams};
+
+lazy_static! {
+    // Таблицы поиска Jupiter: содержимое меняется редко, читаем один раз
+    static ref JUPITER_LOOKUP_TABLES: DashMap<Pubkey, AddressLookupTableAccount> = DashMap::new();
+}
+
+/// Инструкции маршрута из /swap-instructions
+#[derive(Debug, Clone)]
+pub struct JupiterInstructions {
+    pub setup: Vec<Instruction>,
+    pub swap: Instruction,
+    pub cleanup: Option<Instruction>,
+    pub lookup_table_addresses: Vec<Pubkey>,
+}
+
+fn api_url(path: &str) -> String {
+    format!("{}/{}", CONFIG.local_api_host.trim_end_matches('/'), path)
+}
+
+// Тип DEX по метке Jupiter (котировка ограничена JUPITER_DEXES)
+fn dex_from_label(label: &str) -> Option<DexType> {
+    if label.contains("Whirlpool") || label.contains("Orca") {
+        Some(DexType::Orca)
+    } else if label.contains("Raydium") {
+        Some(DexType::Raydium)
+    } else if label.contains("Meteora") {
+        Some(DexType::Meteora)
+    } else {
+        None
+    }
+}
+
+fn symbol_of(mint: &Pubkey) -> String {
+    GLOBAL_DATA.token_addresses
+        .get(mint)
+        .map(|t| t.symbol.clone())
+        .unwrap_or_else(|| mint.to_string())
+}
+
+fn parse_pubkey(value: &Value, field: &str) -> Result<Pubkey> {
+    let s = value[field].as_str().ok_or_else(|| anyhow!("jupiter: нет поля {}", field))?;
+    Ok(Pubkey::from_str(s)?)
+}
+
+fn parse_amount(value: &Value, field: &str) -> Result<u64> {
+    value[field]
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| anyhow!("jupiter: нет поля {}", field))
+}
+
+/// Круговая котировка SOL -> SOL на `amount_in` лампортов
+pub async fn circular_quote(amount_in: u64) -> Result<Value> {
+    circular_quote_for(&WSOL, amount_in).await
+}
+
+/// Круговая котировка `mint` -> `mint` на `amount_in` в единицах токена
+pub async fn circular_quote_for(mint: &Pubkey, amount_in: u64) -> Result<Value> {
+    fetch_quote(mint, mint, amount_in, JUPITER_DEXES, false).await
+}
+
+// Метки Jupiter для DEX
+fn dex_labels(dex: DexType) -> &'static str {
+    match dex {
+        DexType::Orca => "Whirlpool",
+        DexType::Raydium => "Raydium,Raydium CLMM",
+        DexType::Meteora => "Meteora DLMM",
+    }
+}
+
+// Котировка /quote `input_mint` -> `output_mint` по DEX `dexes`
+async fn fetch_quote(input_mint: &Pubkey, output_mint: &Pubkey, amount_in: u64, dexes: &str, only_direct: bool) -> Result<Value> {
+    let input_mint = input_mint.to_string();
+    let output_mint = output_mint.to_string();
+    let amount = amount_in.to_string();
+    let slippage = SWAP_SLIPPAGE_BPS.to_string();
+    let max_accounts = JUPITER_MAX_ACCOUNTS.to_string();
+
+    let response = get_http_client()
+        .get(api_url("quote"))
+        .query(&[
+            ("inputMint", input_mint.as_str()),
+            ("outputMint", output_mint.as_str()),
+            ("amount", amount.as_str()),
+            ("slippageBps", slippage.as_str()),
+            ("swapMode", "ExactIn"),
+            ("dexes", dexes),
+            ("onlyDirectRoutes", if only_direct { "true" } else { "false" }),
+            ("maxAccounts", max_accounts.as_str()),
+        ])
+        .send()
+        .await?;
+
+    let status = response.status();
+    let quote: Value = response.json().await?;
+    ensure!(status.is_success(), "jupiter /quote: {} {}", status, quote);
+    Ok(quote)
+}
+
+/// Возможность по круговой котировке. `chain_id` - идентификатор, под которым ведется
+/// здоровье маршрута (для маршрутов Jupiter - JUPITER_CHAIN_ID).
+pub fn opportunity_from_quote(quote: &Value, chain_id: usize) -> Result<ArbitrageOpportunity> {
+    let amount_in = parse_amount(quote, "inAmount")?;
+    let amount_out = parse_amount(quote, "outAmount")?;
+    let slot = quote["contextSlot"].as_u64().unwrap_or(0);
+
+    let plan = quote["routePlan"]
+        .as_array()
+        .ok_or_else(|| anyhow!("jupiter: в котировке нет routePlan"))?;
+    ensure!(!plan.is_empty(), "jupiter: пустой routePlan");
+
+    let mut hops = Vec::with_capacity(plan.len());
+    for step in plan {
+        let info = &step["swapInfo"];
+        let label = info["label"].as_str().unwrap_or_default();
+        let dex = dex_from_label(label).ok_or_else(|| anyhow!("jupiter: неподдерживаемый DEX {}", label))?;
+        let input_mint = parse_pubkey(info, "inputMint")?;
+        let output_mint = parse_pubkey(info, "outputMint")?;
+
+        let pool_address = parse_pubkey(info, "ammKey")?;
+        let hop_amount_in = parse_amount(info, "inAmount")?;
+        // Пересечения тиков оцениваем по нашему состоянию пула, если оно есть
+        let crossings = RouterEngine::current_pool_state(PoolCommitment::Processed, dex, &pool_address)
+            .map(|(state, _)| estimate_crossings(&state, &input_mint, hop_amount_in))
+            .unwrap_or(0);
+
+        hops.push(SimulatedHop {
+            dex,
+            pool_address,
+            from_token: symbol_of(&input_mint),
+            to_token: symbol_of(&output_mint),
+            input_mint,
+            output_mint,
+            amount_in: hop_amount_in,
+            amount_out: parse_amount(info, "outAmount")?,
+            pool_slot: slot,
+            crossings,
+        });
+    }
+
+    let mut chain: Vec<String> = hops.iter().map(|h| h.from_token.clone()).collect();
+    chain.push(hops[hops.len() - 1].to_token.clone());
+
+    let expected_profit = amount_out as i64 - amount_in as i64;
+    let costs = estimate_costs(&hops, expected_profit);
+
+    Ok(ArbitrageOpportunity {
+        chain_id,
+        chain,
+        commitment: PoolCommitment::Processed,
+        source: OpportunitySource::Jupiter,
+        amount_in,
+        amount_out,
+        expected_profit,
+        costs,
+        net_profit: expected_profit.saturating_sub(costs.total() as i64),
+        hops,
+    })
+}
+
+/// Сверка нашей модели с Jupiter по хопам маршрута: каждый хоп котируется прямым свапом
+/// на своем DEX с тем же входом. Сравниваются только хопы, которые Jupiter провел через
+/// тот же пул. Возвращает наибольшее по модулю расхождение выхода в bps (None - сравнить не с чем).
+pub async fn cross_check(opportunity: &ArbitrageOpportunity) -> Result<Option<i64>> {
+    let mut max_deviation: Option<i64> = None;
+
+    for hop in &opportunity.hops {
+        let quote = fetch_quote(&hop.input_mint, &hop.output_mint, hop.amount_in, dex_labels(hop.dex), true).await?;
+        let plan = quote["routePlan"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let same_pool = plan.len() == 1
+            && parse_pubkey(&plan[0]["swapInfo"], "ammKey").ok() == Some(hop.pool_address);
+        if !same_pool {
+            debug!("jupiter: маршрут [{}]: хоп {}->{} Jupiter провел не через пул {}",
+                opportunity.chain_id, hop.from_token, hop.to_token, hop.pool_address);
+            continue;
+        }
+
+        let jupiter_out = parse_amount(&quote, "outAmount")?;
+        let deviation_bps = (hop.amount_out as i64 - jupiter_out as i64)
+            .saturating_mul(10_000) / jupiter_out.max(1) as i64;
+
+        if deviation_bps.abs() >= JUPITER_DEVIATION_WARN_BPS {
+            warn!("jupiter: маршрут [{}], пул {}: наш выход {} против {} у Jupiter ({} bps)",
+                opportunity.chain_id, hop.pool_address, hop.amount_out, jupiter_out, deviation_bps);
+        } else {
+            debug!("jupiter: маршрут [{}], пул {}: расхождение с Jupiter {} bps",
+                opportunity.chain_id, hop.pool_address, deviation_bps);
+        }
+        if max_deviation.is_none_or(|max| deviation_bps.abs() > max.abs()) {
+            max_deviation = Some(deviation_bps);
+        }
+    }
+    Ok(max_deviation)
+}
+
+/// Инструкция из JSON формата Jupiter
+pub fn parse_instruction(value: &Value) -> Result<Instruction> {
+    let accounts = value["accounts"]
+        .as_array()
+        .ok_or_else(|| anyhow!("jupiter: инструкция без accounts"))?
+        .iter()
+        .map(|a| Ok(AccountMeta {
+            pubkey: parse_pubkey(a, "pubkey")?,
+            is_signer: a["isSigner"].as_bool().unwrap_or(false),
+            is_writable: a["isWritable"].as_bool().unwrap_or(false),
+        }))
+        .collect::<Result<Vec<_>>>()?;
+
+    let data = value["data"].as_str().ok_or_else(|| anyhow!("jupiter: инструкция без data"))?;
+    #[allow(deprecated)]
+    let data = base64::decode(data)?;
+
+    Ok(Instruction {
+        program_id: parse_pubkey(value, "programId")?,
+        accounts,
+        data,
+    })
+}
+
+/// Наибольший slippage (bps, не выше котировочного), при котором минимальный выход
+/// `out_amount * (10_000 - slippage) / 10_000` не меньше `min_amount_out`
+pub fn break_even_slippage_bps(out_amount: u64, quoted_slippage_bps: u64, min_amount_out: u64) -> Result<u64> {
+    ensure!(out_amount > 0 && out_amount >= min_amount_out,
+        "jupiter: выход {} не покрывает минимальный {}", out_amount, min_amount_out);
+    let headroom = (out_amount - min_amount_out) as u128 * 10_000 / out_amount as u128;
+    Ok((headroom as u64).min(quoted_slippage_bps))
+}
+
+/// /swap-instructions по котировке. slippageBps снижается так, чтобы минимальный выход
+/// свапа был не меньше `min_amount_out`.
+pub async fn swap_instructions(quote: &Value, user: &Pubkey, min_amount_out: u64) -> Result<JupiterInstructions> {
+    let mut quote = quote.clone();
+    let out_amount = parse_amount(&quote, "outAmount")?;
+    let quoted_slippage = quote["slippageBps"].as_u64().unwrap_or(SWAP_SLIPPAGE_BPS);
+    let slippage_bps = break_even_slippage_bps(out_amount, quoted_slippage, min_amount_out)?;
+    let threshold = (out_amount as u128 * (10_000 - slippage_bps) as u128 / 10_000) as u64;
+    quote["slippageBps"] = json!(slippage_bps);
+    quote["otherAmountThreshold"] = json!(threshold.to_string());
+
+    let response = get_http_client()
+        .post(api_url("swap-instructions"))
+        .json(&json!({
+            "quoteResponse": quote,
+            "userPublicKey": user.to_string(),
+            // Вход и выход на нашем wSOL ATA (wallet.rs держит его пополненным)
+            "wrapAndUnwrapSol": false,
+            // Круговой маршрут не поддерживается общими аккаунтами программы Jupiter
+            "useSharedAccounts": false,
+        }))
+        .send()
+        .await?;
+
+    let status = response.status();
+    let body: Value = response.json().await?;
+    ensure!(status.is_success(), "jupiter /swap-instructions: {} {}", status, body);
+
+    let setup = body["setupInstructions"]
+        .as_array()
+        .map(|ixs| ixs.iter().map(parse_instruction).collect::<Result<Vec<_>>>())
+        .transpose()?
+        .unwrap_or_default();
+    let cleanup = (!body["cleanupInstruction"].is_null())
+        .then(|| parse_instruction(&body["cleanupInstruction"]))
+        .transpose()?;
+    let lookup_table_addresses = body["addressLookupTableAddresses"]
+        .as_array()
+        .map(|a| a.iter().filter_map(|v| v.as_str().and_then(|s| Pubkey::from_str(s).ok())).collect())
+        .unwrap_or_default();
+
+    Ok(JupiterInstructions {
+        setup,
+        swap: parse_instruction(&body["swapInstruction"])?,
+        cleanup,
+        lookup_table_addresses,
+    })
+}
+
+/// Таблицы поиска маршрута Jupiter (с кешем)
+pub async fn jupiter_lookup_tables(rpc_url: &str, addresses: &[Pubkey]) -> Result<Vec<AddressLookupTableAccount>> {
+    let missing: Vec<Pubkey> = addresses.iter().filter(|a| !JUPITER_LOOKUP_TABLES.contains_key(a)).copied().collect();
+    if !missing.is_empty() {
+        let accounts = get_multiple_accounts(rpc_url, &missing).await?;
+        for (address, account) in missing.iter().zip(accounts) {
+            let account = account.ok_or_else(|| anyhow!("jupiter: таблица {} не найдена", address))?;
+            let state = parse_lookup_table(*address, &account.data, &Pubkey::default())?;
+            JUPITER_LOOKUP_TABLES.insert(*address, state.account);
+        }
+    }
+
+    Ok(addresses.iter().filter_map(|a| JUPITER_LOOKUP_TABLES.get(a).map(|t| t.clone())).collect())
+}
+
+/// Инструкции транзакции: наш compute budget, подготовка, свап, очистка, чаевые
+pub fn build_jupiter_instructions(route: &JupiterInstructions, owner: &Pubkey, params: BuildParams) -> Vec<Instruction> {
+    let mut instructions = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(params.compute_unit_limit),
+        ComputeBudgetInstruction::set_compute_unit_price(params.compute_unit_price),
+    ];
+    instructions.extend(route.setup.iter().cloned());
+    instructions.push(route.swap.clone());
+    instructions.extend(route.cleanup.iter().cloned());
+
+    if let Some((tip_account, lamports)) = params.tip {
+        instructions.push(system_instruction::transfer(owner, &tip_account, lamports));
+    }
+    instructions
+}
+
+/// Компиляция с таблицами Jupiter и проверкой размера
+pub fn compile_jupiter_transaction(
+    payer: &Keypair,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    let tx = compile_transaction(payer, instructions, lookup_tables, recent_blockhash)?;
+    let size = serialize_transaction(&tx).len();
+    ensure!(size <= MAX_TRANSACTION_SIZE,
+        "jupiter: транзакция {} байт превышает лимит {}", size, MAX_TRANSACTION_SIZE);
+    Ok(tx)
+}
+
+/// Полный путь Jupiter: котировка, инструкции, таблицы и транзакция.
+/// Чаевые - по оценке затрат котировки (0, если Jito выключен). Возвращает возможность по котировке, инструкции и подписанную транзакцию.
+pub async fn build_jupiter_route(
+    rpc_url: &str,
+    payer: &Keypair,
+    chain_id: usize,
+    amount_in: u64,
+    tip_account: Pubkey,
+    recent_blockhash: Hash,
+) -> Result<(ArbitrageOpportunity, Vec<Instruction>, VersionedTransaction)> {
+    let quote = circular_quote(amount_in).await?;
+    let opportunity = opportunity_from_quote(&quote, chain_id)?;
+
+    let break_even = opportunity.amount_in.saturating_add(opportunity.costs.total());
+    ensure!(opportunity.amount_out > break_even,
+        "jupiter: выход {} не покрывает вход и затраты {}", opportunity.amount_out, break_even);
+
+    let owner = payer.pubkey();
+    let route = swap_instructions(&quote, &owner, break_even).await?;
+    let lookup_tables = jupiter_lookup_tables(rpc_url, &route.lookup_table_addresses).await?;
+
+    let params = BuildParams::for_opportunity(&opportunity).with_tip(tip_account, opportunity.costs.jito_tip);
+    let instructions = build_jupiter_instructions(&route, &owner, params);
+    let tx = compile_jupiter_transaction(payer, &instructions, &lookup_tables, recent_blockhash)?;
+
+    Ok((opportunity, instructions, tx))
+}
@@ -0,0 +1,292 @@
+// src/execution/landing.rs
+
+// Отслеживание отправленных транзакций до подтверждения или истечения.
+// Исполнитель регистрирует каждую отправленную транзакцию (и бандл, если она ушла
+// через Jito). Трекер опрашивает getSignatureStatuses; для подтвержденной транзакции
+// загружает getTransaction и по pre/post балансам считает фактическую прибыль:
+// изменение стартового токена на токен-аккаунтах кошелька плюс изменение SOL плательщика
+// (комиссия, чаевые и рента уже в нем). Цепочки начинаются в SOL, поэтому обе части в лампортах.
+// Итог записывается в журнал сделок (ledger.rs) и передается риск-лимитам (risk.rs),
+// расход CU по логам - в историю compute_units.rs.
+
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{info, debug, warn};
+use serde_json::Value;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::hash::Hash;
+use solana_sdk::transaction::VersionedTransaction;
+use crate::config::{CONFIG, LANDING_POLL_MS, LANDING_EXPIRY_SECS, LEDGER_REPORT_INTERVAL_SECS};
+use crate::compute_units::{CuKey, COMPUTE_UNITS};
+use crate::data::unix_timestamp;
+use crate::ledger::{LedgerEntry, TradeStatus, LEDGER, report};
+use crate::router::ArbitrageOpportunity;
+use crate::rpc::{get_signature_statuses, get_transaction};
+use crate::execution::blockhash::BLOCKHASH_CACHE;
+use crate::execution::jito::{BundleOutcome, JITO};
+use crate::execution::risk::RISK;
+use crate::execution::sender::FANOUT;
+use crate::execution::simulation::{classify_failure, failed_instruction_index};
+
+// getSignatureStatuses принимает не более 256 подписей
+const MAX_STATUS_BATCH: usize = 256;
+
+/// Отправленная транзакция, ожидающая итога
+#[derive(Debug, Clone)]
+pub struct PendingTrade {
+    pub signature: String,
+    pub bundle_id: Option<String>,
+    pub opportunity: ArbitrageOpportunity,
+    pub simulated_profit: i64,
+    pub owner: Pubkey,
+    pub sent_at: u64,
+    /// Ключи истории CU инструкций транзакции (compute_units.rs)
+    pub cu_keys: Vec<Option<CuKey>>,
+    /// Blockhash транзакции: после его истечения транзакция в блок уже не попадет
+    pub blockhash: Hash,
+    sent: Instant,
+    /// Слот, если транзакция уже видна в блоке, но getTransaction еще ее не отдает
+    landed_slot: Option<u64>,
+}
+
+/// Фактический результат транзакции по ее метаданным
+#[derive(Debug, Clone)]
+pub struct TradeOutcome {
+    pub realized_profit: i64,
+    pub fees_paid: u64,
+    pub failure: Option<String>,
+}
+
+#[derive(Default)]
+pub struct LandingTracker {
+    pending: DashMap<String, PendingTrade>,
+}
+
+lazy_static! {
+    pub static ref LANDING: LandingTracker = LandingTracker::default();
+}
+
+impl LandingTracker {
+    /// Регистрация отправленной транзакции
+    pub fn track(
+        &self,
+        tx: &VersionedTransaction,
+        bundle_id: Option<String>,
+        opportunity: &ArbitrageOpportunity,
+        simulated_profit: i64,
+        cu_keys: Vec<Option<CuKey>>,
+    ) {
+        let signature = tx.signatures[0].to_string();
+        self.pending.insert(signature.clone(), PendingTrade {
+            signature,
+            bundle_id,
+            opportunity: opportunity.clone(),
+            simulated_profit,
+            // Плательщик - первый аккаунт транзакции
+            owner: tx.message.static_account_keys()[0],
+            sent_at: unix_timestamp(),
+            cu_keys,
+            blockhash: *tx.message.recent_blockhash(),
+            sent: Instant::now(),
+            landed_slot: None,
+        });
+    }
+
+    // Итог записывается в журнал, транзакция снимается с отслеживания
+    fn resolve(&self, signature: &str, status: TradeStatus, slot: Option<u64>, outcome: TradeOutcome) {
+        let Some((_, trade)) = self.pending.remove(signature) else {
+            return;
+        };
+        let opportunity = &trade.opportunity;
+        let pools: Vec<Pubkey> = opportunity.hops.iter().map(|h| h.pool_address).collect();
+
+        info!("landing: [{}] {} {:?}: прибыль ожидаемая {} / по симуляции {} / фактическая {}, комиссии {}{}",
+            opportunity.chain_id, trade.signature, status, opportunity.net_profit, trade.simulated_profit,
+            outcome.realized_profit, outcome.fees_paid,
+            outcome.failure.as_ref().map(|f| format!(", причина: {}", f)).unwrap_or_default());
+
+        LEDGER.record(LedgerEntry {
+            signature: trade.signature,
+            bundle_id: trade.bundle_id,
+            chain_id: opportunity.chain_id,
+            chain: opportunity.chain.clone(),
+            source: format!("{:?}", opportunity.source),
+            amount_in: opportunity.amount_in,
+            expected_profit: opportunity.net_profit,
+            simulated_profit: trade.simulated_profit,
+            realized_profit: outcome.realized_profit,
+            fees_paid: outcome.fees_paid,
+            status,
+            slot,
+            failure: outcome.failure,
+            sent_at: trade.sent_at,
+            resolved_at: unix_timestamp(),
+        });
+        RISK.record_outcome(status, &pools);
+    }
+
+    // Транзакция не попала в блок: без прибыли и комиссий
+    fn expire(&self, signature: &str, reason: String) {
+        self.resolve(signature, TradeStatus::Expired, None, TradeOutcome {
+            realized_profit: 0,
+            fees_paid: 0,
+            failure: Some(reason),
+        });
+    }
+
+    /// Один проход опроса всех ожидающих транзакций
+    pub async fn poll(&self, rpc_url: &str) -> Result<()> {
+        let trades: Vec<PendingTrade> = self.pending.iter().map(|t| t.clone()).collect();
+
+        for chunk in trades.chunks(MAX_STATUS_BATCH) {
+            let signatures: Vec<String> = chunk.iter().map(|t| t.signature.clone()).collect();
+            let statuses = get_signature_statuses(rpc_url, &signatures).await?;
+
+            for (trade, status) in chunk.iter().zip(statuses) {
+                let slot = match status {
+                    Some(status) if matches!(status["confirmationStatus"].as_str(), Some("confirmed") | Some("finalized")) => {
+                        status["slot"].as_u64()
+                    }
+                    _ => trade.landed_slot,
+                };
+
+                match slot {
+                    Some(slot) => self.settle(rpc_url, trade, slot).await,
+                    None => self.check_expiry(rpc_url, trade).await,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Подтвержденная транзакция: разбор метаданных
+    async fn settle(&self, rpc_url: &str, trade: &PendingTrade, slot: u64) {
+        match get_transaction(rpc_url, &trade.signature).await {
+            Ok(Some(tx)) => {
+                let outcome = trade_outcome(&tx, trade);
+                COMPUTE_UNITS.record_execution(&trade.cu_keys, &transaction_logs(&tx), failed_instruction_index(&tx["meta"]["err"]));
+                let status = if outcome.failure.is_some() { TradeStatus::Failed } else { TradeStatus::Landed };
+                self.resolve(&trade.signature, status, Some(slot), outcome);
+            }
+            Ok(None) => {
+                if let Some(mut pending) = self.pending.get_mut(&trade.signature) {
+                    pending.landed_slot = Some(slot);
+                }
+            }
+            Err(e) => debug!("landing: ошибка getTransaction {}: {}", trade.signature, e),
+        }
+    }
+
+    // Неподтвержденная транзакция: отказ бандла или истечение blockhash.
+    // Оценка высоты блока в кеше завышена на слоты без блоков, поэтому истечение
+    // подтверждается по getBlockHeight и последней проверкой статуса подписи.
+    async fn check_expiry(&self, rpc_url: &str, trade: &PendingTrade) {
+        if let Some(bundle_id) = &trade.bundle_id {
+            match JITO.bundle(bundle_id).map(|b| b.outcome) {
+                Some(BundleOutcome::Failed) => return self.expire(&trade.signature, "бандл отклонен".to_string()),
+                Some(BundleOutcome::Dropped) => return self.expire(&trade.signature, "бандл потерян".to_string()),
+                _ => {}
+            }
+        }
+
+        // По высоте блока, если blockhash известен кешу, иначе по времени
+        let expired = match BLOCKHASH_CACHE.is_expired(&trade.blockhash) {
+            Some(true) => match BLOCKHASH_CACHE.confirm_expired(rpc_url, &trade.blockhash).await {
+                // Кеш забыл blockhash между проверками - он уже истек
+                Ok(confirmed) => confirmed.unwrap_or(true),
+                Err(e) => {
+                    debug!("landing: ошибка getBlockHeight для {}: {}", trade.signature, e);
+                    return;
+                }
+            },
+            Some(false) => false,
+            None => trade.sent.elapsed() >= Duration::from_secs(LANDING_EXPIRY_SECS),
+        };
+        if !expired {
+            return;
+        }
+
+        // Транзакция могла попасть в блок после опроса статусов: итог дождется следующего прохода
+        match get_signature_statuses(rpc_url, std::slice::from_ref(&trade.signature)).await {
+            Ok(statuses) if statuses.first().is_some_and(|s| s.is_some()) => {
+                debug!("landing: {} видна узлу после истечения blockhash, ждем подтверждения", trade.signature);
+            }
+            Ok(_) => self.expire(&trade.signature, "не подтверждена до истечения blockhash".to_string()),
+            Err(e) => debug!("landing: ошибка повторной проверки статуса {}: {}", trade.signature, e),
+        }
+    }
+}
+
+// Логи транзакции из метаданных getTransaction
+fn transaction_logs(tx: &Value) -> Vec<String> {
+    tx["meta"]["logMessages"]
+        .as_array()
+        .map(|logs| logs.iter().filter_map(|l| l.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+// Сумма балансов токена `mint` на аккаунтах владельца `owner` из pre/postTokenBalances
+fn owner_token_amount(balances: &Value, owner: &str, mint: &str) -> i64 {
+    balances
+        .as_array()
+        .map(|balances| {
+            balances
+                .iter()
+                .filter(|b| b["owner"].as_str() == Some(owner) && b["mint"].as_str() == Some(mint))
+                .filter_map(|b| b["uiTokenAmount"]["amount"].as_str()?.parse::<i64>().ok())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Фактический результат по метаданным getTransaction
+pub fn trade_outcome(tx: &Value, trade: &PendingTrade) -> TradeOutcome {
+    let meta = &tx["meta"];
+    let owner = trade.owner.to_string();
+    let start_mint = trade.opportunity.hops.first().map(|h| h.input_mint.to_string()).unwrap_or_default();
+
+    let token_delta = owner_token_amount(&meta["postTokenBalances"], &owner, &start_mint)
+        - owner_token_amount(&meta["preTokenBalances"], &owner, &start_mint);
+    // Плательщик - первый аккаунт транзакции
+    let lamports_delta = meta["postBalances"][0].as_i64().unwrap_or(0) - meta["preBalances"][0].as_i64().unwrap_or(0);
+
+    let fee = meta["fee"].as_u64().unwrap_or(0);
+    let failure = (!meta["err"].is_null())
+        .then(|| format!("{:?}: {}", classify_failure(&meta["err"], &transaction_logs(tx)), meta["err"]));
+    // Чаевые переводятся только при успешном исполнении
+    let tip = if failure.is_none() { trade.opportunity.costs.jito_tip } else { 0 };
+
+    TradeOutcome {
+        realized_profit: token_delta + lamports_delta,
+        fees_paid: fee + tip,
+        failure,
+    }
+}
+
+/// Запуск трекера отправленных транзакций
+pub async fn run_landing_tracker() {
+    let rpc_url = CONFIG.solana_rpc_url.clone();
+    info!("landing: запуск трекера отправленных транзакций");
+
+    let mut poll_interval = tokio::time::interval(Duration::from_millis(LANDING_POLL_MS));
+    let mut report_interval = tokio::time::interval(Duration::from_secs(LEDGER_REPORT_INTERVAL_SECS));
+
+    loop {
+        tokio::select! {
+            _ = poll_interval.tick() => {
+                if LANDING.pending.is_empty() {
+                    continue;
+                }
+                if let Err(e) = LANDING.poll(&rpc_url).await {
+                    warn!("landing: ошибка опроса статусов: {}", e);
+                }
+            }
+            _ = report_interval.tick() => {
+                report();
+                FANOUT.report();
+            }
+        }
+    }
+}
@@ -0,0 +1,312 @@
+// src/execution/lookup_tables.rs
+
+// Менеджер собственных address lookup tables (ALT).
+// Маршрут из 4-5 хопов по Whirlpool/CLMM/DLMM не помещается в лимит аккаунтов
+// legacy транзакции, поэтому все аккаунты пулов из dex_pools (пул, хранилища,
+// tick/bin arrays, программы) складываются в наши таблицы, а сборщик компилирует
+// маршрут в v0 сообщение с подходящими таблицами.
+//
+// Адреса созданных таблиц сохраняются в LOOKUP_TABLES_FILE, чтобы после
+// перезапуска продолжать расширять те же таблицы, а не платить ренту за новые.
+
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use hashbrown::{HashMap, HashSet};
+use lazy_static::lazy_static;
+use log::{info, debug, warn, error};
+use solana_program::pubkey::Pubkey;
+use solana_program::instruction::Instruction;
+use solana_sdk::message::AddressLookupTableAccount;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+#[allow(deprecated)]
+use solana_sdk::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use crate::config::{
+    CONFIG, LOOKUP_TABLES_FILE, LOOKUP_TABLE_SYNC_INTERVAL_SECS, LOOKUP_TABLE_EXTEND_BATCH,
+    MAX_LOOKUP_TABLES, MAX_ROUTE_LOOKUP_TABLES,
+};
+use crate::data::GLOBAL_DATA;
+use crate::rpc::{get_multiple_accounts, get_slot, send_transaction, confirm_signature};
+use crate::wallet::PAYER;
+use crate::execution::accounts::read_pubkey;
+use crate::execution::blockhash::BLOCKHASH_CACHE;
+use crate::execution::pool_keys::{resolve_pool_keys, POOL_KEYS};
+use crate::execution::tx_builder::{compile_transaction, serialize_transaction};
+
+// Формат аккаунта таблицы: 56 байт метаданных, затем адреса по 32 байта
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+const DEACTIVATION_SLOT_OFFSET: usize = 4;
+const AUTHORITY_OPTION_OFFSET: usize = 21;
+const AUTHORITY_OFFSET: usize = 22;
+const LOOKUP_TABLE_MAX_ADDRESSES: usize = 256;
+
+// Сколько пулов разрешаем за один запрос
+const RESOLVE_CHUNK: usize = 50;
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Состояние таблицы в сети
+#[derive(Debug, Clone)]
+pub struct LookupTableState {
+    pub account: AddressLookupTableAccount,
+    /// Таблица наша и не деактивирована: ее можно расширять
+    pub extendable: bool,
+}
+
+/// Набор таблиц и индекс "адрес -> таблица"
+#[derive(Debug, Default)]
+pub struct LookupTableManager {
+    tables: RwLock<Vec<LookupTableState>>,
+    index: DashMap<Pubkey, usize>,
+}
+
+lazy_static! {
+    pub static ref LOOKUP_TABLES: LookupTableManager = LookupTableManager::default();
+}
+
+/// Разбор аккаунта таблицы
+pub fn parse_lookup_table(address: Pubkey, data: &[u8], authority: &Pubkey) -> Result<LookupTableState> {
+    if data.len() < LOOKUP_TABLE_META_SIZE {
+        return Err(anyhow!("таблица {}: {} байт, меньше заголовка", address, data.len()));
+    }
+
+    let deactivation_slot = u64::from_le_bytes(data[DEACTIVATION_SLOT_OFFSET..DEACTIVATION_SLOT_OFFSET + 8].try_into()?);
+    let table_authority = (data[AUTHORITY_OPTION_OFFSET] == 1)
+        .then(|| read_pubkey(data, AUTHORITY_OFFSET))
+        .transpose()?;
+
+    let addresses = data[LOOKUP_TABLE_META_SIZE..]
+        .chunks_exact(32)
+        .map(|chunk| Pubkey::new_from_array(chunk.try_into().unwrap()))
+        .collect();
+
+    Ok(LookupTableState {
+        account: AddressLookupTableAccount { key: address, addresses },
+        extendable: deactivation_slot == u64::MAX && table_authority.as_ref() == Some(authority),
+    })
+}
+
+impl LookupTableManager {
+    /// Добавляет или заменяет таблицу и обновляет индекс
+    pub fn install(&self, state: LookupTableState) {
+        let mut tables = self.tables.write().unwrap();
+        let position = match tables.iter().position(|t| t.account.key == state.account.key) {
+            Some(i) => {
+                tables[i] = state;
+                i
+            }
+            None => {
+                tables.push(state);
+                tables.len() - 1
+            }
+        };
+        for address in &tables[position].account.addresses {
+            self.index.entry(*address).or_insert(position);
+        }
+    }
+
+    pub fn contains(&self, address: &Pubkey) -> bool {
+        self.index.contains_key(address)
+    }
+
+    pub fn table_addresses(&self) -> Vec<Pubkey> {
+        self.tables.read().unwrap().iter().map(|t| t.account.key).collect()
+    }
+
+    /// Таблица, которую можно расширить, и свободное место в ней
+    fn extendable_table(&self) -> Option<(Pubkey, usize)> {
+        self.tables.read().unwrap()
+            .iter()
+            .filter(|t| t.extendable)
+            .map(|t| (t.account.key, LOOKUP_TABLE_MAX_ADDRESSES - t.account.addresses.len().min(LOOKUP_TABLE_MAX_ADDRESSES)))
+            .find(|(_, free)| *free > 0)
+    }
+
+    /// Таблицы для набора инструкций: по убыванию числа покрываемых аккаунтов,
+    /// не более MAX_ROUTE_LOOKUP_TABLES
+    pub fn tables_for(&self, instructions: &[Instruction]) -> Vec<AddressLookupTableAccount> {
+        let mut coverage: HashMap<usize, usize> = HashMap::new();
+        let mut seen: HashSet<Pubkey> = HashSet::new();
+
+        for ix in instructions {
+            for meta in ix.accounts.iter().filter(|m| !m.is_signer) {
+                if seen.insert(meta.pubkey) {
+                    if let Some(table) = self.index.get(&meta.pubkey) {
+                        *coverage.entry(*table).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        let mut ranked: Vec<(usize, usize)> = coverage.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let tables = self.tables.read().unwrap();
+        ranked
+            .into_iter()
+            .take(MAX_ROUTE_LOOKUP_TABLES)
+            .filter_map(|(i, _)| tables.get(i).map(|t| t.account.clone()))
+            .collect()
+    }
+}
+
+// Сохраненные адреса таблиц
+fn load_table_addresses() -> Vec<Pubkey> {
+    let content = match std::fs::read_to_string(LOOKUP_TABLES_FILE) {
+        Ok(content) => content,
+        Err(_) => return Vec::new(),
+    };
+    serde_json::from_str::<Vec<String>>(&content)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|s| Pubkey::from_str(s).ok())
+        .collect()
+}
+
+fn save_table_addresses(addresses: &[Pubkey]) {
+    let list: Vec<String> = addresses.iter().map(|a| a.to_string()).collect();
+    match serde_json::to_string_pretty(&list) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(LOOKUP_TABLES_FILE, json) {
+                warn!("alt: не удалось сохранить {}: {}", LOOKUP_TABLES_FILE, e);
+            }
+        }
+        Err(e) => warn!("alt: не удалось сериализовать список таблиц: {}", e),
+    }
+}
+
+// Перечитывает таблицы из сети
+async fn refresh_tables(rpc_url: &str, addresses: &[Pubkey], authority: &Pubkey) -> Result<()> {
+    let accounts = get_multiple_accounts(rpc_url, addresses).await?;
+    for (address, account) in addresses.iter().zip(accounts) {
+        match account {
+            Some(account) => LOOKUP_TABLES.install(parse_lookup_table(*address, &account.data, authority)?),
+            None => warn!("alt: таблица {} не найдена", address),
+        }
+    }
+    Ok(())
+}
+
+// Отправка служебной транзакции с подтверждением
+async fn send_and_confirm(rpc_url: &str, payer: &Keypair, instruction: Instruction) -> Result<String> {
+    let blockhash = BLOCKHASH_CACHE.get(rpc_url).await?.blockhash;
+    let tx = compile_transaction(payer, &[instruction], &[], blockhash)?;
+    let signature = send_transaction(rpc_url, &serialize_transaction(&tx), false).await?;
+    confirm_signature(rpc_url, &signature, CONFIRM_TIMEOUT).await?;
+    Ok(signature)
+}
+
+// Ключи для пулов из dex_pools, которые еще не разрешены
+async fn resolve_missing_pools(rpc_url: &str) {
+    let pools: Vec<Pubkey> = GLOBAL_DATA.dex_pools
+        .iter()
+        .flat_map(|dex| {
+            dex.value()
+                .iter()
+                .flat_map(|pair| pair.value().iter().map(|p| p.pool_address).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        })
+        .filter(|pool| !POOL_KEYS.contains_key(pool))
+        .collect();
+
+    for chunk in pools.chunks(RESOLVE_CHUNK) {
+        if let Err(e) = resolve_pool_keys(rpc_url, chunk).await {
+            // Один неподдерживаемый пул не должен блокировать остальные
+            debug!("alt: пакетное разрешение ключей не удалось ({}), разрешаем по одному", e);
+            for pool in chunk {
+                if let Err(e) = resolve_pool_keys(rpc_url, std::slice::from_ref(pool)).await {
+                    warn!("alt: ключи пула {} не разрешены: {}", pool, e);
+                }
+            }
+        }
+    }
+}
+
+/// Один проход синхронизации: разрешить новые пулы и дописать недостающие адреса в таблицы
+pub async fn sync_lookup_tables(rpc_url: &str, payer: &Keypair) -> Result<()> {
+    resolve_missing_pools(rpc_url).await;
+
+    let mut missing: Vec<Pubkey> = POOL_KEYS
+        .iter()
+        .flat_map(|keys| keys.value().lookup_accounts())
+        .filter(|address| !LOOKUP_TABLES.contains(address))
+        .collect();
+    missing.sort();
+    missing.dedup();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+    info!("alt: {} адресов пулов отсутствуют в таблицах", missing.len());
+
+    // Адреса несозданных таблиц нельзя сохранять, поэтому в paper trading таблицы не меняем
+    if CONFIG.paper_trading {
+        return Ok(());
+    }
+
+    let authority = payer.pubkey();
+    let mut remaining = missing.as_slice();
+
+    while !remaining.is_empty() {
+        let (table, free) = match LOOKUP_TABLES.extendable_table() {
+            Some(found) => found,
+            None => {
+                if LOOKUP_TABLES.table_addresses().len() >= MAX_LOOKUP_TABLES {
+                    warn!("alt: достигнут лимит {} таблиц, {} адресов не добавлено", MAX_LOOKUP_TABLES, remaining.len());
+                    return Ok(());
+                }
+
+                let recent_slot = get_slot(rpc_url, "finalized").await?;
+                #[allow(deprecated)]
+                let (instruction, table) = create_lookup_table(authority, authority, recent_slot);
+                let signature = send_and_confirm(rpc_url, payer, instruction).await?;
+                info!("alt: создана таблица {} ({})", table, signature);
+
+                let mut addresses = LOOKUP_TABLES.table_addresses();
+                addresses.push(table);
+                save_table_addresses(&addresses);
+                refresh_tables(rpc_url, &[table], &authority).await?;
+                continue;
+            }
+        };
+
+        let batch_len = remaining.len().min(free).min(LOOKUP_TABLE_EXTEND_BATCH);
+        let (batch, rest) = remaining.split_at(batch_len);
+
+        #[allow(deprecated)]
+        let instruction = extend_lookup_table(table, authority, Some(authority), batch.to_vec());
+        let signature = send_and_confirm(rpc_url, payer, instruction).await?;
+        debug!("alt: таблица {} расширена на {} адресов ({})", table, batch.len(), signature);
+
+        refresh_tables(rpc_url, &[table], &authority).await?;
+        remaining = rest;
+    }
+
+    Ok(())
+}
+
+/// Запуск менеджера: загрузка сохраненных таблиц и периодическая синхронизация
+pub async fn run_lookup_table_manager() {
+    let rpc_url = CONFIG.solana_rpc_url.clone();
+    let payer = PAYER.clone();
+    let authority = payer.pubkey();
+
+    let saved = load_table_addresses();
+    if !saved.is_empty() {
+        match refresh_tables(&rpc_url, &saved, &authority).await {
+            Ok(()) => info!("alt: загружено {} таблиц из {}", saved.len(), LOOKUP_TABLES_FILE),
+            Err(e) => error!("alt: не удалось загрузить сохраненные таблицы: {}", e),
+        }
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(LOOKUP_TABLE_SYNC_INTERVAL_SECS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        if let Err(e) = sync_lookup_tables(&rpc_url, &payer).await {
+            error!("alt: ошибка синхронизации таблиц: {}", e);
+        }
+    }
+}
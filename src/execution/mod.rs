@@ -0,0 +1,20 @@
+pub mod accounts;
+pub mod ix_orca;
+pub mod ix_raydium;
+pub mod ix_meteora;
+pub mod pool_keys;
+pub mod tx_builder;
+pub mod lookup_tables;
+pub mod jito;
+pub mod health;
+pub mod simulation;
+pub mod executor;
+pub mod jupiter;
+pub mod landing;
+pub mod paper;
+pub mod risk;
+pub mod blockhash;
+pub mod sender;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
@@ -0,0 +1,139 @@
+// src/execution/paper.rs
+
+// Paper trading (PAPER_TRADING в .env).
+// Конвейер работает полностью: обнаружение, размер сделки, сборка и предварительная
+// симуляция, но отправка - no-op (rpc::send_transaction, JitoClient::send_bundle).
+// Вместо трекера отправленных транзакций сделка попадает сюда и оценивается по
+// следующему наблюдаемому состоянию пулов маршрута: как только хотя бы один пул
+// обновился (или прошло PAPER_MARK_TIMEOUT_SECS), маршрут пересчитывается нашими
+// котировщиками на том же входе. Гипотетическая прибыль пишется в журнал сделок.
+
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::info;
+use solana_program::pubkey::Pubkey;
+use crate::config::{PAPER_MARK_POLL_MS, PAPER_MARK_TIMEOUT_SECS};
+use crate::data::{unix_timestamp, GLOBAL_DATA};
+use crate::ledger::{LedgerEntry, TradeStatus, LEDGER};
+use crate::quote::quote_route_with_our_quoters;
+use crate::router::ArbitrageOpportunity;
+use crate::websocket::ws_parser::PoolCommitment;
+
+/// Сделка, которая была бы отправлена
+#[derive(Debug, Clone)]
+pub struct PaperTrade {
+    pub signature: String,
+    pub bundle_id: Option<String>,
+    pub opportunity: ArbitrageOpportunity,
+    pub simulated_profit: i64,
+    pub recorded_at: u64,
+    recorded: Instant,
+    /// Версии processed состояний пулов маршрута на момент записи
+    pool_versions: Vec<(Pubkey, u64)>,
+}
+
+impl PaperTrade {
+    // Хотя бы один пул маршрута получил новое состояние
+    fn pools_updated(&self) -> bool {
+        self.pool_versions
+            .iter()
+            .any(|(pool, version)| GLOBAL_DATA.pool_version(PoolCommitment::Processed, pool) > *version)
+    }
+}
+
+#[derive(Default)]
+pub struct PaperTrader {
+    pending: DashMap<String, PaperTrade>,
+}
+
+lazy_static! {
+    pub static ref PAPER: PaperTrader = PaperTrader::default();
+}
+
+impl PaperTrader {
+    /// Запись сделки вместо отслеживания отправки
+    pub fn record(
+        &self,
+        signature: String,
+        bundle_id: Option<String>,
+        opportunity: &ArbitrageOpportunity,
+        simulated_profit: i64,
+    ) {
+        let pool_versions = opportunity.hops
+            .iter()
+            .map(|h| (h.pool_address, GLOBAL_DATA.pool_version(PoolCommitment::Processed, &h.pool_address)))
+            .collect();
+
+        self.pending.insert(signature.clone(), PaperTrade {
+            signature,
+            bundle_id,
+            opportunity: opportunity.clone(),
+            simulated_profit,
+            recorded_at: unix_timestamp(),
+            recorded: Instant::now(),
+            pool_versions,
+        });
+    }
+
+    /// Оценка сделок, для которых появилось новое состояние пулов
+    pub fn mark_ready(&self) {
+        let timeout = Duration::from_secs(PAPER_MARK_TIMEOUT_SECS);
+        let ready: Vec<String> = self.pending
+            .iter()
+            .filter(|t| t.pools_updated() || t.recorded.elapsed() >= timeout)
+            .map(|t| t.signature.clone())
+            .collect();
+
+        for signature in ready {
+            if let Some((_, trade)) = self.pending.remove(&signature) {
+                mark(trade);
+            }
+        }
+    }
+}
+
+// Пересчет маршрута по текущему состоянию пулов и запись в журнал
+fn mark(trade: PaperTrade) {
+    let opportunity = &trade.opportunity;
+    let costs = &opportunity.costs;
+    let fees = costs.signature_fee + costs.priority_fee + costs.jito_tip;
+
+    let (profit, failure) = match quote_route_with_our_quoters(opportunity) {
+        Some(amount_out) => (amount_out as i64 - opportunity.amount_in as i64 - costs.total() as i64, None),
+        None => (0, Some("нет состояния пула для оценки".to_string())),
+    };
+
+    info!("paper: [{}] {}: прибыль ожидаемая {} / по симуляции {} / по следующему состоянию {}",
+        opportunity.chain_id, trade.signature, opportunity.net_profit, trade.simulated_profit, profit);
+
+    LEDGER.record(LedgerEntry {
+        signature: trade.signature,
+        bundle_id: trade.bundle_id,
+        chain_id: opportunity.chain_id,
+        chain: opportunity.chain.clone(),
+        source: format!("{:?}", opportunity.source),
+        amount_in: opportunity.amount_in,
+        expected_profit: opportunity.net_profit,
+        simulated_profit: trade.simulated_profit,
+        realized_profit: profit,
+        fees_paid: fees,
+        status: TradeStatus::Paper,
+        slot: None,
+        failure,
+        sent_at: trade.recorded_at,
+        resolved_at: unix_timestamp(),
+    });
+}
+
+/// Запуск оценки сделок paper trading
+pub async fn run_paper_marker() {
+    info!("paper: режим paper trading, транзакции не отправляются");
+
+    let mut interval = tokio::time::interval(Duration::from_millis(PAPER_MARK_POLL_MS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        PAPER.mark_ready();
+    }
+}
@@ -0,0 +1,258 @@
+// src/execution/pool_keys.rs
+
+// Аккаунты пулов для сборки инструкций свапа.
+// Декодеры WebSocket хранят только ценовые параметры, поэтому адреса хранилищ,
+// рынков и tick/bin arrays читаются из сырых аккаунтов через RPC и кешируются.
+// Текущий тик / активный бин меняются со временем: перед сборкой маршрута ключи
+// его пулов перечитываются (`resolve_pool_keys`).
+
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use hashbrown::HashMap;
+use lazy_static::lazy_static;
+use log::debug;
+use solana_program::pubkey::Pubkey;
+use crate::rpc::{get_multiple_accounts, RpcAccount};
+use crate::execution::accounts::{
+    ORCA_PROGRAM, RAYDIUM_V4_PROGRAM, RAYDIUM_CLMM_PROGRAM, METEORA_PROGRAM,
+    TOKEN_PROGRAM, TOKEN_2022_PROGRAM, MEMO_PROGRAM, RAYDIUM_V4_AUTHORITY_KEY,
+};
+use crate::execution::ix_orca::{
+    parse_whirlpool, tick_arrays_for_swap, oracle_address as whirlpool_oracle_address, WhirlpoolKeys,
+};
+use crate::execution::ix_raydium::{
+    parse_amm_v4, apply_market, parse_clmm_pool, clmm_tick_array_candidates, clmm_bitmap_extension_address,
+    AmmV4Keys, ClmmKeys,
+};
+use crate::execution::ix_meteora::{
+    parse_lb_pair, bin_array_candidates, bitmap_extension_address, event_authority_address, DlmmKeys,
+};
+
+// Сколько tick/bin arrays от текущего проверяем в каждом направлении
+const SWAP_ARRAY_CANDIDATES: i32 = 3;
+
+/// Ключи пула любого поддерживаемого типа
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolKeys {
+    Whirlpool(WhirlpoolKeys),
+    RaydiumAmm(AmmV4Keys),
+    RaydiumClmm(ClmmKeys),
+    MeteoraDlmm(DlmmKeys),
+}
+
+impl PoolKeys {
+    pub fn pool(&self) -> Pubkey {
+        match self {
+            PoolKeys::Whirlpool(k) => k.pool,
+            PoolKeys::RaydiumAmm(k) => k.pool,
+            PoolKeys::RaydiumClmm(k) => k.pool,
+            PoolKeys::MeteoraDlmm(k) => k.pair,
+        }
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            PoolKeys::Whirlpool(_) => *ORCA_PROGRAM,
+            PoolKeys::RaydiumAmm(_) => *RAYDIUM_V4_PROGRAM,
+            PoolKeys::RaydiumClmm(_) => *RAYDIUM_CLMM_PROGRAM,
+            PoolKeys::MeteoraDlmm(_) => *METEORA_PROGRAM,
+        }
+    }
+
+    /// Минты пула в порядке (a, b) / (coin, pc) / (0, 1) / (x, y)
+    pub fn mints(&self) -> (Pubkey, Pubkey) {
+        match self {
+            PoolKeys::Whirlpool(k) => (k.mint_a, k.mint_b),
+            PoolKeys::RaydiumAmm(k) => (k.coin_mint, k.pc_mint),
+            PoolKeys::RaydiumClmm(k) => (k.mint_0, k.mint_1),
+            PoolKeys::MeteoraDlmm(k) => (k.mint_x, k.mint_y),
+        }
+    }
+
+    /// Токен-программа минта пула
+    pub fn token_program_for(&self, mint: &Pubkey) -> Pubkey {
+        let (first, second) = match self {
+            PoolKeys::Whirlpool(k) => ((k.mint_a, k.token_program_a), (k.mint_b, k.token_program_b)),
+            PoolKeys::RaydiumAmm(k) => ((k.coin_mint, *TOKEN_PROGRAM), (k.pc_mint, *TOKEN_PROGRAM)),
+            PoolKeys::RaydiumClmm(k) => ((k.mint_0, k.token_program_0), (k.mint_1, k.token_program_1)),
+            PoolKeys::MeteoraDlmm(k) => ((k.mint_x, k.token_program_x), (k.mint_y, k.token_program_y)),
+        };
+        if *mint == first.0 {
+            first.1
+        } else if *mint == second.0 {
+            second.1
+        } else {
+            *TOKEN_PROGRAM
+        }
+    }
+
+    /// Адреса пула для таблиц поиска: пул, хранилища, минты, программы,
+    /// оракул/рынок и текущие tick/bin arrays в обе стороны
+    pub fn lookup_accounts(&self) -> Vec<Pubkey> {
+        let mut accounts = vec![self.pool(), self.program_id()];
+
+        match self {
+            PoolKeys::Whirlpool(k) => {
+                accounts.extend([
+                    k.mint_a, k.mint_b, k.vault_a, k.vault_b,
+                    k.token_program_a, k.token_program_b, *MEMO_PROGRAM,
+                    whirlpool_oracle_address(&k.pool),
+                ]);
+                accounts.extend(tick_arrays_for_swap(k, true));
+                accounts.extend(tick_arrays_for_swap(k, false));
+            }
+            PoolKeys::RaydiumAmm(k) => {
+                accounts.extend([
+                    *TOKEN_PROGRAM, *RAYDIUM_V4_AUTHORITY_KEY,
+                    k.coin_vault, k.pc_vault, k.open_orders, k.target_orders,
+                    k.market_program, k.market, k.market_bids, k.market_asks, k.market_event_queue,
+                    k.market_coin_vault, k.market_pc_vault, k.market_vault_signer,
+                ]);
+            }
+            PoolKeys::RaydiumClmm(k) => {
+                accounts.extend([
+                    k.amm_config, k.mint_0, k.mint_1, k.vault_0, k.vault_1, k.observation,
+                    *TOKEN_PROGRAM, *TOKEN_2022_PROGRAM, *MEMO_PROGRAM,
+                    clmm_bitmap_extension_address(&k.pool),
+                ]);
+                accounts.extend(k.tick_arrays_zero_for_one.iter().copied());
+                accounts.extend(k.tick_arrays_one_for_zero.iter().copied());
+            }
+            PoolKeys::MeteoraDlmm(k) => {
+                accounts.extend([
+                    k.mint_x, k.mint_y, k.reserve_x, k.reserve_y, k.oracle,
+                    k.token_program_x, k.token_program_y, event_authority_address(),
+                ]);
+                accounts.extend(k.bitmap_extension);
+                accounts.extend(k.bin_arrays_x_to_y.iter().copied());
+                accounts.extend(k.bin_arrays_y_to_x.iter().copied());
+            }
+        }
+
+        accounts.sort();
+        accounts.dedup();
+        accounts
+    }
+}
+
+lazy_static! {
+    // Последние разрешенные ключи пулов
+    pub static ref POOL_KEYS: DashMap<Pubkey, PoolKeys> = DashMap::new();
+}
+
+/// Ключи пула из кеша
+pub fn cached_pool_keys(pool: &Pubkey) -> Option<PoolKeys> {
+    POOL_KEYS.get(pool).map(|k| k.clone())
+}
+
+/// Сброс ключей пула: следующий запрос перечитает аккаунты (например, сдвинулись tick arrays)
+pub fn invalidate_pool_keys(pool: &Pubkey) {
+    POOL_KEYS.remove(pool);
+}
+
+fn parse_pool(pool: Pubkey, account: &RpcAccount) -> Result<PoolKeys> {
+    if account.owner == *ORCA_PROGRAM {
+        Ok(PoolKeys::Whirlpool(parse_whirlpool(pool, &account.data)?))
+    } else if account.owner == *RAYDIUM_V4_PROGRAM {
+        Ok(PoolKeys::RaydiumAmm(parse_amm_v4(pool, &account.data)?))
+    } else if account.owner == *RAYDIUM_CLMM_PROGRAM {
+        Ok(PoolKeys::RaydiumClmm(parse_clmm_pool(pool, &account.data)?))
+    } else if account.owner == *METEORA_PROGRAM {
+        Ok(PoolKeys::MeteoraDlmm(parse_lb_pair(pool, &account.data)?))
+    } else {
+        Err(anyhow!("пул {} принадлежит неизвестной программе {}", pool, account.owner))
+    }
+}
+
+// Вспомогательные аккаунты, которые нужно дочитать для пула
+fn auxiliary_accounts(keys: &PoolKeys) -> Vec<Pubkey> {
+    let (mint_a, mint_b) = keys.mints();
+    let mut accounts = vec![mint_a, mint_b];
+
+    match keys {
+        PoolKeys::Whirlpool(_) => {}
+        PoolKeys::RaydiumAmm(k) => accounts.push(k.market),
+        PoolKeys::RaydiumClmm(k) => {
+            accounts.extend(clmm_tick_array_candidates(k, true, SWAP_ARRAY_CANDIDATES));
+            accounts.extend(clmm_tick_array_candidates(k, false, SWAP_ARRAY_CANDIDATES));
+        }
+        PoolKeys::MeteoraDlmm(k) => {
+            accounts.push(bitmap_extension_address(&k.pair));
+            accounts.extend(bin_array_candidates(k, true, SWAP_ARRAY_CANDIDATES as i64));
+            accounts.extend(bin_array_candidates(k, false, SWAP_ARRAY_CANDIDATES as i64));
+        }
+    }
+    accounts
+}
+
+fn token_program_of(mint: &Pubkey, aux: &HashMap<Pubkey, Option<RpcAccount>>) -> Pubkey {
+    match aux.get(mint) {
+        Some(Some(account)) if account.owner == *TOKEN_2022_PROGRAM => *TOKEN_2022_PROGRAM,
+        _ => *TOKEN_PROGRAM,
+    }
+}
+
+fn existing(candidates: Vec<Pubkey>, aux: &HashMap<Pubkey, Option<RpcAccount>>) -> Vec<Pubkey> {
+    candidates
+        .into_iter()
+        .filter(|a| matches!(aux.get(a), Some(Some(_))))
+        .collect()
+}
+
+// Заполняет ключи пула данными вспомогательных аккаунтов
+fn complete_pool(keys: &mut PoolKeys, aux: &HashMap<Pubkey, Option<RpcAccount>>) -> Result<()> {
+    match keys {
+        PoolKeys::Whirlpool(k) => {
+            k.token_program_a = token_program_of(&k.mint_a, aux);
+            k.token_program_b = token_program_of(&k.mint_b, aux);
+        }
+        PoolKeys::RaydiumAmm(k) => {
+            let market = aux.get(&k.market)
+                .and_then(|a| a.as_ref())
+                .ok_or_else(|| anyhow!("рынок {} пула {} не найден", k.market, k.pool))?;
+            apply_market(k, &market.data)?;
+        }
+        PoolKeys::RaydiumClmm(k) => {
+            k.token_program_0 = token_program_of(&k.mint_0, aux);
+            k.token_program_1 = token_program_of(&k.mint_1, aux);
+            k.tick_arrays_zero_for_one = existing(clmm_tick_array_candidates(k, true, SWAP_ARRAY_CANDIDATES), aux);
+            k.tick_arrays_one_for_zero = existing(clmm_tick_array_candidates(k, false, SWAP_ARRAY_CANDIDATES), aux);
+        }
+        PoolKeys::MeteoraDlmm(k) => {
+            k.token_program_x = token_program_of(&k.mint_x, aux);
+            k.token_program_y = token_program_of(&k.mint_y, aux);
+            let extension = bitmap_extension_address(&k.pair);
+            k.bitmap_extension = matches!(aux.get(&extension), Some(Some(_))).then_some(extension);
+            k.bin_arrays_x_to_y = existing(bin_array_candidates(k, true, SWAP_ARRAY_CANDIDATES as i64), aux);
+            k.bin_arrays_y_to_x = existing(bin_array_candidates(k, false, SWAP_ARRAY_CANDIDATES as i64), aux);
+        }
+    }
+    Ok(())
+}
+
+/// Читает аккаунты пулов и вспомогательные аккаунты двумя запросами getMultipleAccounts,
+/// обновляет кеш и возвращает ключи в порядке `pools`.
+pub async fn resolve_pool_keys(rpc_url: &str, pools: &[Pubkey]) -> Result<Vec<PoolKeys>> {
+    let pool_accounts = get_multiple_accounts(rpc_url, pools).await?;
+
+    let mut resolved = Vec::with_capacity(pools.len());
+    for (pool, account) in pools.iter().zip(pool_accounts.iter()) {
+        let account = account.as_ref().ok_or_else(|| anyhow!("аккаунт пула {} не найден", pool))?;
+        resolved.push(parse_pool(*pool, account)?);
+    }
+
+    let mut aux_addresses: Vec<Pubkey> = resolved.iter().flat_map(auxiliary_accounts).collect();
+    aux_addresses.sort();
+    aux_addresses.dedup();
+
+    let aux_accounts = get_multiple_accounts(rpc_url, &aux_addresses).await?;
+    let aux: HashMap<Pubkey, Option<RpcAccount>> = aux_addresses.into_iter().zip(aux_accounts).collect();
+
+    for keys in resolved.iter_mut() {
+        complete_pool(keys, &aux)?;
+        POOL_KEYS.insert(keys.pool(), keys.clone());
+    }
+
+    debug!("pool_keys: разрешены ключи {} пулов, вспомогательных аккаунтов {}", resolved.len(), aux.len());
+    Ok(resolved)
+}
@@ -0,0 +1,343 @@
+// src/execution/risk.rs
+
+// Риск-лимиты исполнителя. Проверяются перед каждой отправкой (executor.rs):
+// - вход сделки не больше MAX_TRADE_NOTIONAL;
+// - не больше MAX_TRADES_PER_MINUTE отправок за скользящую минуту;
+// - фактический убыток за сутки (ledger.rs) не больше DAILY_LOSS_LIMIT;
+// - не больше MAX_CONSECUTIVE_SEND_FAILURES неудачных отправок подряд
+//   (ошибка RPC / block engine или упавшая в блоке транзакция);
+// - не больше MAX_CONSECUTIVE_EXPIRIES истекших транзакций подряд: истечение обычно
+//   означает проигранный аукцион, а не сломанный маршрут, поэтому лимит выше;
+// - пулы неудачной сделки на паузе RISK_POOL_COOLDOWN_SECS (истечение паузу не включает).
+//
+// Kill switch останавливает исполнение, не трогая загрузку данных и роутер.
+// Срабатывает при нарушении суточного лимита убытка или лимита неудач подряд,
+// при появлении файла KILL_SWITCH_FILE и по SIGUSR1. SIGUSR2 снимает остановку
+// (удаление файла снимает остановку, включенную файлом).
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{info, warn, error};
+use solana_program::pubkey::Pubkey;
+use crate::config::{
+    MAX_TRADE_NOTIONAL, MAX_TRADES_PER_MINUTE, DAILY_LOSS_LIMIT, MAX_CONSECUTIVE_SEND_FAILURES,
+    MAX_CONSECUTIVE_EXPIRIES, RISK_POOL_COOLDOWN_SECS, KILL_SWITCH_FILE, KILL_SWITCH_POLL_SECS,
+};
+use crate::data::unix_timestamp;
+use crate::ledger::{TradeStatus, LEDGER};
+use crate::router::ArbitrageOpportunity;
+
+/// Что включило kill switch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KillReason {
+    /// Нарушен лимит (описание)
+    Limit(String),
+    File,
+    Signal,
+}
+
+/// Почему отправка запрещена
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RiskRejection {
+    KillSwitch(KillReason),
+    Notional { amount_in: u64 },
+    RateLimit,
+    DailyLoss { realized: i64 },
+    PoolCooldown(Pubkey),
+}
+
+#[derive(Default)]
+pub struct RiskManager {
+    killed: AtomicBool,
+    kill_reason: Mutex<Option<KillReason>>,
+    // Моменты отправок за последнюю минуту
+    sends: Mutex<VecDeque<Instant>>,
+    consecutive_failures: AtomicU32,
+    consecutive_expiries: AtomicU32,
+    // Пул -> до какого момента (unix, сек) отправки через него запрещены
+    pool_cooldowns: DashMap<Pubkey, u64>,
+}
+
+lazy_static! {
+    pub static ref RISK: RiskManager = RiskManager::default();
+}
+
+impl RiskManager {
+    pub fn is_killed(&self) -> bool {
+        self.killed.load(Ordering::Relaxed)
+    }
+
+    pub fn kill_reason(&self) -> Option<KillReason> {
+        self.kill_reason.lock().unwrap().clone()
+    }
+
+    /// Остановка исполнения
+    pub fn kill(&self, reason: KillReason) {
+        let mut current = self.kill_reason.lock().unwrap();
+        if current.is_none() {
+            error!("risk: kill switch, исполнение остановлено: {:?}", reason);
+            *current = Some(reason);
+            self.killed.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Снятие остановки и сброс счетчиков неудач и истечений подряд
+    pub fn resume(&self) {
+        let mut current = self.kill_reason.lock().unwrap();
+        if let Some(reason) = current.take() {
+            info!("risk: исполнение возобновлено (было остановлено: {:?})", reason);
+        }
+        self.killed.store(false, Ordering::Relaxed);
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        self.consecutive_expiries.store(0, Ordering::Relaxed);
+    }
+
+    /// Проверка перед отправкой
+    pub fn check(&self, opportunity: &ArbitrageOpportunity, pools: &[Pubkey]) -> Result<(), RiskRejection> {
+        self.check_limits(opportunity, pools, LEDGER.daily().realized_profit)
+    }
+
+    // Проверка при фактическом результате за сутки `realized`
+    fn check_limits(&self, opportunity: &ArbitrageOpportunity, pools: &[Pubkey], realized: i64) -> Result<(), RiskRejection> {
+        if let Some(reason) = self.kill_reason() {
+            return Err(RiskRejection::KillSwitch(reason));
+        }
+
+        if opportunity.amount_in > MAX_TRADE_NOTIONAL {
+            return Err(RiskRejection::Notional { amount_in: opportunity.amount_in });
+        }
+
+        if self.daily_loss_exceeded(realized) {
+            return Err(RiskRejection::DailyLoss { realized });
+        }
+
+        let now = unix_timestamp();
+        if let Some(pool) = pools.iter().find(|p| self.pool_cooldowns.get(p).is_some_and(|until| now < *until)) {
+            return Err(RiskRejection::PoolCooldown(*pool));
+        }
+
+        let mut sends = self.sends.lock().unwrap();
+        while sends.front().is_some_and(|t| t.elapsed() >= Duration::from_secs(60)) {
+            sends.pop_front();
+        }
+        if sends.len() >= MAX_TRADES_PER_MINUTE {
+            return Err(RiskRejection::RateLimit);
+        }
+        sends.push_back(Instant::now());
+        Ok(())
+    }
+
+    /// Неудачная отправка: ошибка RPC / block engine или упавшая в блоке транзакция
+    pub fn record_failure(&self, pools: &[Pubkey]) {
+        let until = unix_timestamp() + RISK_POOL_COOLDOWN_SECS;
+        for pool in pools {
+            self.pool_cooldowns.insert(*pool, until);
+        }
+
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= MAX_CONSECUTIVE_SEND_FAILURES {
+            self.kill(KillReason::Limit(format!("{} неудачных отправок подряд", failures)));
+        }
+    }
+
+    /// Итог отправленной транзакции из трекера (landing.rs)
+    pub fn record_outcome(&self, status: TradeStatus, pools: &[Pubkey]) {
+        self.count_outcome(status, pools);
+        self.daily_loss_exceeded(LEDGER.daily().realized_profit);
+    }
+
+    // Счетчики подряд и паузы пулов по итогу транзакции
+    fn count_outcome(&self, status: TradeStatus, pools: &[Pubkey]) {
+        match status {
+            TradeStatus::Landed => {
+                self.consecutive_failures.store(0, Ordering::Relaxed);
+                self.consecutive_expiries.store(0, Ordering::Relaxed);
+            }
+            TradeStatus::Failed => self.record_failure(pools),
+            TradeStatus::Expired => self.record_expiry(),
+            TradeStatus::Paper => {}
+        }
+    }
+
+    // Суточный лимит убытка: при нарушении включает kill switch
+    fn daily_loss_exceeded(&self, realized: i64) -> bool {
+        if realized >= -DAILY_LOSS_LIMIT {
+            return false;
+        }
+        self.kill(KillReason::Limit(format!("убыток за сутки {} больше лимита {}", -realized, DAILY_LOSS_LIMIT)));
+        true
+    }
+
+    // Истекшая транзакция: пулы не ставим на паузу, считаем отдельно от неудач
+    fn record_expiry(&self) {
+        let expiries = self.consecutive_expiries.fetch_add(1, Ordering::Relaxed) + 1;
+        if expiries >= MAX_CONSECUTIVE_EXPIRIES {
+            self.kill(KillReason::Limit(format!("{} истекших транзакций подряд", expiries)));
+        }
+    }
+
+    // Файл kill switch: появление останавливает, удаление снимает остановку, включенную файлом
+    fn check_file(&self) {
+        let exists = std::path::Path::new(KILL_SWITCH_FILE).exists();
+        if exists && !self.is_killed() {
+            self.kill(KillReason::File);
+        } else if !exists && self.kill_reason() == Some(KillReason::File) {
+            self.resume();
+        }
+    }
+}
+
+/// Запуск наблюдения за файлом и сигналами kill switch
+pub async fn run_kill_switch() {
+    info!("risk: kill switch - файл {}, SIGUSR1 (остановка) / SIGUSR2 (возобновление)", KILL_SWITCH_FILE);
+
+    #[cfg(unix)]
+    tokio::spawn(async {
+        use tokio::signal::unix::{signal, SignalKind};
+        let (mut stop, mut resume) = match (signal(SignalKind::user_defined1()), signal(SignalKind::user_defined2())) {
+            (Ok(stop), Ok(resume)) => (stop, resume),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("risk: не удалось подписаться на сигналы: {}", e);
+                return;
+            }
+        };
+        loop {
+            tokio::select! {
+                _ = stop.recv() => RISK.kill(KillReason::Signal),
+                _ = resume.recv() => RISK.resume(),
+            }
+        }
+    });
+
+    let mut interval = tokio::time::interval(Duration::from_secs(KILL_SWITCH_POLL_SECS));
+    loop {
+        interval.tick().await;
+        RISK.check_file();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::costs::ExecutionCosts;
+    use crate::router::OpportunitySource;
+    use crate::websocket::ws_parser::PoolCommitment;
+
+    fn opportunity(amount_in: u64) -> ArbitrageOpportunity {
+        ArbitrageOpportunity {
+            chain_id: 0,
+            chain: Vec::new(),
+            commitment: PoolCommitment::Processed,
+            source: OpportunitySource::Router,
+            amount_in,
+            amount_out: amount_in,
+            expected_profit: 0,
+            costs: ExecutionCosts::default(),
+            net_profit: 0,
+            hops: Vec::new(),
+        }
+    }
+
+    fn check(risk: &RiskManager, pools: &[Pubkey]) -> Result<(), RiskRejection> {
+        risk.check_limits(&opportunity(1_000_000_000), pools, 0)
+    }
+
+    #[test]
+    fn rejects_notional_above_limit() {
+        let risk = RiskManager::default();
+        assert!(risk.check_limits(&opportunity(MAX_TRADE_NOTIONAL), &[], 0).is_ok());
+        assert_eq!(
+            risk.check_limits(&opportunity(MAX_TRADE_NOTIONAL + 1), &[], 0),
+            Err(RiskRejection::Notional { amount_in: MAX_TRADE_NOTIONAL + 1 })
+        );
+    }
+
+    #[test]
+    fn rejects_sends_above_rate_limit() {
+        let risk = RiskManager::default();
+        for _ in 0..MAX_TRADES_PER_MINUTE {
+            assert!(check(&risk, &[]).is_ok());
+        }
+        assert_eq!(check(&risk, &[]), Err(RiskRejection::RateLimit));
+    }
+
+    #[test]
+    fn failed_trade_puts_pools_on_cooldown() {
+        let risk = RiskManager::default();
+        let (failed_pool, other_pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        risk.record_failure(&[failed_pool]);
+
+        assert_eq!(check(&risk, &[other_pool, failed_pool]), Err(RiskRejection::PoolCooldown(failed_pool)));
+        assert!(check(&risk, &[other_pool]).is_ok());
+    }
+
+    #[test]
+    fn daily_loss_kills_execution() {
+        let risk = RiskManager::default();
+        assert!(risk.check_limits(&opportunity(1), &[], -DAILY_LOSS_LIMIT).is_ok());
+
+        let realized = -DAILY_LOSS_LIMIT - 1;
+        assert_eq!(risk.check_limits(&opportunity(1), &[], realized), Err(RiskRejection::DailyLoss { realized }));
+        assert!(risk.is_killed());
+        assert!(matches!(risk.check_limits(&opportunity(1), &[], 0), Err(RiskRejection::KillSwitch(KillReason::Limit(_)))));
+    }
+
+    #[test]
+    fn kill_switch_blocks_until_resume() {
+        let risk = RiskManager::default();
+        risk.kill(KillReason::Signal);
+        assert_eq!(check(&risk, &[]), Err(RiskRejection::KillSwitch(KillReason::Signal)));
+
+        // Повторная остановка не подменяет причину
+        risk.kill(KillReason::File);
+        assert_eq!(risk.kill_reason(), Some(KillReason::Signal));
+
+        risk.resume();
+        assert!(check(&risk, &[]).is_ok());
+    }
+
+    #[test]
+    fn consecutive_failures_kill_execution() {
+        let risk = RiskManager::default();
+        let pools = [Pubkey::new_unique()];
+        for _ in 0..MAX_CONSECUTIVE_SEND_FAILURES - 1 {
+            risk.count_outcome(TradeStatus::Failed, &pools);
+        }
+        assert!(!risk.is_killed());
+
+        // Попавшая в блок сделка сбрасывает счетчик
+        risk.count_outcome(TradeStatus::Landed, &pools);
+        risk.count_outcome(TradeStatus::Failed, &pools);
+        assert!(!risk.is_killed());
+
+        for _ in 0..MAX_CONSECUTIVE_SEND_FAILURES - 1 {
+            risk.count_outcome(TradeStatus::Failed, &pools);
+        }
+        assert!(risk.is_killed());
+    }
+
+    #[test]
+    fn expiries_are_counted_separately_from_failures() {
+        let risk = RiskManager::default();
+        let pools = [Pubkey::new_unique()];
+
+        // Истечения не ставят пулы на паузу и не приближают лимит неудач
+        for _ in 0..MAX_CONSECUTIVE_EXPIRIES - 1 {
+            risk.count_outcome(TradeStatus::Expired, &pools);
+        }
+        assert!(!risk.is_killed());
+        assert!(check(&risk, &pools).is_ok());
+        for _ in 0..MAX_CONSECUTIVE_SEND_FAILURES - 1 {
+            risk.count_outcome(TradeStatus::Failed, &pools);
+        }
+        assert!(!risk.is_killed());
+
+        risk.count_outcome(TradeStatus::Expired, &pools);
+        assert!(risk.is_killed());
+        assert!(matches!(risk.kill_reason(), Some(KillReason::Limit(reason)) if reason.contains("истекших")));
+    }
+}
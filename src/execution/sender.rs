@@ -0,0 +1,211 @@
+// src/execution/sender.rs
+
+// Рассылка подписанной транзакции по нескольким endpoint одновременно:
+// solana_rpc_url, helius_rpc_url, helius_enhanced_rpc_url и sendTransaction
+// block engine Jito. Отправка с skipPreflight и maxRetries 0; повторяем сами
+// каждые FANOUT_RETRY_MS, пока подпись не появится в сети или не истечет blockhash.
+// Раунд отправки завершается первым успешным ответом; ответы остальных endpoint
+// дожидаются в фоне и идут только в статистику.
+//
+// Для настройки маршрутизации по каждому endpoint считаются отправки, ошибки,
+// задержка ответа и сколько раз он первым принял транзакцию. Когда транзакция
+// появляется в сети, победителем считается endpoint, первым принявший ее
+// (копии одинаковы, поэтому точнее определить источник нельзя).
+//
+// В режиме paper trading транзакции не рассылаются и статистика не ведется.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use futures::stream::{FuturesUnordered, StreamExt};
+use lazy_static::lazy_static;
+use log::{info, debug};
+use solana_sdk::hash::Hash;
+use solana_sdk::transaction::VersionedTransaction;
+use crate::config::{CONFIG, FANOUT_RETRY_MS, FANOUT_MAX_DURATION_SECS, FANOUT_INCLUDE_JITO};
+use crate::rpc::{get_signature_statuses, send_transaction};
+use crate::execution::blockhash::BLOCKHASH_CACHE;
+use crate::execution::jito::JITO;
+use crate::execution::tx_builder::serialize_transaction;
+
+/// Endpoint рассылки. В логах - только имя: URL может содержать ключ API.
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub name: &'static str,
+    url: String,
+}
+
+/// Статистика endpoint
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EndpointStats {
+    pub submissions: u64,
+    pub errors: u64,
+    /// Сколько раз endpoint первым принял транзакцию
+    pub first_accepts: u64,
+    /// Сколько из первых принятых endpoint транзакций попало в сеть
+    pub first_ack_landed: u64,
+    accepted: u64,
+    accept_latency_ms: u64,
+}
+
+impl EndpointStats {
+    /// Средняя задержка успешного ответа
+    pub fn mean_accept_latency(&self) -> Option<Duration> {
+        (self.accepted > 0).then(|| Duration::from_millis(self.accept_latency_ms / self.accepted))
+    }
+}
+
+pub struct FanoutSender {
+    endpoints: Vec<Endpoint>,
+    stats: DashMap<&'static str, EndpointStats>,
+}
+
+lazy_static! {
+    pub static ref FANOUT: Arc<FanoutSender> = Arc::new(FanoutSender::from_config());
+}
+
+// Helius принимает ключ в параметре api-key
+fn helius_url(url: &str) -> String {
+    if url.is_empty() || url.contains("api-key=") || CONFIG.helius_api_key.is_empty() {
+        url.to_string()
+    } else {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!("{}{}api-key={}", url, separator, CONFIG.helius_api_key)
+    }
+}
+
+impl FanoutSender {
+    /// Endpoint из конфигурации без пустых и повторяющихся URL
+    pub fn from_config() -> Self {
+        let mut candidates = vec![
+            ("rpc", CONFIG.solana_rpc_url.clone()),
+            ("helius", helius_url(&CONFIG.helius_rpc_url)),
+            ("helius_enhanced", helius_url(&CONFIG.helius_enhanced_rpc_url)),
+        ];
+        if FANOUT_INCLUDE_JITO {
+            candidates.push(("jito", JITO.transactions_url()));
+        }
+
+        let mut endpoints: Vec<Endpoint> = Vec::new();
+        for (name, url) in candidates {
+            if url.starts_with("http") && !endpoints.iter().any(|e| e.url == url) {
+                endpoints.push(Endpoint { name, url });
+            }
+        }
+        if CONFIG.paper_trading {
+            info!("sender: paper trading - рассылка по endpoint и ее статистика отключены");
+        }
+        Self { endpoints, stats: DashMap::new() }
+    }
+
+    pub fn stats(&self) -> Vec<(&'static str, EndpointStats)> {
+        self.endpoints
+            .iter()
+            .map(|e| (e.name, self.stats.get(e.name).map(|s| *s).unwrap_or_default()))
+            .collect()
+    }
+
+    fn record_submission(&self, name: &'static str, latency: Duration, result: &Result<String>) {
+        let mut stats = self.stats.entry(name).or_default();
+        stats.submissions += 1;
+        match result {
+            Ok(_) => {
+                stats.accepted += 1;
+                stats.accept_latency_ms += latency.as_millis() as u64;
+            }
+            Err(e) => {
+                stats.errors += 1;
+                debug!("sender: {} не принял транзакцию: {}", name, e);
+            }
+        }
+    }
+
+    // Одна отправка на все endpoint. Возвращает endpoint, ответивший успехом первым,
+    // не дожидаясь остальных.
+    async fn submit_round(self: &Arc<Self>, wire: &Arc<Vec<u8>>) -> Result<&'static str> {
+        let mut pending: FuturesUnordered<_> = self.endpoints.iter().cloned().map(|endpoint| {
+            let sender = Arc::clone(self);
+            let wire = Arc::clone(wire);
+            async move {
+                let started = Instant::now();
+                let result = send_transaction(&endpoint.url, &wire, true).await;
+                sender.record_submission(endpoint.name, started.elapsed(), &result);
+                (endpoint.name, result)
+            }
+        }).collect();
+
+        let mut last_error = None;
+        while let Some((name, result)) = pending.next().await {
+            match result {
+                Ok(_) => {
+                    if !pending.is_empty() {
+                        tokio::spawn(async move { while pending.next().await.is_some() {} });
+                    }
+                    return Ok(name);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("нет endpoint для отправки")))
+    }
+
+    /// Отправка транзакции на все endpoint. Возвращает подпись после первого успешного ответа;
+    /// повторы до подтверждения или истечения blockhash идут в фоне.
+    pub async fn send(self: &Arc<Self>, tx: &VersionedTransaction) -> Result<String> {
+        let signature = tx.signatures[0].to_string();
+        if CONFIG.paper_trading {
+            return Ok(signature);
+        }
+
+        let wire = Arc::new(serialize_transaction(tx));
+        let blockhash = *tx.message.recent_blockhash();
+
+        let first = self.submit_round(&wire).await?;
+        self.stats.entry(first).or_default().first_accepts += 1;
+
+        let sender = Arc::clone(self);
+        let retry_signature = signature.clone();
+        tokio::spawn(async move { sender.retry_until_landed(wire, retry_signature, blockhash, first).await });
+        Ok(signature)
+    }
+
+    // Повторы до появления подписи в сети или истечения blockhash
+    async fn retry_until_landed(self: &Arc<Self>, wire: Arc<Vec<u8>>, signature: String, blockhash: Hash, first: &'static str) {
+        let started = Instant::now();
+        let signatures = [signature.clone()];
+        let status_url = CONFIG.solana_rpc_url.clone();
+
+        loop {
+            tokio::time::sleep(Duration::from_millis(FANOUT_RETRY_MS)).await;
+
+            if let Ok(statuses) = get_signature_statuses(&status_url, &signatures).await {
+                if statuses.first().is_some_and(|s| s.is_some()) {
+                    self.stats.entry(first).or_default().first_ack_landed += 1;
+                    debug!("sender: {} в сети через {:?}, первым принял {}", signature, started.elapsed(), first);
+                    return;
+                }
+            }
+
+            let expired = BLOCKHASH_CACHE.is_expired(&blockhash)
+                .unwrap_or_else(|| started.elapsed() >= Duration::from_secs(FANOUT_MAX_DURATION_SECS));
+            if expired {
+                debug!("sender: {} не попала в сеть до истечения blockhash", signature);
+                return;
+            }
+
+            let _ = self.submit_round(&wire).await;
+        }
+    }
+
+    /// Вывод статистики по endpoint
+    pub fn report(&self) {
+        if CONFIG.paper_trading {
+            return;
+        }
+        for (name, stats) in self.stats() {
+            info!("sender: {}: отправок {}, ошибок {}, первым принял {}, из них в сети {}, задержка ответа {:?}",
+                name, stats.submissions, stats.errors, stats.first_accepts, stats.first_ack_landed, stats.mean_accept_latency());
+        }
+    }
+}
@@ -0,0 +1,272 @@
+// src/execution/simulation.rs
+
+// Предварительная симуляция транзакции маршрута перед отправкой.
+// simulateTransaction с replaceRecentBlockhash исполняет транзакцию на текущем
+// processed состоянии; по токен-аккаунту стартового токена до и после
+// исполнения считаем фактическую прибыль за вычетом затрат.
+// Возможность отбрасывается, если симуляция упала или прибыль ниже порогов.
+// Причина отказа классифицируется и передается в health.rs.
+// Расход CU инструкций свапов по логам пополняет историю compute_units.rs.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use crate::compute_units::{instruction_keys, COMPUTE_UNITS};
+use crate::router::{ArbitrageOpportunity, RouterEngine};
+use crate::rpc::{get_multiple_accounts_with_slot, simulate_transaction, RpcAccount};
+use crate::config::SIMULATION_SLOT_ATTEMPTS;
+use crate::execution::accounts::read_u64;
+use crate::execution::health::HEALTH;
+use crate::execution::pool_keys::invalidate_pool_keys;
+use crate::execution::tx_builder::serialize_transaction;
+
+// Смещение amount в аккаунте SPL токена (mint 32 + owner 32)
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+// Фрагменты логов / ошибок по причинам отказа
+const SLIPPAGE_MARKERS: [&str; 5] = [
+    "AmountOutBelowMinimum",            // Orca Whirlpool
+    "TooLittleOutputReceived",          // Raydium CLMM
+    "ExceededAmountSlippageTolerance",  // Meteora DLMM
+    "exceeds desired slippage limit",   // Raydium AMM V4
+    "ExceededSlippage",
+];
+// AccountNotInitialized (Anchor 3012) сюда не входит: его же дает отсутствующий
+// промежуточный ATA, и по нему нельзя сбрасывать ключи пула
+const STALE_ARRAY_MARKERS: [&str; 4] = [
+    "TickArray",                        // InvalidTickArraySequence, TickArrayIndexOutofBounds, ...
+    "BinArray",                         // InvalidBinArray, NonContinuousBinArrays, ...
+    "NotEnoughTickArrayAccount",
+    "InvalidFirstTickArrayAccount",
+];
+// AccountNotFound - отсутствующий аккаунт, а не нехватка средств; он остается в Other
+const INSUFFICIENT_FUNDS_MARKERS: [&str; 4] = [
+    "InsufficientFundsForFee",
+    "InsufficientFundsForRent",
+    "insufficient funds",
+    "insufficient lamports",
+];
+const COMPUTE_BUDGET_MARKERS: [&str; 2] = [
+    "ComputationalBudgetExceeded",
+    "exceeded CUs meter",
+];
+
+/// Причина отказа предварительной симуляции
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimulationFailure {
+    /// Выход хопа ниже min-out: котировка устарела или пул сдвинулся
+    Slippage,
+    /// Переданы не те tick / bin arrays: цена ушла из разрешенного диапазона
+    StaleTickArray,
+    /// Не хватает SOL или токенов на кошельке
+    InsufficientFunds,
+    ComputeBudgetExceeded,
+    /// Симуляция прошла, но прибыль ниже порогов
+    Unprofitable,
+    Other,
+}
+
+impl SimulationFailure {
+    /// Учитывается ли отказ в здоровье маршрута
+    pub fn penalizes_route(&self) -> bool {
+        // Нехватка средств - проблема кошелька, а не маршрута
+        !matches!(self, SimulationFailure::InsufficientFunds)
+    }
+
+    /// Учитывается ли отказ в здоровье пула, на инструкции которого упала транзакция
+    pub fn penalizes_pool(&self) -> bool {
+        matches!(self, SimulationFailure::Slippage | SimulationFailure::StaleTickArray | SimulationFailure::Other)
+    }
+}
+
+/// Классификация ошибки симуляции по полю err и логам
+pub fn classify_failure(err: &Value, logs: &[String]) -> SimulationFailure {
+    let err_text = err.to_string();
+    let matches = |markers: &[&str]| {
+        markers.iter().any(|m| err_text.contains(m) || logs.iter().any(|l| l.contains(m)))
+    };
+
+    // Порядок важен: нехватка средств и CU перекрывают последствия в логах DEX
+    if matches(&INSUFFICIENT_FUNDS_MARKERS) {
+        SimulationFailure::InsufficientFunds
+    } else if matches(&COMPUTE_BUDGET_MARKERS) {
+        SimulationFailure::ComputeBudgetExceeded
+    } else if matches(&SLIPPAGE_MARKERS) {
+        SimulationFailure::Slippage
+    } else if matches(&STALE_ARRAY_MARKERS) {
+        SimulationFailure::StaleTickArray
+    } else {
+        SimulationFailure::Other
+    }
+}
+
+/// Индекс инструкции из ошибки вида {"InstructionError": [index, ...]}
+pub fn failed_instruction_index(err: &Value) -> Option<usize> {
+    err["InstructionError"][0].as_u64().map(|i| i as usize)
+}
+
+/// Пул хопа, которому принадлежит инструкция. None, если инструкция не свап
+/// или затрагивает несколько пулов маршрута (агрегатор исполняет весь маршрут одной инструкцией).
+pub fn pool_of_instruction(instruction: &Instruction, opportunity: &ArbitrageOpportunity) -> Option<Pubkey> {
+    let mut pools = opportunity.hops
+        .iter()
+        .map(|h| h.pool_address)
+        .filter(|pool| instruction.accounts.iter().any(|a| a.pubkey == *pool));
+    match (pools.next(), pools.next()) {
+        (Some(pool), None) => Some(pool),
+        _ => None,
+    }
+}
+
+/// Баланс токен-аккаунта (0, если аккаунта нет)
+pub fn token_account_amount(account: Option<&RpcAccount>) -> Result<u64> {
+    match account {
+        Some(account) => read_u64(&account.data, TOKEN_ACCOUNT_AMOUNT_OFFSET),
+        None => Ok(0),
+    }
+}
+
+/// Успешная симуляция
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationReport {
+    /// Прирост стартового токена за вычетом затрат на исполнение
+    pub net_profit: i64,
+    pub units_consumed: Option<u64>,
+}
+
+/// Отказ симуляции
+#[derive(Debug, Clone)]
+pub struct SimulationRejection {
+    pub failure: SimulationFailure,
+    pub pool: Option<Pubkey>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum GateVerdict {
+    Pass(SimulationReport),
+    Reject(SimulationRejection),
+}
+
+/// Предварительная симуляция транзакции маршрута.
+/// `profit_account` - токен-аккаунт стартового токена, `instructions` - инструкции,
+/// из которых собрана `tx` (для поиска пула по индексу упавшей инструкции).
+/// Результат передается в HEALTH; ошибка RPC не считается отказом маршрута.
+pub async fn simulate_gate(
+    rpc_url: &str,
+    opportunity: &ArbitrageOpportunity,
+    instructions: &[Instruction],
+    tx: &VersionedTransaction,
+    profit_account: &Pubkey,
+) -> Result<GateVerdict> {
+    let pools: Vec<Pubkey> = opportunity.hops.iter().map(|h| h.pool_address).collect();
+
+    let wire = serialize_transaction(tx);
+
+    // Прибыль - разница баланса до и после исполнения, поэтому баланс "до" должен быть
+    // прочитан на слоте симуляции: баланс и симуляция читаются разными запросами
+    let mut attempt = 1;
+    let (pre_amount, simulation) = loop {
+        let pre_accounts = get_multiple_accounts_with_slot(rpc_url, &[*profit_account], "processed").await?;
+        let (pre_slot, pre_account) = &pre_accounts[0];
+        let simulation = simulate_transaction(rpc_url, &wire, &[*profit_account]).await?;
+
+        if *pre_slot == simulation.slot {
+            break (token_account_amount(pre_account.as_ref())?, simulation);
+        }
+        if attempt >= SIMULATION_SLOT_ATTEMPTS {
+            return Err(anyhow!("баланс прочитан на слоте {}, симуляция на слоте {}", pre_slot, simulation.slot));
+        }
+        attempt += 1;
+    };
+
+    let cu_keys = instruction_keys(instructions, opportunity);
+
+    if let Some(err) = &simulation.err {
+        let failure = classify_failure(err, &simulation.logs);
+        // Расход упавшей инструкции неполный: обычно пропускаем его, при нехватке CU
+        // учитываем как нижнюю границу с надбавкой
+        let failed = failed_instruction_index(err);
+        if failure == SimulationFailure::ComputeBudgetExceeded {
+            COMPUTE_UNITS.record_budget_exceeded(&cu_keys, &simulation.logs, failed);
+        } else {
+            COMPUTE_UNITS.record_execution(&cu_keys, &simulation.logs, failed);
+        }
+
+        let pool = failed
+            .and_then(|i| instructions.get(i))
+            .and_then(|ix| pool_of_instruction(ix, opportunity));
+
+        HEALTH.record_failure(opportunity.chain_id, pool, failure);
+        if failure == SimulationFailure::StaleTickArray {
+            if let Some(pool) = pool {
+                invalidate_pool_keys(&pool);
+            }
+        }
+
+        return Ok(GateVerdict::Reject(SimulationRejection {
+            failure,
+            pool,
+            detail: err.to_string(),
+        }));
+    }
+
+    COMPUTE_UNITS.record_execution(&cu_keys, &simulation.logs, None);
+
+    let post_amount = token_account_amount(simulation.accounts.first().and_then(|a| a.as_ref()))?;
+    let net_profit = post_amount as i64 - pre_amount as i64 - opportunity.costs.total() as i64;
+
+    if !RouterEngine::profit_meets_threshold(net_profit, opportunity.amount_in) {
+        HEALTH.record_failure(opportunity.chain_id, None, SimulationFailure::Unprofitable);
+        return Ok(GateVerdict::Reject(SimulationRejection {
+            failure: SimulationFailure::Unprofitable,
+            pool: None,
+            detail: format!("прибыль по симуляции {} ниже порога (ожидалась {})", net_profit, opportunity.net_profit),
+        }));
+    }
+
+    HEALTH.record_success(opportunity.chain_id, &pools);
+    Ok(GateVerdict::Pass(SimulationReport {
+        net_profit,
+        units_consumed: simulation.units_consumed,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn classifies_tick_array_errors_as_stale() {
+        let err = json!({ "InstructionError": [2, { "Custom": 6023 }] });
+        let logs = logs(&[
+            "Program whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc invoke [1]",
+            "Program log: AnchorError occurred. Error Code: InvalidTickArraySequence. Error Number: 6023.",
+        ]);
+        assert_eq!(classify_failure(&err, &logs), SimulationFailure::StaleTickArray);
+    }
+
+    #[test]
+    fn account_not_initialized_is_not_stale_tick_array() {
+        // Промежуточный ATA маршрута не создан
+        let err = json!({ "InstructionError": [3, { "Custom": 3012 }] });
+        let logs = logs(&[
+            "Program whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc invoke [1]",
+            "Program log: AnchorError caused by account: token_owner_account_b. Error Code: AccountNotInitialized. Error Number: 3012. Error Message: The program expected this account to be already initialized.",
+        ]);
+        assert_eq!(classify_failure(&err, &logs), SimulationFailure::Other);
+    }
+
+    #[test]
+    fn account_not_found_is_not_insufficient_funds() {
+        assert_eq!(classify_failure(&json!("AccountNotFound"), &[]), SimulationFailure::Other);
+        assert_eq!(classify_failure(&json!("InsufficientFundsForFee"), &[]), SimulationFailure::InsufficientFunds);
+    }
+}
@@ -0,0 +1,403 @@
+// src/execution/tx_builder.rs
+
+// Сборка атомарной многохоповой транзакции по арбитражной возможности:
+// compute budget -> создание недостающих ATA -> свап на каждом хопе -> чаевые Jito.
+// Все хопы в одной versioned (v0) транзакции: если любой свап не выполнит
+// min-out, откатывается весь маршрут.
+//
+// Аккаунты маршрута сжимаются через наши address lookup tables (lookup_tables.rs).
+//
+// Сборка детерминирована: одинаковые ключи пулов, подписант и blockhash дают
+// побайтно одинаковую транзакцию, поэтому результат можно сверять с эталонными
+// сериализациями (`serialize_transaction`).
+
+use anyhow::{anyhow, ensure, Result};
+use solana_program::pubkey::Pubkey;
+use solana_program::instruction::Instruction;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{v0, AddressLookupTableAccount, VersionedMessage};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+#[allow(deprecated)]
+use solana_sdk::system_instruction;
+use crate::router::ArbitrageOpportunity;
+use crate::costs::has_token_account;
+use crate::config::{SWAP_SLIPPAGE_BPS, MAX_TRANSACTION_SIZE};
+use crate::execution::accounts::{associated_token_address, create_ata_idempotent_ix};
+use crate::execution::pool_keys::PoolKeys;
+use crate::execution::lookup_tables::LOOKUP_TABLES;
+use crate::execution::ix_orca::swap_v2_ix;
+use crate::execution::ix_raydium::{swap_base_in_ix, clmm_swap_v2_ix};
+use crate::execution::ix_meteora::swap_ix;
+
+/// Параметры сборки транзакции
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildParams {
+    pub compute_unit_limit: u32,
+    /// Цена CU в микролампортах
+    pub compute_unit_price: u64,
+    /// Допустимое проскальзывание на каждом хопе
+    pub slippage_bps: u64,
+    /// Чаевые Jito: tip-аккаунт и сумма. Перевод идет последней инструкцией,
+    /// поэтому платится только при успешном исполнении маршрута
+    pub tip: Option<(Pubkey, u64)>,
+}
+
+impl BuildParams {
+    /// Параметры из оценки затрат возможности
+    pub fn for_opportunity(opportunity: &ArbitrageOpportunity) -> Self {
+        Self {
+            compute_unit_limit: opportunity.costs.compute_units,
+            compute_unit_price: opportunity.costs.cu_price_micro_lamports,
+            slippage_bps: SWAP_SLIPPAGE_BPS,
+            tip: None,
+        }
+    }
+
+    pub fn with_tip(mut self, tip_account: Pubkey, lamports: u64) -> Self {
+        self.tip = (lamports > 0).then_some((tip_account, lamports));
+        self
+    }
+}
+
+/// Вход и минимальный выход хопа
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegAmounts {
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+}
+
+/// Входы и min-out для каждого хопа.
+/// Вход следующего хопа равен min-out предыдущего: он гарантированно есть на счету,
+/// а превышение над min-out остается в промежуточном токене.
+/// Min-out последнего хопа не ниже входа плюс затраты на исполнение, иначе
+/// транзакция откатится вместо убыточного исполнения.
+pub fn plan_leg_amounts(opportunity: &ArbitrageOpportunity, slippage_bps: u64) -> Result<Vec<LegAmounts>> {
+    ensure!(!opportunity.hops.is_empty(), "возможность [{}] без хопов", opportunity.chain_id);
+    ensure!(slippage_bps < 10_000, "некорректное проскальзывание {} bps", slippage_bps);
+
+    let break_even = opportunity.amount_in.saturating_add(opportunity.costs.total());
+    let last = opportunity.hops.len() - 1;
+
+    let mut legs = Vec::with_capacity(opportunity.hops.len());
+    let mut amount_in = opportunity.amount_in;
+
+    for (i, hop) in opportunity.hops.iter().enumerate() {
+        // Ожидаемый выход пропорционально уменьшенному входу
+        let expected_out = (hop.amount_out as u128 * amount_in as u128 / hop.amount_in.max(1) as u128) as u64;
+        let mut min_amount_out = (expected_out as u128 * (10_000 - slippage_bps) as u128 / 10_000) as u64;
+
+        if i == last {
+            ensure!(expected_out > break_even,
+                "возможность [{}]: ожидаемый выход {} не покрывает вход и затраты {}",
+                opportunity.chain_id, expected_out, break_even);
+            min_amount_out = min_amount_out.max(break_even);
+        }
+
+        ensure!(min_amount_out > 0, "возможность [{}]: нулевой min-out на хопе {}", opportunity.chain_id, i);
+        legs.push(LegAmounts { amount_in, min_amount_out });
+        amount_in = min_amount_out;
+    }
+
+    Ok(legs)
+}
+
+/// Инструкция свапа с точным входом для пула любого поддерживаемого типа
+pub fn swap_instruction(
+    keys: &PoolKeys,
+    owner: &Pubkey,
+    input_mint: &Pubkey,
+    output_mint: &Pubkey,
+    leg: LegAmounts,
+) -> Result<Instruction> {
+    let (first, second) = keys.mints();
+    ensure!(
+        (*input_mint == first && *output_mint == second) || (*input_mint == second && *output_mint == first),
+        "пул {} не торгует {} -> {}", keys.pool(), input_mint, output_mint
+    );
+    let first_to_second = *input_mint == first;
+
+    let user_in = associated_token_address(owner, input_mint, &keys.token_program_for(input_mint));
+    let user_out = associated_token_address(owner, output_mint, &keys.token_program_for(output_mint));
+
+    let ix = match keys {
+        PoolKeys::Whirlpool(k) => {
+            let (account_a, account_b) = if first_to_second { (user_in, user_out) } else { (user_out, user_in) };
+            swap_v2_ix(k, owner, &account_a, &account_b, leg.amount_in, leg.min_amount_out, first_to_second)
+        }
+        PoolKeys::RaydiumAmm(k) => {
+            swap_base_in_ix(k, owner, &user_in, &user_out, leg.amount_in, leg.min_amount_out)
+        }
+        PoolKeys::RaydiumClmm(k) => {
+            let tick_arrays = if first_to_second { &k.tick_arrays_zero_for_one } else { &k.tick_arrays_one_for_zero };
+            ensure!(!tick_arrays.is_empty(), "CLMM {}: нет инициализированных tick arrays", k.pool);
+            clmm_swap_v2_ix(k, owner, &user_in, &user_out, leg.amount_in, leg.min_amount_out, first_to_second)
+        }
+        PoolKeys::MeteoraDlmm(k) => {
+            let bin_arrays = if first_to_second { &k.bin_arrays_x_to_y } else { &k.bin_arrays_y_to_x };
+            ensure!(!bin_arrays.is_empty(), "DLMM {}: нет bin arrays", k.pair);
+            swap_ix(k, owner, &user_in, &user_out, leg.amount_in, leg.min_amount_out, first_to_second)
+        }
+    };
+    Ok(ix)
+}
+
+/// Инструкции маршрута. `keys` - ключи пулов хопов (порядок не важен).
+pub fn build_route_instructions(
+    opportunity: &ArbitrageOpportunity,
+    keys: &[PoolKeys],
+    owner: &Pubkey,
+    params: BuildParams,
+) -> Result<Vec<Instruction>> {
+    let legs = plan_leg_amounts(opportunity, params.slippage_bps)?;
+
+    let mut instructions = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(params.compute_unit_limit),
+        ComputeBudgetInstruction::set_compute_unit_price(params.compute_unit_price),
+    ];
+
+    let mut swaps = Vec::with_capacity(opportunity.hops.len());
+    let mut created: Vec<Pubkey> = Vec::new();
+
+    for (hop, leg) in opportunity.hops.iter().zip(legs) {
+        let pool_keys = keys.iter()
+            .find(|k| k.pool() == hop.pool_address)
+            .ok_or_else(|| anyhow!("нет ключей пула {}", hop.pool_address))?;
+
+        // Недостающие ATA создаем идемпотентно до свапов
+        if !has_token_account(&hop.output_mint, &hop.to_token) && !created.contains(&hop.output_mint) {
+            instructions.push(create_ata_idempotent_ix(
+                owner, owner, &hop.output_mint, &pool_keys.token_program_for(&hop.output_mint)
+            ));
+            created.push(hop.output_mint);
+        }
+
+        swaps.push(swap_instruction(pool_keys, owner, &hop.input_mint, &hop.output_mint, leg)?);
+    }
+
+    instructions.extend(swaps);
+
+    if let Some((tip_account, lamports)) = params.tip {
+        instructions.push(system_instruction::transfer(owner, &tip_account, lamports));
+    }
+
+    Ok(instructions)
+}
+
+/// Компиляция инструкций в v0 сообщение и подпись
+pub fn compile_transaction(
+    payer: &Keypair,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    let message = v0::Message::try_compile(&payer.pubkey(), instructions, lookup_tables, recent_blockhash)?;
+    Ok(VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer])?)
+}
+
+/// Компиляция готовых инструкций с таблицами поиска менеджера ALT и проверкой размера
+pub fn compile_with_lookup_tables(
+    payer: &Keypair,
+    instructions: &[Instruction],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    let lookup_tables = LOOKUP_TABLES.tables_for(instructions);
+    let tx = compile_transaction(payer, instructions, &lookup_tables, recent_blockhash)?;
+
+    let size = serialize_transaction(&tx).len();
+    ensure!(size <= MAX_TRANSACTION_SIZE,
+        "транзакция {} байт превышает лимит {} (таблиц: {})",
+        size, MAX_TRANSACTION_SIZE, lookup_tables.len());
+    Ok(tx)
+}
+
+// compact-u16 длина (short_vec) из формата транзакций Solana
+fn encode_compact_u16(mut value: u16, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        byte |= 0x80;
+        out.push(byte);
+    }
+}
+
+/// Сериализация транзакции в wire-формат (как для sendTransaction)
+pub fn serialize_transaction(tx: &VersionedTransaction) -> Vec<u8> {
+    let message = tx.message.serialize();
+    let mut out = Vec::with_capacity(1 + tx.signatures.len() * 64 + message.len());
+    encode_compact_u16(tx.signatures.len() as u16, &mut out);
+    for signature in &tx.signatures {
+        out.extend_from_slice(signature.as_ref());
+    }
+    out.extend_from_slice(&message);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::costs::ExecutionCosts;
+    use crate::router::{OpportunitySource, SimulatedHop};
+    use crate::websocket::ws_data::DexType;
+    use crate::websocket::ws_parser::PoolCommitment;
+    use crate::execution::fixtures::{
+        assert_fixture, blockhash, key, payer, mint_a, mint_b, mint_c, mint_d,
+        whirlpool_keys, amm_v4_keys, clmm_keys, dlmm_keys,
+    };
+
+    fn hop(dex: DexType, pool_address: Pubkey, from: (&str, Pubkey), to: (&str, Pubkey), amount_in: u64, amount_out: u64) -> SimulatedHop {
+        SimulatedHop {
+            dex,
+            pool_address,
+            from_token: from.0.to_string(),
+            to_token: to.0.to_string(),
+            input_mint: from.1,
+            output_mint: to.1,
+            amount_in,
+            amount_out,
+            pool_slot: 1,
+            crossings: 0,
+        }
+    }
+
+    // Маршрут A -> B -> C -> D -> A через все четыре типа пулов.
+    // D не входит в INITIAL_TOKENS, поэтому перед свапами создается его ATA.
+    fn route_opportunity() -> ArbitrageOpportunity {
+        let a = ("SOL", mint_a());
+        let b = ("USDC", mint_b());
+        let c = ("USDT", mint_c());
+        let d = ("FIXTURE", mint_d());
+        ArbitrageOpportunity {
+            chain_id: 7,
+            chain: vec!["SOL".into(), "USDC".into(), "USDT".into(), "FIXTURE".into()],
+            commitment: PoolCommitment::Processed,
+            source: OpportunitySource::Router,
+            amount_in: 1_000_000,
+            amount_out: 1_100_000,
+            expected_profit: 100_000,
+            costs: ExecutionCosts {
+                signature_fee: 5_000,
+                priority_fee: 6_000,
+                jito_tip: 10_000,
+                ata_rent: 2_000,
+                compute_units: 600_000,
+                cu_price_micro_lamports: 10_000,
+                missing_token_accounts: 1,
+            },
+            net_profit: 77_000,
+            hops: vec![
+                hop(DexType::Orca, key(10), a, b, 1_000_000, 2_000_000),
+                hop(DexType::Raydium, key(20), b, c, 2_000_000, 3_000_000),
+                hop(DexType::Raydium, key(40), c, d, 3_000_000, 1_500_000),
+                hop(DexType::Meteora, key(50), d, a, 1_500_000, 1_100_000),
+            ],
+        }
+    }
+
+    fn route_keys() -> Vec<PoolKeys> {
+        vec![
+            PoolKeys::MeteoraDlmm(dlmm_keys()),
+            PoolKeys::RaydiumClmm(clmm_keys()),
+            PoolKeys::RaydiumAmm(amm_v4_keys()),
+            PoolKeys::Whirlpool(whirlpool_keys()),
+        ]
+    }
+
+    fn route_params() -> BuildParams {
+        BuildParams {
+            compute_unit_limit: 600_000,
+            compute_unit_price: 10_000,
+            slippage_bps: 50,
+            tip: None,
+        }.with_tip(key(90), 10_000)
+    }
+
+    fn compact_u16(value: u16) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_compact_u16(value, &mut out);
+        out
+    }
+
+    #[test]
+    fn compact_u16_matches_short_vec_encoding() {
+        assert_eq!(compact_u16(0), [0x00]);
+        assert_eq!(compact_u16(1), [0x01]);
+        assert_eq!(compact_u16(0x7f), [0x7f]);
+        assert_eq!(compact_u16(0x80), [0x80, 0x01]);
+        assert_eq!(compact_u16(0xff), [0xff, 0x01]);
+        assert_eq!(compact_u16(0x3fff), [0xff, 0x7f]);
+        assert_eq!(compact_u16(0x4000), [0x80, 0x80, 0x01]);
+        assert_eq!(compact_u16(u16::MAX), [0xff, 0xff, 0x03]);
+    }
+
+    #[test]
+    fn leg_amounts_chain_min_outs_and_cover_break_even() {
+        let opportunity = route_opportunity();
+        let legs = plan_leg_amounts(&opportunity, 50).unwrap();
+
+        assert_eq!(legs.len(), 4);
+        assert_eq!(legs[0].amount_in, opportunity.amount_in);
+        for pair in legs.windows(2) {
+            assert_eq!(pair[1].amount_in, pair[0].min_amount_out);
+        }
+        let break_even = opportunity.amount_in + opportunity.costs.total();
+        assert!(legs[3].min_amount_out >= break_even);
+    }
+
+    #[test]
+    fn route_instructions_order() {
+        let owner = key(100);
+        let ixs = build_route_instructions(&route_opportunity(), &route_keys(), &owner, route_params()).unwrap();
+
+        // 2 compute budget + ATA для D + 4 свапа + чаевые
+        assert_eq!(ixs.len(), 8);
+        let programs: Vec<Pubkey> = ixs.iter().map(|ix| ix.program_id).collect();
+        assert_eq!(programs[3..7], [
+            *crate::execution::accounts::ORCA_PROGRAM,
+            *crate::execution::accounts::RAYDIUM_V4_PROGRAM,
+            *crate::execution::accounts::RAYDIUM_CLMM_PROGRAM,
+            *crate::execution::accounts::METEORA_PROGRAM,
+        ]);
+        assert_eq!(ixs[2], create_ata_idempotent_ix(&owner, &owner, &mint_d(),
+            &crate::execution::accounts::TOKEN_2022_PROGRAM));
+    }
+
+    #[test]
+    fn route_transaction_matches_fixture() {
+        let payer = payer();
+        let ixs = build_route_instructions(&route_opportunity(), &route_keys(), &payer.pubkey(), route_params()).unwrap();
+        let tx = compile_transaction(&payer, &ixs, &[], blockhash()).unwrap();
+
+        let bytes = serialize_transaction(&tx);
+        assert_fixture("route_v0_transaction", &bytes);
+
+        // Wire-формат: compact-u16 числа подписей, подписи, затем сообщение с префиксом версии
+        assert_eq!(bytes[0], 1);
+        assert_eq!(&bytes[1..65], tx.signatures[0].as_ref());
+        assert_eq!(bytes[65], 0x80);
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+    }
+
+    #[test]
+    fn route_transaction_with_lookup_table_matches_fixture() {
+        let payer = payer();
+        let ixs = build_route_instructions(&route_opportunity(), &route_keys(), &payer.pubkey(), route_params()).unwrap();
+        let table = AddressLookupTableAccount {
+            key: key(200),
+            addresses: vec![key(11), key(12), key(21), key(22), key(42), key(43), key(51), key(52)],
+        };
+        let tx = compile_transaction(&payer, &ixs, &[table], blockhash()).unwrap();
+
+        let VersionedMessage::V0(message) = &tx.message else { panic!("ожидалось v0 сообщение") };
+        assert_eq!(message.address_table_lookups.len(), 1);
+        assert_eq!(message.address_table_lookups[0].writable_indexes.len(), 8);
+
+        assert_fixture("route_v0_transaction_lookup_table", &serialize_transaction(&tx));
+    }
+}
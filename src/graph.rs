@@ -23,6 +23,8 @@ pub struct PoolEdge {
     pub fee_rate: f64,
     pub liquidity: f64,
     pub is_active: bool,
+    // Состояние пула могло устареть (обрыв WebSocket), ребро не используется до дозагрузки
+    pub stale: bool,
     pub current_amount: u64,
     pub chain_position: Option<usize>,
     pub last_update_slot: u64,
@@ -39,6 +41,7 @@ impl PoolEdge {
             liquidity: 0.0,
             weight: 0.0,
             is_active: true,
+            stale: false,
             current_amount: 0,
             chain_position: None,
             last_update_slot: 0,
@@ -94,9 +97,10 @@ impl ValidPairs {
 pub fn build_and_find_chains() {
     info!("Начинаем инициализацию графов");
 
-    // Явно создаем графы
-    GLOBAL_DATA.processed_graph.insert("main".to_string(), Graph::new());
-    GLOBAL_DATA.finalized_graph.insert("main".to_string(), Graph::new());
+    // Явно создаем графы активных commitment
+    for (_, graph) in GLOBAL_DATA.commitment_graphs() {
+        graph.insert("main".to_string(), Graph::new());
+    }
 
     // Валидируем токены и строим цепочки
     let initial_tokens = crate::config::INITIAL_TOKENS;
@@ -366,7 +370,7 @@ pub fn build_and_find_chains() {
     }
 
     // После создания цепочек инициализируем графы
-    for graph in [&GLOBAL_DATA.processed_graph, &GLOBAL_DATA.finalized_graph] {
+    for (_, graph) in GLOBAL_DATA.commitment_graphs() {
         if let Some(mut g) = graph.get_mut("main") {
             // Для каждой цепочки создаем ребра
             for chain in chains_by_4.iter().chain(chains_by_5.iter()) {
@@ -390,6 +394,7 @@ pub fn build_and_find_chains() {
                                 fee_rate: 0.0,
                                 liquidity: 0.0,
                                 is_active: true,
+                                stale: false,
                                 current_amount: 0,
                                 chain_position: None,
                                 last_update_slot: 0,
@@ -0,0 +1,176 @@
+// src/ledger.rs
+
+// Журнал сделок.
+// Каждая отправленная транзакция после подтверждения или истечения
+// (execution/landing.rs) записывается сюда: возможность, ожидаемая прибыль модели,
+// прибыль по симуляции, фактическая прибыль, уплаченные комиссии и причина неудачи.
+// Записи дописываются в LEDGER_FILE (JSON в строке) и последние LEDGER_RETENTION_SECS
+// держатся в памяти для часовых и суточных сводок.
+// В режиме paper trading журнал ведется в PAPER_LEDGER_FILE (execution/paper.rs).
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Mutex;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use crate::config::{CONFIG, LEDGER_FILE, PAPER_LEDGER_FILE, LEDGER_RETENTION_SECS};
+use crate::data::unix_timestamp;
+
+/// Итог отправленной транзакции
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeStatus {
+    /// Транзакция в блоке и исполнилась
+    Landed,
+    /// Транзакция в блоке, но упала (комиссия уплачена)
+    Failed,
+    /// Бандл отклонен или транзакция не попала в блок до истечения blockhash
+    Expired,
+    /// Paper trading: не отправлялась, прибыль оценена по следующему состоянию пулов
+    Paper,
+}
+
+/// Запись журнала
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub signature: String,
+    pub bundle_id: Option<String>,
+    pub chain_id: usize,
+    pub chain: Vec<String>,
+    pub source: String,
+    pub amount_in: u64,
+    /// Чистая прибыль по модели роутера
+    pub expected_profit: i64,
+    /// Чистая прибыль по предварительной симуляции
+    pub simulated_profit: i64,
+    /// Изменение стартового токена и SOL кошелька по pre/post балансам транзакции
+    pub realized_profit: i64,
+    /// Базовая + приоритетная комиссия и чаевые Jito
+    pub fees_paid: u64,
+    pub status: TradeStatus,
+    pub slot: Option<u64>,
+    pub failure: Option<String>,
+    pub sent_at: u64,
+    pub resolved_at: u64,
+}
+
+/// Сводка по записям за период
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LedgerSummary {
+    pub trades: usize,
+    pub landed: usize,
+    pub failed: usize,
+    pub expired: usize,
+    pub paper: usize,
+    pub expected_profit: i64,
+    pub simulated_profit: i64,
+    pub realized_profit: i64,
+    pub fees_paid: u64,
+}
+
+impl LedgerSummary {
+    fn add(&mut self, entry: &LedgerEntry) {
+        self.trades += 1;
+        match entry.status {
+            TradeStatus::Landed => self.landed += 1,
+            TradeStatus::Failed => self.failed += 1,
+            TradeStatus::Expired => self.expired += 1,
+            TradeStatus::Paper => self.paper += 1,
+        }
+        self.expected_profit += entry.expected_profit;
+        self.simulated_profit += entry.simulated_profit;
+        self.realized_profit += entry.realized_profit;
+        self.fees_paid += entry.fees_paid;
+    }
+
+    /// Доля попавших в блок отправленных транзакций
+    pub fn landing_rate(&self) -> f64 {
+        let sent = self.trades - self.paper;
+        if sent == 0 {
+            0.0
+        } else {
+            (self.landed + self.failed) as f64 / sent as f64
+        }
+    }
+}
+
+pub struct Ledger {
+    path: &'static str,
+    entries: Mutex<VecDeque<LedgerEntry>>,
+}
+
+lazy_static! {
+    // Сделки paper trading пишутся в отдельный журнал
+    pub static ref LEDGER: Ledger = Ledger::load(
+        if CONFIG.paper_trading { PAPER_LEDGER_FILE } else { LEDGER_FILE }
+    );
+}
+
+impl Ledger {
+    /// Записи за последние LEDGER_RETENTION_SECS из файла журнала
+    pub fn load(path: &'static str) -> Self {
+        let oldest = unix_timestamp().saturating_sub(LEDGER_RETENTION_SECS);
+        let entries: VecDeque<LedgerEntry> = std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str::<LedgerEntry>(line).ok())
+            .filter(|entry| entry.resolved_at >= oldest)
+            .collect();
+
+        if !entries.is_empty() {
+            info!("ledger: загружено {} записей из {}", entries.len(), path);
+        }
+        Self { path, entries: Mutex::new(entries) }
+    }
+
+    /// Добавление записи в память и в файл журнала
+    pub fn record(&self, entry: LedgerEntry) {
+        if let Err(e) = append_entry(self.path, &entry) {
+            warn!("ledger: не удалось записать {} в {}: {}", entry.signature, self.path, e);
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.push_back(entry);
+        let oldest = unix_timestamp().saturating_sub(LEDGER_RETENTION_SECS);
+        while entries.front().is_some_and(|e| e.resolved_at < oldest) {
+            entries.pop_front();
+        }
+    }
+
+    /// Сводка за последние `secs` секунд
+    pub fn summary_since(&self, secs: u64) -> LedgerSummary {
+        let since = unix_timestamp().saturating_sub(secs);
+        let mut summary = LedgerSummary::default();
+        for entry in self.entries.lock().unwrap().iter().filter(|e| e.resolved_at >= since) {
+            summary.add(entry);
+        }
+        summary
+    }
+
+    /// Сводка за последний час
+    pub fn hourly(&self) -> LedgerSummary {
+        self.summary_since(3_600)
+    }
+
+    /// Сводка за последние сутки
+    pub fn daily(&self) -> LedgerSummary {
+        self.summary_since(86_400)
+    }
+}
+
+fn append_entry(path: &str, entry: &LedgerEntry) -> anyhow::Result<()> {
+    let line = serde_json::to_string(entry)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Вывод часовой и суточной сводки
+pub fn report() {
+    for (period, summary) in [("час", LEDGER.hourly()), ("сутки", LEDGER.daily())] {
+        info!("ledger: за {}: сделок {} (в блоке {}, упало {}, истекло {}, paper {}, попадание {:.1}%), прибыль ожидаемая {} / по симуляции {} / фактическая {}, комиссии {}",
+            period, summary.trades, summary.landed, summary.failed, summary.expired, summary.paper,
+            summary.landing_rate() * 100.0, summary.expected_profit, summary.simulated_profit, summary.realized_profit, summary.fees_paid);
+    }
+}
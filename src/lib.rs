@@ -7,4 +7,14 @@ pub mod decoder;
 pub mod websocket;
 pub mod math;
 pub mod graph;
-pub mod router;
\ No newline at end of file
+pub mod router;
+pub mod arbitrage;
+pub mod quote;
+pub mod opportunity_book;
+pub mod costs;
+pub mod priority_fees;
+pub mod compute_units;
+pub mod rpc;
+pub mod execution;
+pub mod wallet;
+pub mod ledger;
\ No newline at end of file
@@ -10,15 +10,23 @@ mod shred;
 mod graph;
 mod math;
 mod router;
+mod arbitrage;
+mod quote;
+mod opportunity_book;
+mod costs;
+mod priority_fees;
+mod compute_units;
+mod rpc;
+mod execution;
+mod wallet;
+mod ledger;
 
 #[allow(unused_imports)]
 use log::{info, error};
 use crate::data::GLOBAL_DATA;
 use crate::websocket::ws_data::DexType;
 
-use crate::websocket::ws_orca::{start_orca_websocket_finalized, start_orca_websocket_processed};
-use crate::websocket::ws_raydium::{start_raydium_websocket_finalized, start_raydium_websocket_processed};
-use crate::websocket::ws_meteora::{start_meteora_websocket_finalized, start_meteora_websocket_processed};
+use crate::websocket::ws_runner::{start_dex_subscriptions, stop_dex_subscriptions};
 
 use crate::config::{INITIALIZE_HTTP_CLIENT, get_config, DEFAULT_QUOTE_API_URL};
 #[allow(unused_imports)]
@@ -80,6 +88,47 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Запуск построения графа и поиска цепочек...");
     graph::build_and_find_chains();
     info!("Построение графа и поиск цепочек завершены");
+
+    // Запуск симуляции пересчитанных цепочек и публикации арбитражных возможностей
+    tokio::spawn(router::RouterEngine::run_simulation_worker());
+
+    // Периодический полный обход цепочек на снимке графа
+    tokio::spawn(arbitrage::monitor_arbitrage_opportunities());
+
+    // Книга возможностей: дедупликация, ранжирование и выдача пакетов исполнителю раз в слот
+    tokio::spawn(opportunity_book::run_opportunity_book());
+
+    // Приоритетные комиссии по пулам маршрутов для цены CU
+    tokio::spawn(priority_fees::run_priority_fee_estimator());
+
+    // Address lookup tables с аккаунтами всех пулов для v0 транзакций маршрутов
+    tokio::spawn(execution::lookup_tables::run_lookup_table_manager());
+
+    // Кеш blockhash: обновление по таймеру и по новым слотам
+    tokio::spawn(execution::blockhash::run_blockhash_cache());
+
+    // Tip-аккаунты Jito и статусы отправленных бандлов
+    tokio::spawn(execution::jito::run_bundle_tracker());
+
+    // Круговые котировки локального Jupiter: дополнительные возможности и сверка котировщиков
+    tokio::spawn(quote::run_quote_scanner());
+
+    // Кошелек: ATA, живые балансы и резерв SOL / wSOL
+    tokio::spawn(wallet::run_wallet_manager());
+
+    // Kill switch исполнителя: файл и сигналы
+    tokio::spawn(execution::risk::run_kill_switch());
+
+    // Исполнитель: сборка транзакций, предварительная симуляция и отправка
+    tokio::spawn(execution::executor::run_executor());
+
+    // Итоги отправленных транзакций и журнал сделок с PnL
+    tokio::spawn(execution::landing::run_landing_tracker());
+
+    // Paper trading: оценка несостоявшихся отправок по следующему состоянию пулов
+    if config::CONFIG.paper_trading {
+        tokio::spawn(execution::paper::run_paper_marker());
+    }
 /*
     // TODO: Запуск RPC вызова для получения актуальных данных
 
@@ -94,38 +143,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // quote::test_valid_pools().await?;
 */ 
 
-    // Запуск подписки на пулах Finalized в отдельной задаче
-    tokio::spawn(async {
-        let _ = start_orca_websocket_finalized().await;
-    });
-
-    // Запуск подписки на пулах Processed в отдельной задаче
-    tokio::spawn(async {
-        let _ = start_orca_websocket_processed().await;
-    });
-
-    // Запуск подписки на пулах Raydium CLMM и Raydium V4 finalized
-    tokio::spawn(async {
-        let _ = start_raydium_websocket_finalized().await;
-    });
-    
-    // Запуск подписки на пулах Raydium CLMM и Raydium V4 Processed
-    tokio::spawn(async {
-        let _ = start_raydium_websocket_processed().await;
-    });
-
-    // Запуск подписки на пулах Meteora finalized
-    tokio::spawn(async {
-        let _ = start_meteora_websocket_finalized().await;
-    });
-        
-    // Запуск подписки на пулах Meteora Processed
-    tokio::spawn(async {
-        let _ = start_meteora_websocket_processed().await;
-    });
+    // Запуск подписок на пулы DEX (активные уровни commitment) и слоты
+    let subscriptions = start_dex_subscriptions();
 
     // Держим главный поток активным
     tokio::signal::ctrl_c().await.unwrap();
 
+    // Снимаем подписки WebSocket перед выходом
+    stop_dex_subscriptions(subscriptions).await;
+
     Ok(())
 }
\ No newline at end of file
@@ -75,4 +75,67 @@ pub mod weight_calculators {
         let multiplier_factor = (multiplier as f64 / 100.0).min(2.0);
         base * (1.0 + multiplier_factor)
     }
-}
\ No newline at end of file
+}
+
+// Котировщики свопов для симуляции цепочек (чистая математика без обращения к GLOBAL_DATA)
+pub mod quoters {
+    const Q64: f64 = 18_446_744_073_709_551_616.0; // 2^64
+
+    // Своп в концентрированной ликвидности в пределах текущего диапазона (Orca Whirlpool, Meteora)
+    // sqrt_price_x64 - корень цены token_b/token_a в формате Q64.64
+    // a_to_b = true: на вход token_a, цена снижается
+    pub fn quote_concentrated(
+        amount_in: u64,
+        sqrt_price_x64: u128,
+        liquidity: u128,
+        fee_rate: f64,
+        a_to_b: bool
+    ) -> u64 {
+        if liquidity == 0 || sqrt_price_x64 == 0 {
+            return 0;
+        }
+
+        let liquidity = liquidity as f64;
+        let sqrt_price = sqrt_price_x64 as f64 / Q64;
+        let net_in = amount_in as f64 * (1.0 - fee_rate);
+
+        let amount_out = if a_to_b {
+            // 1/√P' = 1/√P + Δx/L
+            let sqrt_price_new = liquidity * sqrt_price / (liquidity + net_in * sqrt_price);
+            liquidity * (sqrt_price - sqrt_price_new)
+        } else {
+            // √P' = √P + Δy/L
+            let sqrt_price_new = sqrt_price + net_in / liquidity;
+            liquidity * (1.0 / sqrt_price - 1.0 / sqrt_price_new)
+        };
+
+        amount_out.max(0.0) as u64
+    }
+
+    // Сдвиг цены свопа в концентрированной ликвидности, в тиках (тик - множитель цены 1.0001).
+    // Как и quote_concentrated, считает в пределах текущей ликвидности.
+    pub fn concentrated_tick_move(
+        amount_in: u64,
+        sqrt_price_x64: u128,
+        liquidity: u128,
+        fee_rate: f64,
+        a_to_b: bool
+    ) -> f64 {
+        if liquidity == 0 || sqrt_price_x64 == 0 {
+            return 0.0;
+        }
+
+        let liquidity = liquidity as f64;
+        let sqrt_price = sqrt_price_x64 as f64 / Q64;
+        let net_in = amount_in as f64 * (1.0 - fee_rate);
+
+        let sqrt_price_new = if a_to_b {
+            liquidity * sqrt_price / (liquidity + net_in * sqrt_price)
+        } else {
+            sqrt_price + net_in / liquidity
+        };
+
+        // P = 1.0001^tick => Δtick = 2 * ln(√P'/√P) / ln(1.0001)
+        (2.0 * (sqrt_price_new / sqrt_price).ln() / 1.0001_f64.ln()).abs()
+    }
+}
@@ -0,0 +1,383 @@
+// src/opportunity_book.rs
+
+// Книга арбитражных возможностей между роутером/полным обходом и исполнителем:
+// - дедупликация: повернутые цепочки и processed/finalized дубли одного цикла
+//   сводятся к одному ключу (последовательность пулов от минимального адреса);
+// - ранжирование по чистой прибыли (за вычетом затрат на исполнение);
+// - экспирация при продвижении слота любого из пулов возможности (в пределах своего commitment);
+// - раз в слот исполнителю отдается лучший набор возможностей без общих пулов;
+//   непрочитанный исполнителем пакет вытесняется новым.
+
+use std::sync::Mutex;
+use std::time::Duration;
+use hashbrown::{HashMap, HashSet};
+use lazy_static::lazy_static;
+use log::{info, debug, warn};
+use flume::{Sender, Receiver, TrySendError};
+use solana_program::pubkey::Pubkey;
+use tokio::sync::broadcast::error::RecvError;
+use crate::data::GLOBAL_DATA;
+use crate::websocket::ws_parser::PoolCommitment;
+use crate::router::{ArbitrageOpportunity, subscribe_opportunities};
+use crate::priority_fees::PRIORITY_FEES;
+use crate::config::{MAX_EXECUTION_BATCH_SIZE, BOOK_SLOT_POLL_MS, EXECUTION_BATCH_QUEUE_SIZE};
+
+// Запись книги: возможность и слоты пулов, на которых она посчитана
+#[derive(Debug, Clone)]
+struct BookEntry {
+    opportunity: ArbitrageOpportunity,
+    pool_slots: Vec<(Pubkey, u64)>,
+}
+
+impl BookEntry {
+    fn newest_slot(&self) -> u64 {
+        self.pool_slots.iter().map(|(_, slot)| *slot).max().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Default)]
+struct BookInner {
+    // Ключ - последовательность пулов цикла, повернутая к минимальному адресу
+    entries: HashMap<Vec<Pubkey>, BookEntry>,
+    // Последний известный слот обновления каждого пула по commitment
+    pool_slots: HashMap<(PoolCommitment, Pubkey), u64>,
+}
+
+/// Ранжированная книга возможностей
+#[derive(Debug, Default)]
+pub struct OpportunityBook {
+    inner: Mutex<BookInner>,
+}
+
+lazy_static! {
+    pub static ref OPPORTUNITY_BOOK: OpportunityBook = OpportunityBook::default();
+
+    // Канал пакетов на исполнение (один пакет на слот). Если исполнитель не успевает,
+    // непрочитанный пакет вытесняется новым: его возможности посчитаны на старых слотах
+    static ref EXECUTION_BATCHES: (Sender<Vec<ArbitrageOpportunity>>, Receiver<Vec<ArbitrageOpportunity>>) =
+        flume::bounded(EXECUTION_BATCH_QUEUE_SIZE);
+}
+
+/// Получатель пакетов возможностей для исполнителя
+pub fn execution_batches() -> Receiver<Vec<ArbitrageOpportunity>> {
+    EXECUTION_BATCHES.1.clone()
+}
+
+/// Ключ дедупликации: пулы цикла в порядке прохождения, начиная с минимального адреса.
+/// Повороты одного цикла дают одинаковый ключ, обратное направление - другой.
+pub fn cycle_key(opportunity: &ArbitrageOpportunity) -> Vec<Pubkey> {
+    let pools: Vec<Pubkey> = opportunity.hops.iter().map(|h| h.pool_address).collect();
+    let start = pools
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, pool)| **pool)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+
+    pools[start..].iter().chain(pools[..start].iter()).copied().collect()
+}
+
+impl OpportunityBook {
+    /// Добавляет возможность. Возвращает false, если она устарела или хуже имеющейся.
+    pub fn insert(&self, opportunity: ArbitrageOpportunity) -> bool {
+        let pool_slots: Vec<(Pubkey, u64)> = opportunity.hops
+            .iter()
+            .map(|h| (h.pool_address, h.pool_slot))
+            .collect();
+
+        let commitment = opportunity.commitment;
+        let mut inner = self.inner.lock().unwrap();
+
+        // Возможность посчитана на состоянии своего commitment, которое уже сменилось
+        let is_stale = pool_slots.iter().any(|(pool, slot)| {
+            inner.pool_slots.get(&(commitment, *pool)).map(|latest| latest > slot).unwrap_or(false)
+        });
+        if is_stale {
+            debug!("book: отброшена устаревшая возможность по цепочке [{}]", opportunity.chain_id);
+            return false;
+        }
+
+        let entry = BookEntry { opportunity, pool_slots };
+        let key = cycle_key(&entry.opportunity);
+
+        if let Some(existing) = inner.entries.get(&key) {
+            // Оставляем запись на более свежих данных, при равных - более прибыльную
+            let replace = entry.newest_slot() > existing.newest_slot()
+                || (entry.newest_slot() == existing.newest_slot()
+                    && entry.opportunity.net_profit > existing.opportunity.net_profit);
+            if !replace {
+                return false;
+            }
+        }
+
+        // Комиссии опрашиваем только по пулам маршрутов, дошедших до книги
+        let pools: Vec<Pubkey> = entry.pool_slots.iter().map(|(pool, _)| *pool).collect();
+        inner.entries.insert(key, entry);
+        drop(inner);
+
+        PRIORITY_FEES.touch_pools(&pools);
+        true
+    }
+
+    /// Обновление пула в `commitment`: удаляем возможности этого commitment,
+    /// посчитанные на более старом слоте пула.
+    pub fn on_pool_update(&self, commitment: PoolCommitment, pool_address: Pubkey, slot: u64) {
+        let mut inner = self.inner.lock().unwrap();
+
+        let latest = inner.pool_slots.entry((commitment, pool_address)).or_insert(0);
+        if slot <= *latest {
+            return;
+        }
+        *latest = slot;
+
+        inner.entries.retain(|_, entry| {
+            entry.opportunity.commitment != commitment || !entry.pool_slots
+                .iter()
+                .any(|(pool, pool_slot)| *pool == pool_address && *pool_slot < slot)
+        });
+    }
+
+    /// Удаляет возможности через пул, посчитанные на состоянии `commitment` (оно устарело).
+    pub fn remove_pool(&self, pool_address: &Pubkey, commitment: PoolCommitment) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.retain(|_, entry| {
+            entry.opportunity.commitment != commitment
+                || !entry.pool_slots.iter().any(|(pool, _)| pool == pool_address)
+        });
+    }
+
+    /// Забирает из книги лучший набор возможностей без общих пулов (не более `max`).
+    pub fn take_best_non_conflicting(&self, max: usize) -> Vec<ArbitrageOpportunity> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let mut ranked: Vec<(&Vec<Pubkey>, &BookEntry)> = inner.entries.iter().collect();
+        ranked.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.opportunity.net_profit));
+
+        let mut used_pools: HashSet<Pubkey> = HashSet::new();
+        let mut selected_keys = Vec::new();
+
+        for (key, entry) in ranked {
+            if selected_keys.len() >= max {
+                break;
+            }
+            if entry.pool_slots.iter().any(|(pool, _)| used_pools.contains(pool)) {
+                continue;
+            }
+            used_pools.extend(entry.pool_slots.iter().map(|(pool, _)| *pool));
+            selected_keys.push(key.clone());
+        }
+
+        selected_keys
+            .iter()
+            .filter_map(|key| inner.entries.remove(key))
+            .map(|entry| entry.opportunity)
+            .collect()
+    }
+
+    /// Количество возможностей в книге
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Передача пакета исполнителю без ожидания: при полной очереди вытесняется самый старый пакет
+fn send_batch(mut batch: Vec<ArbitrageOpportunity>) {
+    loop {
+        match EXECUTION_BATCHES.0.try_send(batch) {
+            Ok(()) => return,
+            Err(TrySendError::Full(rejected)) => {
+                if let Ok(stale) = EXECUTION_BATCHES.1.try_recv() {
+                    debug!("book: исполнитель не успевает, вытеснен пакет из {} возможностей", stale.len());
+                }
+                batch = rejected;
+            }
+            Err(TrySendError::Disconnected(_)) => return,
+        }
+    }
+}
+
+/// Запуск книги: сбор возможностей из канала роутера и выдача пакетов исполнителю раз в слот.
+pub async fn run_opportunity_book() {
+    info!("book: запуск книги арбитражных возможностей");
+
+    // Сбор возможностей
+    tokio::spawn(async {
+        let mut rx = subscribe_opportunities();
+        loop {
+            match rx.recv().await {
+                Ok(opportunity) => {
+                    OPPORTUNITY_BOOK.insert(opportunity);
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("book: пропущено {} возможностей из-за переполнения канала", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // Выдача пакетов по смене слота
+    let mut last_slot = 0;
+    let mut interval = tokio::time::interval(Duration::from_millis(BOOK_SLOT_POLL_MS));
+    loop {
+        interval.tick().await;
+
+        let current_slot = GLOBAL_DATA.network_state
+            .get("current")
+            .map(|s| s.current_slot)
+            .unwrap_or(0);
+        if current_slot == last_slot {
+            continue;
+        }
+        last_slot = current_slot;
+        if OPPORTUNITY_BOOK.is_empty() {
+            continue;
+        }
+
+        let batch = OPPORTUNITY_BOOK.take_best_non_conflicting(MAX_EXECUTION_BATCH_SIZE);
+        if batch.is_empty() {
+            continue;
+        }
+
+        debug!("book: слот {}: на исполнение {} возможностей, в книге осталось {}",
+            current_slot, batch.len(), OPPORTUNITY_BOOK.len());
+        send_batch(batch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::costs::ExecutionCosts;
+    use crate::router::{OpportunitySource, SimulatedHop};
+    use crate::websocket::ws_data::DexType;
+
+    fn pool(n: u8) -> Pubkey {
+        Pubkey::new_from_array([n; 32])
+    }
+
+    // Возможность по циклу через `pools` (пул, слот) в порядке прохождения
+    fn opportunity(commitment: PoolCommitment, pools: &[(u8, u64)], net_profit: i64) -> ArbitrageOpportunity {
+        let hops = pools.iter().map(|&(n, slot)| SimulatedHop {
+            dex: DexType::Orca,
+            pool_address: pool(n),
+            from_token: String::new(),
+            to_token: String::new(),
+            input_mint: Pubkey::default(),
+            output_mint: Pubkey::default(),
+            amount_in: 1_000,
+            amount_out: 1_000,
+            pool_slot: slot,
+            crossings: 0,
+        }).collect();
+
+        ArbitrageOpportunity {
+            chain_id: 0,
+            chain: Vec::new(),
+            commitment,
+            source: OpportunitySource::Router,
+            amount_in: 1_000,
+            amount_out: 1_000,
+            expected_profit: net_profit,
+            costs: ExecutionCosts::default(),
+            net_profit,
+            hops,
+        }
+    }
+
+    fn processed(pools: &[(u8, u64)], net_profit: i64) -> ArbitrageOpportunity {
+        opportunity(PoolCommitment::Processed, pools, net_profit)
+    }
+
+    #[test]
+    fn rotated_cycles_share_key() {
+        let key = cycle_key(&processed(&[(1, 0), (2, 0), (3, 0)], 0));
+        assert_eq!(key, vec![pool(1), pool(2), pool(3)]);
+        assert_eq!(cycle_key(&processed(&[(2, 0), (3, 0), (1, 0)], 0)), key);
+        assert_eq!(cycle_key(&processed(&[(3, 0), (1, 0), (2, 0)], 0)), key);
+
+        let book = OpportunityBook::default();
+        assert!(book.insert(processed(&[(1, 10), (2, 10), (3, 10)], 100)));
+        assert!(!book.insert(processed(&[(2, 10), (3, 10), (1, 10)], 50)));
+        assert_eq!(book.len(), 1);
+    }
+
+    #[test]
+    fn reverse_direction_is_separate_entry() {
+        let forward = processed(&[(1, 10), (2, 10), (3, 10)], 100);
+        let reverse = processed(&[(1, 10), (3, 10), (2, 10)], 100);
+        assert_ne!(cycle_key(&forward), cycle_key(&reverse));
+
+        let book = OpportunityBook::default();
+        assert!(book.insert(forward));
+        assert!(book.insert(reverse));
+        assert_eq!(book.len(), 2);
+    }
+
+    #[test]
+    fn insert_keeps_fresher_then_more_profitable() {
+        let book = OpportunityBook::default();
+        assert!(book.insert(processed(&[(1, 10), (2, 10), (3, 10)], 100)));
+
+        // Тот же слот: заменяет только более прибыльная
+        assert!(!book.insert(processed(&[(1, 10), (2, 10), (3, 10)], 100)));
+        assert!(book.insert(processed(&[(1, 10), (2, 10), (3, 10)], 200)));
+
+        // Более старые данные не заменяют запись даже с большей прибылью
+        assert!(!book.insert(processed(&[(1, 9), (2, 9), (3, 9)], 500)));
+        // Более свежие заменяют даже с меньшей прибылью
+        assert!(book.insert(processed(&[(1, 11), (2, 10), (3, 10)], 50)));
+
+        assert_eq!(book.len(), 1);
+        assert_eq!(book.take_best_non_conflicting(1)[0].net_profit, 50);
+    }
+
+    #[test]
+    fn insert_rejects_opportunity_on_superseded_pool_slot() {
+        let book = OpportunityBook::default();
+        book.on_pool_update(PoolCommitment::Processed, pool(1), 20);
+
+        assert!(!book.insert(processed(&[(1, 15), (2, 15), (3, 15)], 100)));
+        assert!(book.insert(processed(&[(1, 20), (2, 15), (3, 15)], 100)));
+        // Слот пула в другом commitment на processed возможность не влияет
+        assert!(book.insert(opportunity(PoolCommitment::Finalized, &[(1, 5), (4, 5), (5, 5)], 100)));
+    }
+
+    #[test]
+    fn pool_update_expires_only_its_commitment() {
+        let book = OpportunityBook::default();
+        assert!(book.insert(processed(&[(1, 10), (2, 10), (3, 10)], 100)));
+        assert!(book.insert(opportunity(PoolCommitment::Finalized, &[(1, 10), (7, 10), (8, 10)], 100)));
+
+        book.on_pool_update(PoolCommitment::Finalized, pool(1), 11);
+        assert_eq!(book.len(), 1);
+
+        // Тот же слот пула возможность не снимает
+        book.on_pool_update(PoolCommitment::Processed, pool(1), 10);
+        assert_eq!(book.len(), 1);
+
+        book.on_pool_update(PoolCommitment::Processed, pool(1), 11);
+        assert!(book.is_empty());
+    }
+
+    #[test]
+    fn take_best_selects_pool_disjoint_by_profit() {
+        let book = OpportunityBook::default();
+        book.insert(processed(&[(1, 10), (2, 10), (3, 10)], 300));
+        book.insert(processed(&[(3, 10), (4, 10), (5, 10)], 200));
+        book.insert(processed(&[(6, 10), (7, 10), (8, 10)], 100));
+        book.insert(processed(&[(9, 10), (10, 10), (11, 10)], 50));
+
+        let profits = |batch: Vec<ArbitrageOpportunity>| batch.iter().map(|o| o.net_profit).collect::<Vec<_>>();
+
+        // Вторая по прибыли пересекается с первой по пулу 3
+        assert_eq!(profits(book.take_best_non_conflicting(2)), vec![300, 100]);
+        assert_eq!(book.len(), 2);
+        assert_eq!(profits(book.take_best_non_conflicting(10)), vec![200, 50]);
+        assert!(book.is_empty());
+    }
+}
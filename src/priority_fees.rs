@@ -0,0 +1,252 @@
+// src/priority_fees.rs
+
+// Оценщик приоритетных комиссий.
+// Для пулов возможностей, недавно принятых книгой (opportunity_book.rs), опрашивает
+// getRecentPrioritizationFees (пул блокируется на запись каждым свапом) и держит
+// скользящее окно комиссий за последние PRIORITY_FEE_WINDOW_SLOTS слотов.
+// Цена CU для возможности - максимум PRIORITY_FEE_PERCENTILE перцентилей по пулам
+// маршрута, но приоритетная комиссия не превышает MAX_PRIORITY_FEE_PROFIT_BPS от прибыли.
+// Перцентиль по всей сети обновляет цену CU по умолчанию (FEE_LEVELS).
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use futures::stream::{self, StreamExt};
+use lazy_static::lazy_static;
+use log::{info, debug};
+use solana_program::pubkey::Pubkey;
+use crate::config::{
+    CONFIG, PRIORITY_FEE_POLL_MS, PRIORITY_FEE_WINDOW_SLOTS, PRIORITY_FEE_PERCENTILE,
+    PRIORITY_FEE_POOL_TTL_SECS, PRIORITY_FEE_CONCURRENCY, MAX_PRIORITY_FEE_PROFIT_BPS,
+};
+use crate::costs::FEE_LEVELS;
+use crate::rpc::get_recent_prioritization_fees;
+
+/// Скользящее окно комиссий (слот, микролампорты за CU) по возрастанию слота
+#[derive(Debug, Default, Clone)]
+pub struct FeeWindow {
+    samples: VecDeque<(u64, u64)>,
+}
+
+impl FeeWindow {
+    /// Добавляет слоты новее последнего и отрезает окно по глубине
+    pub fn merge(&mut self, mut samples: Vec<(u64, u64)>) {
+        samples.sort_unstable_by_key(|(slot, _)| *slot);
+        let mut last_slot = self.samples.back().map(|(slot, _)| *slot);
+
+        // Повторы слота внутри ответа тоже отбрасываются
+        for sample in samples {
+            if last_slot.is_none_or(|last| sample.0 > last) {
+                last_slot = Some(sample.0);
+                self.samples.push_back(sample);
+            }
+        }
+
+        if let Some(&(newest, _)) = self.samples.back() {
+            let oldest = newest.saturating_sub(PRIORITY_FEE_WINDOW_SLOTS);
+            while self.samples.front().is_some_and(|(slot, _)| *slot <= oldest) {
+                self.samples.pop_front();
+            }
+        }
+    }
+
+    /// Перцентиль комиссий окна; None, если окно пусто
+    pub fn percentile(&self, p: usize) -> Option<u64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut fees: Vec<u64> = self.samples.iter().map(|(_, fee)| *fee).collect();
+        fees.sort_unstable();
+        Some(fees[(fees.len() - 1) * p.min(100) / 100])
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PriorityFeeEstimator {
+    pools: DashMap<Pubkey, FeeWindow>,
+    // PRIORITY_FEE_PERCENTILE перцентиль окна пула, пересчитывается при опросе
+    pool_prices: DashMap<Pubkey, u64>,
+    // Пулы, по которым недавно считались возможности, и время последнего обращения
+    active: DashMap<Pubkey, Instant>,
+    network: Mutex<FeeWindow>,
+}
+
+lazy_static! {
+    pub static ref PRIORITY_FEES: PriorityFeeEstimator = PriorityFeeEstimator::default();
+}
+
+impl PriorityFeeEstimator {
+    /// Отмечает пулы маршрута как активные для опроса
+    pub fn touch_pools(&self, pools: &[Pubkey]) {
+        let now = Instant::now();
+        for pool in pools {
+            self.active.insert(*pool, now);
+        }
+    }
+
+    /// Перцентиль комиссий по всей сети
+    pub fn network_percentile(&self, p: usize) -> Option<u64> {
+        self.network.lock().unwrap().percentile(p)
+    }
+
+    /// Рекомендуемая цена CU (микролампорты) для маршрута по пулам `pools`.
+    /// `profit` - прибыль до приоритетной комиссии; комиссия `compute_units * цена`
+    /// не превышает MAX_PRIORITY_FEE_PROFIT_BPS от нее.
+    /// Пулы в опрос не добавляет: это делает книга для принятых возможностей,
+    /// иначе полный обход поставил бы на опрос все пулы графа.
+    pub fn recommend_cu_price(&self, pools: &[Pubkey], compute_units: u32, profit: i64) -> u64 {
+        let base = pools
+            .iter()
+            .filter_map(|pool| self.pool_prices.get(pool).map(|p| *p))
+            .max()
+            .unwrap_or_else(|| FEE_LEVELS.cu_price());
+
+        base.min(max_cu_price_for_profit(compute_units, profit))
+    }
+
+    // Пулы для опроса; устаревшие удаляются вместе с окнами
+    fn active_pools(&self) -> Vec<Pubkey> {
+        let ttl = Duration::from_secs(PRIORITY_FEE_POOL_TTL_SECS);
+        self.active.retain(|_, seen| seen.elapsed() < ttl);
+        self.pools.retain(|pool, _| self.active.contains_key(pool));
+        self.pool_prices.retain(|pool, _| self.active.contains_key(pool));
+        self.active.iter().map(|e| *e.key()).collect()
+    }
+
+    fn merge_pool(&self, pool: Pubkey, samples: Vec<(u64, u64)>) {
+        let mut window = self.pools.entry(pool).or_default();
+        window.merge(samples);
+        if let Some(price) = window.percentile(PRIORITY_FEE_PERCENTILE) {
+            self.pool_prices.insert(pool, price);
+        }
+    }
+
+    fn merge_network(&self, samples: Vec<(u64, u64)>) {
+        self.network.lock().unwrap().merge(samples);
+    }
+}
+
+/// Предельная цена CU, при которой комиссия не превышает допустимую долю прибыли
+pub fn max_cu_price_for_profit(compute_units: u32, profit: i64) -> u64 {
+    if profit <= 0 || compute_units == 0 {
+        return 0;
+    }
+    let max_fee_lamports = profit as u128 * MAX_PRIORITY_FEE_PROFIT_BPS as u128 / 10_000;
+    (max_fee_lamports * 1_000_000 / compute_units as u128).min(u64::MAX as u128) as u64
+}
+
+// Один проход опроса: сеть целиком и каждый активный пул
+async fn poll_fees(rpc_url: &str) {
+    match get_recent_prioritization_fees(rpc_url, &[]).await {
+        Ok(samples) => {
+            PRIORITY_FEES.merge_network(samples);
+            if let Some(price) = PRIORITY_FEES.network_percentile(PRIORITY_FEE_PERCENTILE) {
+                FEE_LEVELS.set_cu_price(price);
+            }
+        }
+        Err(e) => debug!("fees: ошибка запроса комиссий сети: {}", e),
+    }
+
+    let pools = PRIORITY_FEES.active_pools();
+    stream::iter(pools)
+        .for_each_concurrent(PRIORITY_FEE_CONCURRENCY, |pool| async move {
+            match get_recent_prioritization_fees(rpc_url, &[pool]).await {
+                Ok(samples) => PRIORITY_FEES.merge_pool(pool, samples),
+                Err(e) => debug!("fees: ошибка запроса комиссий пула {}: {}", pool, e),
+            }
+        })
+        .await;
+}
+
+/// Запуск оценщика приоритетных комиссий
+pub async fn run_priority_fee_estimator() {
+    info!("fees: запуск оценщика приоритетных комиссий");
+    let rpc_url = CONFIG.solana_rpc_url.clone();
+
+    let mut interval = tokio::time::interval(Duration::from_millis(PRIORITY_FEE_POLL_MS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        poll_fees(&rpc_url).await;
+        debug!("fees: цена CU по сети {}, пулов в опросе {}", FEE_LEVELS.cu_price(), PRIORITY_FEES.active.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots(window: &FeeWindow) -> Vec<u64> {
+        window.samples.iter().map(|(slot, _)| *slot).collect()
+    }
+
+    #[test]
+    fn merge_appends_only_newer_slots() {
+        let mut window = FeeWindow::default();
+        window.merge(vec![(12, 300), (10, 100), (11, 200)]);
+        assert_eq!(slots(&window), vec![10, 11, 12]);
+
+        // Повтор последнего слота, более старые и повторы внутри ответа
+        window.merge(vec![(12, 999), (9, 999), (13, 400), (13, 500)]);
+        assert_eq!(slots(&window), vec![10, 11, 12, 13]);
+        assert_eq!(window.samples.back(), Some(&(13, 400)));
+    }
+
+    #[test]
+    fn merge_trims_window_to_depth() {
+        let mut window = FeeWindow::default();
+        window.merge(vec![(100, 1), (101, 2)]);
+        window.merge(vec![(100 + PRIORITY_FEE_WINDOW_SLOTS, 3)]);
+        assert_eq!(slots(&window), vec![101, 100 + PRIORITY_FEE_WINDOW_SLOTS]);
+
+        // Разрыв больше окна оставляет только новые слоты
+        window.merge(vec![(1_000, 4)]);
+        assert_eq!(slots(&window), vec![1_000]);
+    }
+
+    #[test]
+    fn percentile_edges() {
+        let mut window = FeeWindow::default();
+        assert_eq!(window.percentile(50), None);
+
+        window.merge(vec![(1, 7)]);
+        assert_eq!(window.percentile(0), Some(7));
+        assert_eq!(window.percentile(100), Some(7));
+
+        window.merge(vec![(2, 50), (3, 10), (4, 40), (5, 20)]);
+        assert_eq!(window.percentile(0), Some(7));
+        assert_eq!(window.percentile(50), Some(20));
+        assert_eq!(window.percentile(75), Some(40));
+        assert_eq!(window.percentile(100), Some(50));
+        // Перцентиль больше 100 ограничивается максимумом
+        assert_eq!(window.percentile(250), Some(50));
+    }
+
+    #[test]
+    fn cu_price_keeps_fee_within_profit_share() {
+        assert_eq!(max_cu_price_for_profit(200_000, 0), 0);
+        assert_eq!(max_cu_price_for_profit(200_000, -1_000), 0);
+        assert_eq!(max_cu_price_for_profit(0, 1_000_000), 0);
+
+        for (compute_units, profit) in [(200_000, 1_000_000), (350_000, 12_345), (1, 1), (1_400_000, i64::MAX)] {
+            let price = max_cu_price_for_profit(compute_units, profit);
+            let fee = price as u128 * compute_units as u128 / 1_000_000;
+            let cap = profit as u128 * MAX_PRIORITY_FEE_PROFIT_BPS as u128 / 10_000;
+            assert!(fee <= cap, "fee {} > cap {} ({} CU, profit {})", fee, cap, compute_units, profit);
+        }
+    }
+
+    #[test]
+    fn recommendation_is_capped_by_profit() {
+        let estimator = PriorityFeeEstimator::default();
+        let (pool_a, pool_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        estimator.merge_pool(pool_a, vec![(1, 1_000)]);
+        estimator.merge_pool(pool_b, vec![(1, 5_000)]);
+
+        // Максимум по пулам маршрута
+        assert_eq!(estimator.recommend_cu_price(&[pool_a, pool_b], 200_000, 1_000_000_000), 5_000);
+        // Прибыль 1_000 лампортов: комиссия не больше 300, цена не больше 1_500 микролампорт за CU
+        assert_eq!(estimator.recommend_cu_price(&[pool_a, pool_b], 200_000, 1_000), 1_500);
+    }
+}
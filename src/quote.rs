@@ -1,43 +1,274 @@
 // src/quote.rs
 
-use jup_ag::{
-    quote, QuoteConfig, SwapMode, Result as JupiterResult
-};
+// Сканер круговых котировок локального jupiter-swap-api (--allow-circular-arbitrage).
+// Для каждого стартового токена (START_END_TOKEN_FOR_CHAINS) и каждого объема из
+// QUOTE_SCAN_AMOUNTS запрашивает котировку token -> token с ограничением числа
+// одновременных запросов и учетом задержки каждого запроса.
+//
+// Каждая котировка сравнивается:
+// - с нашими котировщиками на тех же пулах (точность quote_pool);
+// - с лучшим циклом нашего роутера по той же последовательности токенов.
+// Прибыльные линейные маршруты Jupiter публикуются как возможности (OpportunitySource::Jupiter).
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use dashmap::DashMap;
+use futures::stream::{self, StreamExt};
+use lazy_static::lazy_static;
 use log::{info, debug};
-use tokio::time::Instant;
-use std::time::Duration;
-use anchor_lang::prelude::Pubkey;
-use std::str::FromStr;
-
-const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
-const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
-const AMOUNT_IN: u64 = 1_000_000_000;
-
-pub async fn test_valid_pools() -> JupiterResult<()> {
-
-    // Проверяем что URL установлен правильно
-    let api_url = std::env::var("QUOTE_API_URL")
-        .expect("QUOTE_API_URL must be set");
-    debug!("Using Jupiter API URL: {}", api_url);
-
-    info!("Waiting for 2 seconds before getting quote");
-    tokio::time::sleep(Duration::from_secs(2)).await;
-
-    let start_time_quote = Instant::now();
-
-    let orca_quote = quote(
-        Pubkey::from_str(SOL_MINT)?,
-        Pubkey::from_str(USDC_MINT)?,
-        AMOUNT_IN,
-        QuoteConfig {
-            slippage_bps: Some(50),
-            swap_mode: Some(SwapMode::ExactIn),
-            only_direct_routes: true,
-            ..Default::default()
-        },
-    ).await?;
-
-    debug!("Получена котировка, детали: {:#?}", orca_quote);
-    debug!("Latency получения котировки: {:?}", start_time_quote.elapsed());
+use solana_program::pubkey::Pubkey;
+use crate::config::{
+    START_END_TOKEN_FOR_CHAINS, QUOTE_SCAN_INTERVAL_MS, QUOTE_SCAN_AMOUNTS, QUOTE_SCAN_CONCURRENCY,
+    QUOTE_LATENCY_WINDOW, QUOTE_REPORT_INTERVAL_SECS,
+};
+use crate::data::GLOBAL_DATA;
+use crate::router::{RouterEngine, ArbitrageOpportunity, quote_pool};
+use crate::wallet::trade_amount;
+use crate::websocket::ws_parser::PoolCommitment;
+use crate::execution::jupiter::{circular_quote_for, opportunity_from_quote};
+
+/// ID цепочки для маршрутов Jupiter, которых нет среди наших цепочек
+pub const JUPITER_CHAIN_ID: usize = usize::MAX;
+
+/// Перцентили задержки запросов котировок
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LatencyStats {
+    pub requests: u64,
+    pub errors: u64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// Скользящее окно задержек последних QUOTE_LATENCY_WINDOW запросов
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    window: Mutex<VecDeque<Duration>>,
+    requests: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl LatencyTracker {
+    pub fn record(&self, latency: Duration, success: bool) {
+        let mut window = self.window.lock().unwrap();
+        if window.len() == QUOTE_LATENCY_WINDOW {
+            window.pop_front();
+        }
+        window.push_back(latency);
+
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        let mut sorted: Vec<Duration> = self.window.lock().unwrap().iter().copied().collect();
+        sorted.sort();
+
+        let percentile = |p: usize| {
+            if sorted.is_empty() {
+                Duration::ZERO
+            } else {
+                sorted[(sorted.len() - 1) * p / 100]
+            }
+        };
+
+        LatencyStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Сравнение котировок Jupiter с нашими для одного стартового токена и объема
+#[derive(Debug, Default, Clone)]
+pub struct QuoteBenchmark {
+    pub samples: u64,
+    /// Сумма |наш выход - выход Jupiter| на тех же пулах, bps от выхода Jupiter
+    pub quoter_abs_deviation_bps: i64,
+    pub quoter_samples: u64,
+    /// Сумма (лучший цикл роутера - выход Jupiter), bps от входа
+    pub router_gap_bps: i64,
+    pub router_samples: u64,
+    /// Сколько раз Jupiter нашел выход лучше нашего роутера
+    pub jupiter_better: u64,
+    pub last_latency: Duration,
+}
+
+impl QuoteBenchmark {
+    /// Средняя абсолютная ошибка наших котировщиков, bps
+    pub fn mean_quoter_deviation_bps(&self) -> Option<i64> {
+        (self.quoter_samples > 0).then(|| self.quoter_abs_deviation_bps / self.quoter_samples as i64)
+    }
+
+    /// Средний разрыв лучшего цикла роутера с Jupiter, bps (отрицательный - Jupiter лучше)
+    pub fn mean_router_gap_bps(&self) -> Option<i64> {
+        (self.router_samples > 0).then(|| self.router_gap_bps / self.router_samples as i64)
+    }
+}
+
+lazy_static! {
+    pub static ref QUOTE_LATENCY: LatencyTracker = LatencyTracker::default();
+    // (стартовый токен, объем входа) -> сравнение
+    pub static ref QUOTE_BENCHMARKS: DashMap<(String, u64), QuoteBenchmark> = DashMap::new();
+}
+
+// Последовательность токенов -> ID наших цепочек. Цепочки не меняются после построения графа.
+static CHAIN_INDEX: OnceLock<HashMap<Vec<String>, Vec<usize>>> = OnceLock::new();
+
+fn chain_index() -> &'static HashMap<Vec<String>, Vec<usize>> {
+    CHAIN_INDEX.get_or_init(|| {
+        let offset_4 = GLOBAL_DATA.chain_storage_4.len();
+        let mut index: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+        for chain in GLOBAL_DATA.chain_storage_4.iter() {
+            index.entry(chain.value().clone()).or_default().push(*chain.key());
+        }
+        for chain in GLOBAL_DATA.chain_storage_5.iter() {
+            index.entry(chain.value().clone()).or_default().push(offset_4 + *chain.key());
+        }
+        index
+    })
+}
+
+/// Маршрут линейный: без разделения объема, выход хопа - вход следующего.
+/// Только такие маршруты можно собрать нативно (tx_builder.rs).
+pub fn is_linear_route(opportunity: &ArbitrageOpportunity) -> bool {
+    opportunity.hops.windows(2).all(|w| w[0].output_mint == w[1].input_mint && w[0].amount_out == w[1].amount_in)
+        && opportunity.hops.first().map(|h| h.amount_in) == Some(opportunity.amount_in)
+}
+
+/// Выход маршрута по нашим котировщикам на тех же пулах (processed состояние)
+pub fn quote_route_with_our_quoters(opportunity: &ArbitrageOpportunity) -> Option<u64> {
+    let mut amount = opportunity.amount_in;
+    for hop in &opportunity.hops {
+        let (state, _) = RouterEngine::current_pool_state(PoolCommitment::Processed, hop.dex, &hop.pool_address)?;
+        amount = quote_pool(&state, &hop.input_mint, amount)?;
+    }
+    Some(amount)
+}
+
+/// Лучший цикл нашего роутера по последовательности токенов на объем `amount_in`
+pub fn router_best_cycle(tokens: &[String], amount_in: u64) -> Option<ArbitrageOpportunity> {
+    chain_index()
+        .get(tokens)?
+        .iter()
+        .filter_map(|chain_id| {
+            let chain_result = GLOBAL_DATA.chain_results.get(&(*chain_id, PoolCommitment::Processed))?;
+            RouterEngine::simulate_chain(*chain_id, &chain_result, amount_in)
+        })
+        .max_by_key(|o| o.amount_out)
+}
+
+// Одна круговая котировка: задержка, сравнение и публикация
+async fn scan_one(token: String, mint: Pubkey, amount_in: u64) -> Result<()> {
+    let started = Instant::now();
+    let quote = circular_quote_for(&mint, amount_in).await;
+    let latency = started.elapsed();
+    QUOTE_LATENCY.record(latency, quote.is_ok());
+
+    let quote = quote?;
+    let mut opportunity = opportunity_from_quote(&quote, JUPITER_CHAIN_ID)?;
+    if let Some(chain_id) = chain_index().get(&opportunity.chain).and_then(|ids| ids.first()) {
+        opportunity.chain_id = *chain_id;
+    }
+
+    let quoter_out = quote_route_with_our_quoters(&opportunity);
+    let router_best = router_best_cycle(&opportunity.chain, amount_in);
+
+    {
+        let mut benchmark = QUOTE_BENCHMARKS.entry((token.clone(), amount_in)).or_default();
+        benchmark.samples += 1;
+        benchmark.last_latency = latency;
+
+        if let Some(ours) = quoter_out {
+            let deviation = (ours as i64 - opportunity.amount_out as i64).saturating_mul(10_000)
+                / opportunity.amount_out.max(1) as i64;
+            benchmark.quoter_abs_deviation_bps += deviation.abs();
+            benchmark.quoter_samples += 1;
+        }
+        if let Some(best) = &router_best {
+            let gap = (best.amount_out as i64 - opportunity.amount_out as i64).saturating_mul(10_000)
+                / amount_in.max(1) as i64;
+            benchmark.router_gap_bps += gap;
+            benchmark.router_samples += 1;
+            if opportunity.amount_out > best.amount_out {
+                benchmark.jupiter_better += 1;
+            }
+        }
+    }
+
+    debug!("quote: {} {} -> {} за {:?} ({:?}), наши котировщики {:?}, лучший цикл роутера {:?}",
+        token, amount_in, opportunity.amount_out, latency, opportunity.chain,
+        quoter_out, router_best.as_ref().map(|o| o.amount_out));
+
+    // Дополнительная возможность: только линейные маршруты в пределах остатка кошелька
+    if is_linear_route(&opportunity)
+        && trade_amount(&token, amount_in) == amount_in
+        && RouterEngine::passes_profit_threshold(&opportunity) {
+        info!("quote: возможность от Jupiter [{}] {:?}: net_profit={} на вход {}",
+            opportunity.chain_id, opportunity.chain, opportunity.net_profit, amount_in);
+        RouterEngine::publish_opportunity(opportunity);
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+fn report() {
+    let latency = QUOTE_LATENCY.stats();
+    info!("quote: запросов {}, ошибок {}, задержка p50 {:?} p90 {:?} p99 {:?} max {:?}",
+        latency.requests, latency.errors, latency.p50, latency.p90, latency.p99, latency.max);
+
+    for entry in QUOTE_BENCHMARKS.iter() {
+        let ((token, amount_in), benchmark) = (entry.key(), entry.value());
+        info!("quote: {} {}: котировок {}, ошибка котировщиков {:?} bps, разрыв роутера {:?} bps, Jupiter лучше {} раз",
+            token, amount_in, benchmark.samples, benchmark.mean_quoter_deviation_bps(),
+            benchmark.mean_router_gap_bps(), benchmark.jupiter_better);
+    }
+}
+
+/// Запуск сканера круговых котировок
+pub async fn run_quote_scanner() {
+    info!("quote: запуск сканера круговых котировок Jupiter");
+
+    let mut interval = tokio::time::interval(Duration::from_millis(QUOTE_SCAN_INTERVAL_MS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_report = Instant::now();
+
+    loop {
+        interval.tick().await;
+
+        let requests: Vec<(String, Pubkey, u64)> = START_END_TOKEN_FOR_CHAINS
+            .iter()
+            .filter_map(|token| GLOBAL_DATA.tokens.get(*token).map(|t| (token.to_string(), t.address)))
+            .flat_map(|(token, mint)| QUOTE_SCAN_AMOUNTS.iter().map(move |amount| (token.clone(), mint, *amount)))
+            .collect();
+
+        let results: Vec<(String, u64, Result<()>)> = stream::iter(requests)
+            .map(|(token, mint, amount)| async move {
+                let result = scan_one(token.clone(), mint, amount).await;
+                (token, amount, result)
+            })
+            .buffer_unordered(QUOTE_SCAN_CONCURRENCY)
+            .collect()
+            .await;
+
+        for (token, amount, result) in results {
+            if let Err(e) = result {
+                debug!("quote: ошибка круговой котировки {} {}: {}", token, amount, e);
+            }
+        }
+
+        if last_report.elapsed() >= Duration::from_secs(QUOTE_REPORT_INTERVAL_SECS) {
+            report();
+            last_report = Instant::now();
+        }
+    }
+}
@@ -5,12 +5,22 @@
 use log::{debug, warn, info};
 use solana_program::pubkey::Pubkey;
 use petgraph::graph::EdgeIndex;
+use petgraph::Graph;
 use crate::graph::PoolEdge;
 use crate::data::{GLOBAL_DATA, TokenInfo, unix_timestamp};
 use crate::websocket::ws_data::DexType;
+use crate::websocket::ws_parser::PoolCommitment;
 use crate::math::weight_calculators::*;
-use crate::math::calculators::*;
-use crate::config::INITIAL_BALANCE;
+use crate::math::quoters::{quote_concentrated, concentrated_tick_move};
+use crate::config::{INITIAL_BALANCE, SIMULATION_AMOUNT, MIN_PROFIT_LAMPORTS, MIN_PROFIT_BPS,
+    SIMULATION_QUEUE_SIZE, OPPORTUNITY_CHANNEL_SIZE};
+use crate::data::PoolStateBase;
+use crate::costs::{ExecutionCosts, estimate_costs};
+use crate::wallet::trade_amount;
+use lazy_static::lazy_static;
+use flume::{Sender, Receiver};
+use tokio::sync::broadcast;
+use dashmap::DashMap;
 
 // -----------------------------------------
 // Опционально: Структуры для хранения
@@ -19,6 +29,7 @@ use crate::config::INITIAL_BALANCE;
 #[derive(Debug, Clone)]
 pub struct ExtendedPoolInfo {
     pub pool_address: Pubkey,
+    pub dex: DexType,
     pub price: f64,
     pub fee_rate: f64,
     pub liquidity: f64,
@@ -26,6 +37,8 @@ pub struct ExtendedPoolInfo {
     pub is_active: bool,
     pub last_update_slot: u64,
     pub last_update_time: u64,
+    /// Версия состояния пула, на которой снята информация
+    pub version: u64,
 }
 
 /// HopData расширяем, чтобы вместо (Pubkey, f64) хранился список ExtendedPoolInfo
@@ -41,10 +54,12 @@ pub struct HopData {
     pub best_pool: Option<ExtendedPoolInfo>,
 }
 
-// TODO: Добавить в GlobalData 
+/// Результат пересчета цепочки. Хранится в `GLOBAL_DATA.chain_results`
+/// по ключу (ID цепочки, commitment).
 #[derive(Debug, Clone)]
 pub struct ChainResult {
     pub last_update: u64,
+    pub commitment: PoolCommitment,
     pub chain_tokens: Vec<String>,  // Храним, какие вообще токены идут в цепочке
 
     // Список хопов, где внутри уже не просто (Pubkey, weight),
@@ -54,6 +69,90 @@ pub struct ChainResult {
     /// TODO: Здесь можно хранить "общий" результат по цепочке:
     pub total_weight: f64,
     pub simulated_amount: u64,
+
+    /// Версии всех пулов, использованных при расчете (pool_address, version)
+    pub pool_versions: Vec<(Pubkey, u64)>,
+}
+
+impl ChainResult {
+    /// Актуален ли результат: ни один из использованных пулов не получил новую версию
+    pub fn is_fresh(&self) -> bool {
+        self.pool_versions
+            .iter()
+            .all(|(pool, version)| GLOBAL_DATA.pool_version(self.commitment, pool) == *version)
+    }
+}
+
+/// Результат симуляции одного хопа цепочки
+#[derive(Debug, Clone)]
+pub struct SimulatedHop {
+    pub dex: DexType,
+    pub pool_address: Pubkey,
+    pub from_token: String,
+    pub to_token: String,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Слот, в котором было получено состояние пула
+    pub pool_slot: u64,
+    /// Оценка пересекаемых свопом интервалов tick_spacing (tick / bin arrays), 0 для Raydium
+    pub crossings: u32,
+}
+
+/// Кто нашел возможность
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpportunitySource {
+    /// Инкрементальный пересчет цепочек по обновлениям пулов
+    Router,
+    /// Периодический полный обход всех цепочек (arbitrage.rs)
+    FullScan,
+    /// Круговая котировка локального Jupiter API (execution/jupiter.rs)
+    Jupiter,
+}
+
+/// Арбитражная возможность, найденная симуляцией цепочки
+#[derive(Debug, Clone)]
+pub struct ArbitrageOpportunity {
+    pub chain_id: usize,
+    pub chain: Vec<String>,
+    pub commitment: PoolCommitment,
+    pub source: OpportunitySource,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Валовая прибыль: выход минус вход в единицах стартового токена
+    pub expected_profit: i64,
+    /// Оценка затрат на исполнение
+    pub costs: ExecutionCosts,
+    /// Чистая прибыль: валовая за вычетом затрат. По ней ранжируем и фильтруем
+    pub net_profit: i64,
+    pub hops: Vec<SimulatedHop>,
+}
+
+/// Задание на симуляцию: пересчитанный результат цепочки для одного commitment
+#[derive(Debug, Clone)]
+pub struct SimulationJob {
+    pub chain_id: usize,
+    pub result: ChainResult,
+}
+
+lazy_static! {
+    // Очередь цепочек на симуляцию. Симуляция читает состояния пулов, поэтому выполняется
+    // вне потока обновления (там удерживаются блокировки *_pool_states)
+    static ref SIMULATION_QUEUE: (Sender<SimulationJob>, Receiver<SimulationJob>) =
+        flume::bounded(SIMULATION_QUEUE_SIZE);
+
+    // Канал арбитражных возможностей для всех подписчиков
+    static ref OPPORTUNITY_TX: broadcast::Sender<ArbitrageOpportunity> =
+        broadcast::channel(OPPORTUNITY_CHANNEL_SIZE).0;
+
+    // Версии пулов, на которых роутер последний раз опубликовал возможность по цепочке
+    static ref PUBLISHED_VERSIONS: DashMap<(usize, PoolCommitment), Vec<(Pubkey, u64)>> = DashMap::new();
+}
+
+/// Подписка на арбитражные возможности, публикуемые роутером
+pub fn subscribe_opportunities() -> broadcast::Receiver<ArbitrageOpportunity> {
+    OPPORTUNITY_TX.subscribe()
 }
 
 /// Движок-модуль для работы с цепочками:
@@ -68,24 +167,32 @@ impl RouterEngine {
     /// - Вычисляет total_weight и т. п. (пока упрощённо).
     pub fn recalc_chain(
         chain: &[String],
-        is_finalized: bool
+        commitment: PoolCommitment
     ) -> Option<ChainResult> 
     {
         // 1. Определяем, с каким графом работаем
-        let graph_map = if is_finalized {
-            &GLOBAL_DATA.finalized_graph
-        } else {
-            &GLOBAL_DATA.processed_graph
-        };
-
-        let graph = match graph_map.get("main") {
+        let graph = match GLOBAL_DATA.graph_for(commitment).get("main") {
             Some(g) => g,
             None => {
-                warn!("recalc_chain: Graph 'main' not found (finalized={})", is_finalized);
+                warn!("recalc_chain: Graph 'main' not found ({:?})", commitment);
                 return None;
             }
         };
 
+        Self::recalc_chain_on(&graph, chain, commitment, |pool| GLOBAL_DATA.pool_version(commitment, pool))
+    }
+
+    /// Пересчет цепочки на конкретном графе (глобальном или снимке).
+    /// `pool_version` - источник версий пулов, соответствующий этому графу.
+    pub fn recalc_chain_on<V>(
+        graph: &Graph<String, PoolEdge>,
+        chain: &[String],
+        commitment: PoolCommitment,
+        pool_version: V
+    ) -> Option<ChainResult>
+    where
+        V: Fn(&Pubkey) -> u64,
+    {
         let mut result_hops: Vec<HopData> = Vec::with_capacity(chain.len() - 1);
         let mut total_weight_acc = 1.0;
 
@@ -108,16 +215,22 @@ impl RouterEngine {
                     if let Some(edge_idx) = graph.edge_indices().find(|&e| graph[e].pool_address == pubkey)
                     {
                         let edge = &graph[edge_idx];
+                        // Версию читаем до копирования метрик: если ребро обновится во время
+                        // расчета, результат будет считаться устаревшим и пересчитается
+                        let version = pool_version(&pubkey);
                         // Сохраняем подробные данные
                         let info = ExtendedPoolInfo {
                             pool_address: pubkey,
+                            dex,
                             price: edge.price,
                             fee_rate: edge.fee_rate,
                             liquidity: edge.liquidity,
                             weight: edge.weight,
-                            is_active: edge.is_active,
+                            // Устаревшее ребро не выбирается лучшим пулом хопа
+                            is_active: edge.is_active && !edge.stale,
                             last_update_slot: edge.last_update_slot,
                             last_update_time: edge.last_update_time,
+                            version,
                         };
                         hop_data.pools.push(info);
                     }
@@ -153,69 +266,253 @@ impl RouterEngine {
         // 3. Дополнительно можно вычислить simulate_amount
         let simulated_amount = (INITIAL_BALANCE as f64 * total_weight_acc) as u64;
 
-        // 4. Формируем результат
+        // 4. Версии всех пулов, которые просматривались (не только лучших):
+        //    изменение любого из них может поменять best_pool
+        let pool_versions = result_hops
+            .iter()
+            .flat_map(|hop| hop.pools.iter().map(|p| (p.pool_address, p.version)))
+            .collect();
+
+        // 5. Формируем результат
         let chain_res = ChainResult {
             last_update: unix_timestamp(),
+            commitment,
             chain_tokens: chain.to_vec(),
             hops: result_hops,
             total_weight: total_weight_acc,
             simulated_amount,
+            pool_versions,
         };
 
         Some(chain_res)
     }
 
-    /// Обновляет (пересчитывает) все цепочки, в которых участвует `pool_address`.
-    #[allow(unused_variables)]
-    pub fn update_affected_chains(pool_address: Pubkey) {
-        if let Some(chain_indices) = GLOBAL_DATA.chain_references.get(&pool_address) {
-            let offset_4 = GLOBAL_DATA.chain_storage_4.len();
-
-            for &idx in chain_indices.iter() {
-                if idx < offset_4 {
-                    // Цепочка длины 4
-                    if let Some(chain) = GLOBAL_DATA.chain_storage_4.get(&idx) {
-                        let tokens = chain.value();
-                        // Пересчитываем processed
-                        let r1 = Self::recalc_chain(tokens, false);
-                        debug!("router: обновили цепочку длины 4 [{}] для {}", idx, false);
-                        // Пересчитываем finalized
-                        let r2 = Self::recalc_chain(tokens, true);
-                        debug!("router: обновили цепочку длины 4 [{}] для {}", idx, true);
-
-                        // TODO: Вызвать арбитражную симуляцию, если нужно
-                        // Self::simulate_arbitrage_if_needed(&r1, &r2, ...);
-                    }
-                } else {
-                    // Цепочка длины 5
-                    let idx_in_5 = idx - offset_4;
-                    if let Some(chain) = GLOBAL_DATA.chain_storage_5.get(&idx_in_5) {
-                        let tokens = chain.value();
-                        let r1 = Self::recalc_chain(tokens, false);
-                        debug!("router: обновили цепочку длины 5 [{}] для {}", idx_in_5, false);
-                        let r2 = Self::recalc_chain(tokens, true);
-                        debug!("router: обновили цепочку длины 5 [{}] для {}", idx_in_5, true);
-
-                        // TODO: Вызвать арбитражную симуляцию, если нужно
-                        // Self::simulate_arbitrage_if_needed(&r1, &r2, ...);
-                    }
-                }
+    /// Возвращает результат цепочки из кеша, если версии всех ее пулов не менялись,
+    /// иначе пересчитывает и обновляет кеш.
+    /// Второй элемент - был ли выполнен пересчет.
+    pub fn get_or_recalc_chain(
+        chain_id: usize,
+        chain: &[String],
+        commitment: PoolCommitment
+    ) -> (Option<ChainResult>, bool)
+    {
+        if let Some(cached) = GLOBAL_DATA.chain_results.get(&(chain_id, commitment)) {
+            if cached.is_fresh() {
+                return (Some(cached.clone()), false);
+            }
+        }
+
+        match Self::recalc_chain(chain, commitment) {
+            Some(result) => {
+                GLOBAL_DATA.chain_results.insert((chain_id, commitment), result.clone());
+                (Some(result), true)
+            }
+            None => {
+                // Цепочка стала невалидной - старый результат больше не отражает состояние
+                GLOBAL_DATA.chain_results.remove(&(chain_id, commitment));
+                (None, true)
             }
         }
     }
 
-    /// Пример заглушки для вызова арбитражной логики:
-    /// (Пока пусто, “TODO”).
-    #[allow(dead_code)]
-    #[allow(unused_variables)]
-    fn simulate_arbitrage_if_needed(
-        processed_chain: &Option<ChainResult>,
-        finalized_chain: &Option<ChainResult>,
-    ) {
-        // TODO: Реализовать симуляцию арбитража, используя данные best_pool
-        //       и т. п.
-        //       Могут понадобиться вызовы DEX-калькуляторов, расчет проскальзываний...
-        //       Использование кеша для оптимизации и ускорения работы.
+    /// Обновляет (пересчитывает) на графе `commitment` все цепочки, в которых участвует `pool_address`.
+    /// Цепочки, версии пулов которых не менялись, берутся из кеша.
+    pub fn update_affected_chains(pool_address: Pubkey, commitment: PoolCommitment) {
+        if !GLOBAL_DATA.active_commitments().contains(&commitment) {
+            return;
+        }
+
+        // Копируем индексы, чтобы не держать блокировку chain_references во время пересчета
+        let chain_indices = match GLOBAL_DATA.chain_references.get(&pool_address) {
+            Some(indices) => indices.clone(),
+            None => return,
+        };
+
+        for chain_id in chain_indices {
+            let tokens = match GLOBAL_DATA.get_chain(chain_id) {
+                Some(tokens) => tokens,
+                None => continue,
+            };
+
+            let (result, recalculated) = Self::get_or_recalc_chain(chain_id, &tokens, commitment);
+            debug!("router: цепочка [{}] длины {} {:?} (пересчитана={})", chain_id, tokens.len(), commitment, recalculated);
+
+            // Отправляем на симуляцию только реально пересчитанные результаты
+            let result = match result {
+                Some(result) if recalculated => result,
+                _ => continue,
+            };
+            let job = SimulationJob { chain_id, result };
+            if SIMULATION_QUEUE.0.try_send(job).is_err() {
+                debug!("router: очередь симуляции переполнена, цепочка [{}] пропущена", chain_id);
+            }
+        }
+    }
+
+    /// Обработчик очереди симуляции. Запускается отдельной задачей.
+    pub async fn run_simulation_worker() {
+        info!("router: запуск обработчика симуляции цепочек");
+        let rx = SIMULATION_QUEUE.1.clone();
+        while let Ok(job) = rx.recv_async().await {
+            Self::simulate_arbitrage_if_needed(job.chain_id, &job.result);
+        }
+    }
+
+    /// Симулирует пересчитанный результат цепочки и публикует прибыльную возможность.
+    fn simulate_arbitrage_if_needed(chain_id: usize, chain_result: &ChainResult) {
+        // Вход ограничен остатком стартового токена на кошельке
+        let start_token = chain_result.chain_tokens.first().map(String::as_str).unwrap_or_default();
+        let amount_in = trade_amount(start_token, SIMULATION_AMOUNT);
+        if amount_in == 0 {
+            return;
+        }
+
+        let opportunity = match Self::simulate_chain(chain_id, chain_result, amount_in) {
+            Some(o) => o,
+            None => return,
+        };
+
+        if !Self::passes_profit_threshold(&opportunity) {
+            return;
+        }
+
+        info!("router: найдена возможность [{}] {:?} ({:?}): net_profit={} (gross={}, costs={}) на вход {}",
+            chain_id, opportunity.chain, opportunity.commitment,
+            opportunity.net_profit, opportunity.expected_profit, opportunity.costs.total(),
+            opportunity.amount_in);
+
+        Self::publish_chain_opportunity(opportunity, &chain_result.pool_versions);
+    }
+
+    /// Публикация возможности по цепочке графа с запоминанием версий пулов,
+    /// чтобы полный обход не публиковал ее повторно на тех же версиях
+    pub fn publish_chain_opportunity(opportunity: ArbitrageOpportunity, pool_versions: &[(Pubkey, u64)]) {
+        PUBLISHED_VERSIONS.insert((opportunity.chain_id, opportunity.commitment), pool_versions.to_vec());
+        Self::publish_opportunity(opportunity);
+    }
+
+    /// Публикация возможности всем подписчикам
+    pub fn publish_opportunity(opportunity: ArbitrageOpportunity) {
+        // Ошибка означает только отсутствие подписчиков
+        let _ = OPPORTUNITY_TX.send(opportunity);
+    }
+
+    /// Публиковал ли роутер возможность по цепочке на этих же версиях пулов
+    pub fn was_published(chain_id: usize, commitment: PoolCommitment, pool_versions: &[(Pubkey, u64)]) -> bool {
+        PUBLISHED_VERSIONS
+            .get(&(chain_id, commitment))
+            .map(|versions| versions.as_slice() == pool_versions)
+            .unwrap_or(false)
+    }
+
+    /// Проверка порогов чистой прибыли: абсолютного и относительного
+    pub fn passes_profit_threshold(opportunity: &ArbitrageOpportunity) -> bool {
+        Self::profit_meets_threshold(opportunity.net_profit, opportunity.amount_in)
+    }
+
+    /// Те же пороги для произвольной чистой прибыли (например, по результату симуляции)
+    pub fn profit_meets_threshold(net_profit: i64, amount_in: u64) -> bool {
+        if net_profit < MIN_PROFIT_LAMPORTS {
+            return false;
+        }
+        let profit_bps = net_profit.saturating_mul(10_000) / amount_in.max(1) as i64;
+        profit_bps >= MIN_PROFIT_BPS
+    }
+
+    /// Прогоняет `amount_in` через лучшие пулы каждого хопа с помощью DEX-котировщиков.
+    pub fn simulate_chain(
+        chain_id: usize,
+        chain_result: &ChainResult,
+        amount_in: u64
+    ) -> Option<ArbitrageOpportunity> {
+        let commitment = chain_result.commitment;
+        Self::simulate_hops(chain_id, chain_result, amount_in, OpportunitySource::Router, |dex, pool| {
+            Self::current_pool_state(commitment, dex, pool)
+        })
+    }
+
+    /// Симуляция по хопам с произвольным источником состояний пулов
+    /// (текущее глобальное состояние или снимок).
+    pub fn simulate_hops<F>(
+        chain_id: usize,
+        chain_result: &ChainResult,
+        amount_in: u64,
+        source: OpportunitySource,
+        pool_state: F
+    ) -> Option<ArbitrageOpportunity>
+    where
+        F: Fn(DexType, &Pubkey) -> Option<(PoolStateBase, u64)>,
+    {
+        let mut current_amount = amount_in;
+        let mut hops = Vec::with_capacity(chain_result.hops.len());
+
+        for hop in &chain_result.hops {
+            let best = hop.best_pool.as_ref()?;
+            let input_mint = GLOBAL_DATA.tokens.get(&hop.from_token)?.address;
+            let output_mint = GLOBAL_DATA.tokens.get(&hop.to_token)?.address;
+            let (state, pool_slot) = pool_state(best.dex, &best.pool_address)?;
+
+            let amount_out = quote_pool(&state, &input_mint, current_amount)?;
+            if amount_out == 0 {
+                debug!("router: нулевой выход на хопе {}->{} пула {}", hop.from_token, hop.to_token, best.pool_address);
+                return None;
+            }
+
+            hops.push(SimulatedHop {
+                dex: best.dex,
+                pool_address: best.pool_address,
+                from_token: hop.from_token.clone(),
+                to_token: hop.to_token.clone(),
+                input_mint,
+                output_mint,
+                amount_in: current_amount,
+                amount_out,
+                pool_slot,
+                crossings: estimate_crossings(&state, &input_mint, current_amount),
+            });
+            current_amount = amount_out;
+        }
+
+        let expected_profit = current_amount as i64 - amount_in as i64;
+        let costs = estimate_costs(&hops, expected_profit);
+
+        Some(ArbitrageOpportunity {
+            chain_id,
+            chain: chain_result.chain_tokens.clone(),
+            commitment: chain_result.commitment,
+            source,
+            amount_in,
+            amount_out: current_amount,
+            expected_profit,
+            costs,
+            net_profit: expected_profit.saturating_sub(costs.total() as i64),
+            hops,
+        })
+    }
+
+    /// Текущее состояние пула и слот его обновления для commitment
+    pub fn current_pool_state(
+        commitment: PoolCommitment,
+        dex: DexType,
+        pool_address: &Pubkey
+    ) -> Option<(PoolStateBase, u64)> {
+        match commitment {
+            PoolCommitment::Processed => {
+                let states = GLOBAL_DATA.processed_pool_states.get(&dex)?;
+                let state = states.get(pool_address)?;
+                Some((state.base.clone(), state.processed_slot))
+            }
+            PoolCommitment::Confirmed => {
+                let states = GLOBAL_DATA.confirmed_pool_states.get(&dex)?;
+                let state = states.get(pool_address)?;
+                Some((state.base.clone(), state.confirmed_slot))
+            }
+            PoolCommitment::Finalized => {
+                let states = GLOBAL_DATA.finalized_pool_states.get(&dex)?;
+                let state = states.get(pool_address)?;
+                Some((state.base.clone(), state.finalized_slot))
+            }
+        }
     }
 
     // Поиск лучшего маршрута для заданной цепочки.
@@ -226,33 +523,195 @@ impl RouterEngine {
 }
 
 
+// Делитель fee_rate у Orca: комиссия хранится в сотых долях базисного пункта
+const ORCA_FEE_RATE_DENOMINATOR: f64 = 1_000_000.0;
+
+/// Котировка свопа `amount_in` токена `input_mint` в конкретном пуле.
+/// None, если пул не содержит входной токен или для его типа нет котировщика.
+pub fn quote_pool(state: &PoolStateBase, input_mint: &Pubkey, amount_in: u64) -> Option<u64> {
+    match state {
+        PoolStateBase::Orca(s) => {
+            let a_to_b = swap_direction(input_mint, &s.token_mint_a, &s.token_mint_b)?;
+            let fee_rate = s.fee_rate as f64 / ORCA_FEE_RATE_DENOMINATOR;
+            Some(quote_concentrated(amount_in, s.sqrt_price, s.liquidity, fee_rate, a_to_b))
+        }
+        // AMM V4 котируется по x·y=k от балансов vault-ов, а DLMM - по бинам вокруг
+        // active_id с шагом bin_step. Ни то, ни другое в состоянии пула пока не отслеживается,
+        // поэтому цепочки с такими хопами не симулируются и не публикуются.
+        PoolStateBase::Raydium(_) | PoolStateBase::Meteora(_) => None,
+    }
+}
+
+/// Оценка числа интервалов tick_spacing, которые пересечет своп `amount_in` в пуле.
+/// От нее зависит расход CU на переходы между тиками / бинами.
+pub fn estimate_crossings(state: &PoolStateBase, input_mint: &Pubkey, amount_in: u64) -> u32 {
+    let (ticks, spacing) = match state {
+        PoolStateBase::Orca(s) => {
+            let Some(a_to_b) = swap_direction(input_mint, &s.token_mint_a, &s.token_mint_b) else { return 0 };
+            let fee_rate = s.fee_rate as f64 / ORCA_FEE_RATE_DENOMINATOR;
+            (concentrated_tick_move(amount_in, s.sqrt_price, s.liquidity, fee_rate, a_to_b), s.tick_spacing)
+        }
+        PoolStateBase::Meteora(s) => {
+            let Some(a_to_b) = swap_direction(input_mint, &s.token_mint_a, &s.token_mint_b) else { return 0 };
+            let fee_rate = s.fee_rate as f64 / 10_000.0;
+            (concentrated_tick_move(amount_in, s.sqrt_price, s.liquidity, fee_rate, a_to_b), s.tick_spacing)
+        }
+        PoolStateBase::Raydium(_) => return 0,
+    };
+    (ticks / spacing.max(1) as f64).min(u32::MAX as f64) as u32
+}
+
+// Направление свопа: true - token_a -> token_b
+fn swap_direction(input_mint: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey) -> Option<bool> {
+    if input_mint == mint_a {
+        Some(true)
+    } else if input_mint == mint_b {
+        Some(false)
+    } else {
+        None
+    }
+}
+
 /*
 
 TODO: Реализовать полный Snapshot данных перед иницилизацией чтобы хранить все
-состояния цепочек и пулов, имея общую картину. Затем после snapshot реализовать кеширование данных по пункту 3.
+состояния цепочек и пулов, имея общую картину.
 
 2. Какой объём данных у ChainResult
 ChainResult хранит текущую картину по одной цепочке (списку токенов). То есть при вызове recalc_chain мы берём последние значения PoolEdge из графа и складываем в “hops” (HopData).
 ChainResult не содержит “полной копии” всех пулов системы — только те, что участвуют в конкретных переходах (hop’ах).
 Для будущего арбитража ChainResult может содержать всю нужную информацию (стоимость, комиссию, вес, ликвидность) для каждого хопа, чтобы дальше моделировать сделки.
 
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+    use crate::decoder::{WhirlpoolData, RaydiumData, MeteoraData};
+    use crate::websocket::ws_parser::PoolData;
+    use crate::data::PoolState;
+
+    const Q64: u128 = 1 << 64;
+
+    // Whirlpool с ценой 4 (√P = 2) и комиссией 0.3%
+    fn whirlpool(mint_a: Pubkey, mint_b: Pubkey, liquidity: u128) -> PoolStateBase {
+        let mut data = WhirlpoolData::zeroed();
+        data.token_mint_a = mint_a;
+        data.token_mint_b = mint_b;
+        data.tick_spacing = 64;
+        data.fee_rate = 3_000;
+        data.liquidity = liquidity;
+        data.sqrt_price = 2 * Q64;
+        PoolStateBase::from_pool_data(Pubkey::new_unique(), &PoolData::Whirlpool(data))
+    }
+
+    #[test]
+    fn quote_whirlpool_a_to_b() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = whirlpool(mint_a, mint_b, 1_000_000_000_000);
 
-TODO:
+        // Δy = L·(√P - √P'), 1/√P' = 1/√P + Δx/L, Δx = 1_000_000 · 0.997
+        let out = quote_pool(&pool, &mint_a, 1_000_000).unwrap();
+        assert!(out.abs_diff(3_987_992) <= 1, "out = {}", out);
+    }
 
-3. Идея о кешировании ChainResult
-Почему вообще может понадобиться кэш:
+    #[test]
+    fn quote_whirlpool_b_to_a() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = whirlpool(mint_a, mint_b, 1_000_000_000_000);
 
-Если цепочек очень много (или пересчёты дорогие), а пул не менялся, то нет смысла пересчитывать цепочку заново. Можно взять готовый ChainResult из кэша.
-Аналогично, если мы хотим быстро ответить “каков итоговый вес” цепочки через минуту после предыдущего пересчёта, а за это время ничего не изменилось — можно вернуть старый результат.
-Какая польза, если данные и так “актуальны” в PoolEdge?
+        // Δx = L·(1/√P - 1/√P'), √P' = √P + Δy/L
+        let out = quote_pool(&pool, &mint_b, 1_000_000).unwrap();
+        assert!(out.abs_diff(249_249) <= 1, "out = {}", out);
+    }
 
-В PoolEdge — только сырые метрики пула. А ChainResult — агрегированный расчёт, связанный именно с конкретной последовательностью хопов, включающей потенциально несколько пулов на шаге.
-Если у нас нет кэша, придётся каждый раз (при любом запросе) проходиться по всем шагам цепочки, смотреть все DEX, находить лучший пул и т. д.
-Если у нас есть кэш, мы можем просто проверить, “не изменилась ли версия” пулов в цепочке. Если нет, возвращаем готовый ChainResult.
-Где хранить кэш:
+    #[test]
+    fn quote_whirlpool_without_liquidity_is_zero() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = whirlpool(mint_a, mint_b, 0);
+        assert_eq!(quote_pool(&pool, &mint_a, 1_000_000), Some(0));
+    }
 
-Либо внутри router.rs (например, static ref ROUTER_CACHE: DashMap<Vec<String>, ChainResult>).
-Либо в GlobalData (например, Arc<DashMap<Vec<String>, ChainResult>>).
-В любом случае, кэш — это дополнительный механизм оптимизации, не отменяющий того, что PoolEdge хранит самые последние данные.
+    #[test]
+    fn quote_rejects_foreign_mint() {
+        let pool = whirlpool(Pubkey::new_unique(), Pubkey::new_unique(), 1_000_000_000_000);
+        assert_eq!(quote_pool(&pool, &Pubkey::new_unique(), 1_000_000), None);
+    }
 
-*/
\ No newline at end of file
+    #[test]
+    fn raydium_and_meteora_are_not_quoted() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut raydium = RaydiumData::zeroed();
+        raydium.token_a = mint_a;
+        raydium.token_b = mint_b;
+        raydium.min_price = 1;
+        raydium.max_price = 1;
+        raydium.total_lp = u64::MAX;
+        let raydium = PoolStateBase::from_pool_data(Pubkey::new_unique(), &PoolData::Raydium(raydium));
+        assert_eq!(quote_pool(&raydium, &mint_a, 1_000_000), None);
+
+        let mut meteora = MeteoraData::zeroed();
+        meteora.token_mint_a = mint_a;
+        meteora.token_mint_b = mint_b;
+        meteora.liquidity = 1_000_000_000_000;
+        meteora.sqrt_price = Q64;
+        let meteora = PoolStateBase::from_pool_data(Pubkey::new_unique(), &PoolData::Meteora(meteora));
+        assert_eq!(quote_pool(&meteora, &mint_a, 1_000_000), None);
+    }
+
+    // Цикл из трех пулов Orca в графе commitment. Символы и пулы уникальны для теста:
+    // GLOBAL_DATA общий для параллельных тестов.
+    fn register_cycle(prefix: &str, commitment: PoolCommitment) -> (Vec<String>, Vec<Pubkey>) {
+        let symbols: Vec<String> = ["A", "B", "C"].iter().map(|s| format!("{}_{}", prefix, s)).collect();
+        let mints: Vec<Pubkey> = symbols.iter().map(|_| Pubkey::new_unique()).collect();
+        for (symbol, mint) in symbols.iter().zip(&mints) {
+            GLOBAL_DATA.add_token(symbol.clone(), *mint);
+        }
+
+        let mut graph = GLOBAL_DATA.graph_for(commitment).entry("main".to_string()).or_default();
+        let nodes: Vec<_> = symbols.iter().map(|s| graph.add_node(s.clone())).collect();
+        let mut pools = Vec::new();
+        for i in 0..3 {
+            let (from, to) = (i, (i + 1) % 3);
+            let pool = Pubkey::new_unique();
+            GLOBAL_DATA.add_pools(
+                symbols[from].clone(), symbols[to].clone(), pool,
+                mints[from], mints[to], 1_000_000.0, DexType::Orca,
+            );
+            graph.add_edge(nodes[from], nodes[to], PoolEdge::new(pool));
+            pools.push(pool);
+        }
+
+        let chain = vec![symbols[0].clone(), symbols[1].clone(), symbols[2].clone(), symbols[0].clone()];
+        (chain, pools)
+    }
+
+    #[test]
+    fn pool_version_bump_recalculates_chain_once() {
+        let commitment = PoolCommitment::Confirmed;
+        let chain_id = 910_001;
+        let (chain, pools) = register_cycle("CHAIN_CACHE", commitment);
+
+        let (result, recalculated) = RouterEngine::get_or_recalc_chain(chain_id, &chain, commitment);
+        assert!(recalculated);
+        let result = result.unwrap();
+        assert_eq!(result.pool_versions.len(), 3);
+        assert!(result.is_fresh());
+
+        // Без новых версий пулов результат берется из кеша
+        assert!(!RouterEngine::get_or_recalc_chain(chain_id, &chain, commitment).1);
+
+        GLOBAL_DATA.bump_pool_version(commitment, pools[1]);
+        assert!(!GLOBAL_DATA.chain_results.get(&(chain_id, commitment)).unwrap().is_fresh());
+
+        let recalculations = (0..3)
+            .filter(|_| RouterEngine::get_or_recalc_chain(chain_id, &chain, commitment).1)
+            .count();
+        assert_eq!(recalculations, 1);
+
+        let cached = GLOBAL_DATA.chain_results.get(&(chain_id, commitment)).unwrap().clone();
+        assert!(cached.is_fresh());
+        assert!(cached.pool_versions.contains(&(pools[1], GLOBAL_DATA.pool_version(commitment, &pools[1]))));
+    }
+}
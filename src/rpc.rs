@@ -0,0 +1,298 @@
+// src/rpc.rs
+
+// Минимальный JSON-RPC клиент поверх общего HTTP клиента.
+// solana-client тянет solana-program 1.x с несовместимым типом Pubkey,
+// поэтому нужные методы вызываем напрямую.
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use crate::config::{CONFIG, get_http_client};
+
+// Ограничение getMultipleAccounts на количество адресов в одном запросе
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+// Размер подписи ed25519
+const SIGNATURE_LEN: usize = 64;
+
+/// Аккаунт из ответа RPC
+#[derive(Debug, Clone)]
+pub struct RpcAccount {
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+/// Вызов произвольного метода JSON-RPC. Возвращает поле `result`.
+pub async fn rpc_call(url: &str, method: &str, params: Value) -> Result<Value> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+
+    let response: Value = get_http_client()
+        .post(url)
+        .json(&request)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    if let Some(error) = response.get("error") {
+        return Err(anyhow!("RPC {} вернул ошибку: {}", method, error));
+    }
+
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| anyhow!("RPC {}: в ответе нет поля result", method))
+}
+
+/// Разбор аккаунта в кодировке base64
+pub fn parse_account(value: &Value) -> Result<Option<RpcAccount>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    let owner = value["owner"]
+        .as_str()
+        .ok_or_else(|| anyhow!("аккаунт без owner"))?;
+    let data = value["data"][0]
+        .as_str()
+        .ok_or_else(|| anyhow!("аккаунт без data"))?;
+
+    #[allow(deprecated)]
+    let data = base64::decode(data)?;
+
+    Ok(Some(RpcAccount {
+        owner: Pubkey::from_str(owner)?,
+        lamports: value["lamports"].as_u64().unwrap_or(0),
+        data,
+    }))
+}
+
+/// getMultipleAccounts с разбиением на пачки. Порядок результатов совпадает с `addresses`.
+pub async fn get_multiple_accounts(url: &str, addresses: &[Pubkey]) -> Result<Vec<Option<RpcAccount>>> {
+    get_multiple_accounts_with_slot(url, addresses, "processed")
+        .await
+        .map(|accounts| accounts.into_iter().map(|(_, account)| account).collect())
+}
+
+/// getMultipleAccounts с заданным commitment. Каждый аккаунт возвращается вместе
+/// со слотом контекста своей пачки: пачки читаются разными запросами и слоты могут расходиться.
+pub async fn get_multiple_accounts_with_slot(
+    url: &str,
+    addresses: &[Pubkey],
+    commitment: &str,
+) -> Result<Vec<(u64, Option<RpcAccount>)>> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let keys: Vec<String> = chunk.iter().map(|k| k.to_string()).collect();
+        let result = rpc_call(url, "getMultipleAccounts", json!([
+            keys,
+            { "encoding": "base64", "commitment": commitment }
+        ])).await?;
+
+        let values = result["value"]
+            .as_array()
+            .ok_or_else(|| anyhow!("getMultipleAccounts: неожиданный формат ответа"))?;
+        let context_slot = result["context"]["slot"].as_u64().unwrap_or(0);
+
+        for value in values {
+            accounts.push((context_slot, parse_account(value)?));
+        }
+    }
+
+    if accounts.len() != addresses.len() {
+        return Err(anyhow!("getMultipleAccounts: запрошено {}, получено {}", addresses.len(), accounts.len()));
+    }
+
+    Ok(accounts)
+}
+
+/// Последний blockhash и высота блока, до которой он действителен
+pub async fn get_latest_blockhash(url: &str) -> Result<(Hash, u64)> {
+    let result = rpc_call(url, "getLatestBlockhash", json!([{ "commitment": "confirmed" }])).await?;
+
+    let blockhash = result["value"]["blockhash"]
+        .as_str()
+        .ok_or_else(|| anyhow!("getLatestBlockhash: нет blockhash"))?;
+    let last_valid_block_height = result["value"]["lastValidBlockHeight"]
+        .as_u64()
+        .ok_or_else(|| anyhow!("getLatestBlockhash: нет lastValidBlockHeight"))?;
+
+    Ok((Hash::from_str(blockhash)?, last_valid_block_height))
+}
+
+/// Текущая высота блока
+pub async fn get_block_height(url: &str, commitment: &str) -> Result<u64> {
+    let result = rpc_call(url, "getBlockHeight", json!([{ "commitment": commitment }])).await?;
+    result.as_u64().ok_or_else(|| anyhow!("getBlockHeight: неожиданный формат ответа"))
+}
+
+/// Текущий слот
+pub async fn get_slot(url: &str, commitment: &str) -> Result<u64> {
+    let result = rpc_call(url, "getSlot", json!([{ "commitment": commitment }])).await?;
+    result.as_u64().ok_or_else(|| anyhow!("getSlot: неожиданный формат ответа"))
+}
+
+/// Отправка сериализованной транзакции. Возвращает подпись.
+/// В режиме paper trading транзакция не отправляется, возвращается ее подпись.
+pub async fn send_transaction(url: &str, wire_transaction: &[u8], skip_preflight: bool) -> Result<String> {
+    if CONFIG.paper_trading {
+        return wire_signature(wire_transaction);
+    }
+
+    #[allow(deprecated)]
+    let encoded = base64::encode(wire_transaction);
+
+    let result = rpc_call(url, "sendTransaction", json!([
+        encoded,
+        {
+            "encoding": "base64",
+            "skipPreflight": skip_preflight,
+            "preflightCommitment": "processed",
+            "maxRetries": 0
+        }
+    ])).await?;
+
+    result
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| anyhow!("sendTransaction: неожиданный формат ответа"))
+}
+
+/// Первая подпись сериализованной транзакции (после compact-u16 числа подписей)
+pub fn wire_signature(wire_transaction: &[u8]) -> Result<String> {
+    let signature = wire_transaction
+        .get(1..1 + SIGNATURE_LEN)
+        .ok_or_else(|| anyhow!("транзакция короче подписи"))?;
+    Ok(Signature::try_from(signature)?.to_string())
+}
+
+/// Результат simulateTransaction
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    /// Ошибка транзакции (None - симуляция прошла)
+    pub err: Option<Value>,
+    pub logs: Vec<String>,
+    /// Состояние запрошенных аккаунтов после исполнения, в порядке запроса
+    pub accounts: Vec<Option<RpcAccount>>,
+    pub units_consumed: Option<u64>,
+    /// Слот состояния, на котором исполнена симуляция
+    pub slot: u64,
+}
+
+/// Симуляция сериализованной транзакции на processed состоянии.
+/// Blockhash подменяется узлом (replaceRecentBlockhash), подпись не проверяется.
+/// `accounts` - аккаунты, состояние которых вернуть после исполнения.
+pub async fn simulate_transaction(url: &str, wire_transaction: &[u8], accounts: &[Pubkey]) -> Result<SimulationResult> {
+    #[allow(deprecated)]
+    let encoded = base64::encode(wire_transaction);
+    let addresses: Vec<String> = accounts.iter().map(|k| k.to_string()).collect();
+
+    let result = rpc_call(url, "simulateTransaction", json!([
+        encoded,
+        {
+            "encoding": "base64",
+            "commitment": "processed",
+            "sigVerify": false,
+            "replaceRecentBlockhash": true,
+            "accounts": { "encoding": "base64", "addresses": addresses }
+        }
+    ])).await?;
+
+    let value = &result["value"];
+    let err = if value["err"].is_null() { None } else { Some(value["err"].clone()) };
+
+    let logs = value["logs"]
+        .as_array()
+        .map(|logs| logs.iter().filter_map(|l| l.as_str().map(|s| s.to_string())).collect())
+        .unwrap_or_default();
+
+    let accounts = match value["accounts"].as_array() {
+        Some(values) => values.iter().map(parse_account).collect::<Result<Vec<_>>>()?,
+        None => vec![None; accounts.len()],
+    };
+
+    Ok(SimulationResult {
+        err,
+        logs,
+        accounts,
+        units_consumed: value["unitsConsumed"].as_u64(),
+        slot: result["context"]["slot"].as_u64().unwrap_or(0),
+    })
+}
+
+/// Приоритетные комиссии последних слотов (до 150) для транзакций, блокирующих на запись
+/// `accounts` (пустой список - по всем транзакциям). Возвращает пары (слот, микролампорты за CU).
+pub async fn get_recent_prioritization_fees(url: &str, accounts: &[Pubkey]) -> Result<Vec<(u64, u64)>> {
+    let addresses: Vec<String> = accounts.iter().map(|k| k.to_string()).collect();
+    let result = rpc_call(url, "getRecentPrioritizationFees", json!([addresses])).await?;
+
+    let values = result
+        .as_array()
+        .ok_or_else(|| anyhow!("getRecentPrioritizationFees: неожиданный формат ответа"))?;
+
+    Ok(values
+        .iter()
+        .filter_map(|v| Some((v["slot"].as_u64()?, v["prioritizationFee"].as_u64()?)))
+        .collect())
+}
+
+/// Статусы подписей (None - подпись еще не видна узлу)
+pub async fn get_signature_statuses(url: &str, signatures: &[String]) -> Result<Vec<Option<Value>>> {
+    let result = rpc_call(url, "getSignatureStatuses", json!([
+        signatures,
+        { "searchTransactionHistory": false }
+    ])).await?;
+
+    let values = result["value"]
+        .as_array()
+        .ok_or_else(|| anyhow!("getSignatureStatuses: неожиданный формат ответа"))?;
+
+    Ok(values.iter().map(|v| if v.is_null() { None } else { Some(v.clone()) }).collect())
+}
+
+/// Ожидание подтверждения подписи (confirmed или finalized).
+/// Ошибка, если транзакция упала или не подтвердилась за `timeout`.
+/// В режиме paper trading транзакции не отправляются, подтверждение сразу успешно.
+pub async fn confirm_signature(url: &str, signature: &str, timeout: Duration) -> Result<()> {
+    if CONFIG.paper_trading {
+        return Ok(());
+    }
+
+    let started = Instant::now();
+    let signatures = [signature.to_string()];
+
+    while started.elapsed() < timeout {
+        if let Some(Some(status)) = get_signature_statuses(url, &signatures).await?.into_iter().next() {
+            if !status["err"].is_null() {
+                return Err(anyhow!("транзакция {} завершилась ошибкой: {}", signature, status["err"]));
+            }
+            if matches!(status["confirmationStatus"].as_str(), Some("confirmed") | Some("finalized")) {
+                return Ok(());
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(400)).await;
+    }
+
+    Err(anyhow!("транзакция {} не подтверждена за {:?}", signature, timeout))
+}
+
+/// Транзакция по подписи с метаданными (json, версионные транзакции).
+/// None - узел еще не отдает транзакцию.
+pub async fn get_transaction(url: &str, signature: &str) -> Result<Option<Value>> {
+    let result = rpc_call(url, "getTransaction", json!([
+        signature,
+        { "encoding": "json", "commitment": "confirmed", "maxSupportedTransactionVersion": 0 }
+    ])).await?;
+
+    Ok(if result.is_null() { None } else { Some(result) })
+}
@@ -0,0 +1,494 @@
+// src/wallet.rs
+
+// Кошелек бота:
+// - ключ из WALLET_PRIVATE_KEY в base58 или JSON массивом байт (формат solana-keygen);
+// - ATA для каждого токена из INITIAL_TOKENS (Token и Token-2022);
+// - живые балансы SOL и токенов по accountSubscribe;
+// - оборачивание SOL в wSOL и обратно с резервом NATIVE_SOL_RESERVE_LAMPORTS
+//   на комиссии, чаевые и ренту.
+// Доступный остаток по токенам ограничивает объем входа цепочек (trade_amount).
+// В режиме paper trading балансы отслеживаются, но балансировка SOL / wSOL не выполняется.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use futures::{SinkExt, StreamExt};
+use lazy_static::lazy_static;
+use log::{info, debug, warn, error};
+use serde_json::{json, Value};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+#[allow(deprecated)]
+use solana_sdk::system_instruction;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use crate::config::{
+    CONFIG, INITIAL_TOKENS, NATIVE_SOL_RESERVE_LAMPORTS, MIN_WRAP_LAMPORTS,
+    WALLET_REBALANCE_INTERVAL_SECS, WALLET_RECONNECT_DELAY_SECS,
+};
+use crate::costs::{set_token_accounts, mark_token_account};
+use crate::data::GLOBAL_DATA;
+use crate::rpc::{get_multiple_accounts, send_transaction, confirm_signature, parse_account, RpcAccount};
+use crate::execution::accounts::{
+    associated_token_address, create_ata_idempotent_ix, sync_native_ix, close_account_ix, read_u64,
+    TOKEN_PROGRAM, TOKEN_2022_PROGRAM, WSOL,
+};
+use crate::execution::blockhash::BLOCKHASH_CACHE;
+use crate::execution::tx_builder::{compile_transaction, serialize_transaction};
+
+// Смещение amount в аккаунте SPL токена (mint 32 + owner 32)
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    pub static ref PAYER: Arc<Keypair> = Arc::new(
+        load_keypair(&CONFIG.wallet_private_key).expect("WALLET_PRIVATE_KEY: некорректный ключ")
+    );
+
+    pub static ref WALLET: WalletManager = WalletManager::default();
+}
+
+/// Загрузка ключа (64 байта: секрет + публичный ключ) в base58
+/// или JSON массивом байт, как в файле solana-keygen
+pub fn load_keypair(secret: &str) -> Result<Keypair> {
+    let secret = secret.trim();
+
+    if secret.starts_with('[') {
+        let bytes: Vec<u8> = serde_json::from_str(secret)
+            .map_err(|e| anyhow!("ключ не является JSON массивом байт: {}", e))?;
+        return Keypair::try_from(bytes.as_slice()).map_err(|e| anyhow!("некорректная пара ключей: {}", e));
+    }
+
+    // 64 байта в base58 разбираются тем же декодером, что и подпись
+    let bytes = Signature::from_str(secret)
+        .map_err(|_| anyhow!("ключ не является base58 строкой из 64 байт"))?;
+    Keypair::try_from(bytes.as_ref()).map_err(|e| anyhow!("некорректная пара ключей: {}", e))
+}
+
+/// ATA кошелька для токена
+#[derive(Debug, Clone)]
+pub struct TokenAccount {
+    pub symbol: String,
+    pub mint: Pubkey,
+    pub address: Pubkey,
+    pub exists: bool,
+    pub balance: u64,
+    /// Слот последнего обновления баланса
+    pub slot: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct WalletManager {
+    // ATA по адресу аккаунта
+    accounts: DashMap<Pubkey, TokenAccount>,
+    // Адрес ATA по минту
+    by_mint: DashMap<Pubkey, Pubkey>,
+    sol_balance: AtomicU64,
+    loaded: AtomicBool,
+}
+
+/// Минт токена по символу. SOL торгуется как wSOL.
+fn mint_for_symbol(symbol: &str) -> Option<Pubkey> {
+    GLOBAL_DATA.tokens
+        .get(symbol)
+        .map(|t| t.address)
+        .or_else(|| (symbol == "SOL").then_some(*WSOL))
+}
+
+/// Баланс токен-аккаунта (0, если аккаунта нет)
+fn token_amount(account: Option<&RpcAccount>) -> u64 {
+    account
+        .and_then(|a| read_u64(&a.data, TOKEN_ACCOUNT_AMOUNT_OFFSET).ok())
+        .unwrap_or(0)
+}
+
+impl WalletManager {
+    /// Загружены ли ATA и балансы
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.load(Ordering::Relaxed)
+    }
+
+    /// Нативный SOL в лампортах
+    pub fn sol_balance(&self) -> u64 {
+        self.sol_balance.load(Ordering::Relaxed)
+    }
+
+    pub fn token_account(&self, mint: &Pubkey) -> Option<TokenAccount> {
+        let address = *self.by_mint.get(mint)?;
+        self.accounts.get(&address).map(|a| a.clone())
+    }
+
+    /// Остаток токена, доступный для свапа. Для SOL это баланс wSOL:
+    /// свапы списывают вход с ATA, нативный SOL - резерв на комиссии.
+    pub fn available(&self, mint: &Pubkey) -> u64 {
+        self.token_account(mint).map(|a| a.balance).unwrap_or(0)
+    }
+
+    /// Доступный остаток по символу; None, если токен кошельком не отслеживается
+    pub fn available_by_symbol(&self, symbol: &str) -> Option<u64> {
+        let mint = mint_for_symbol(symbol)?;
+        self.by_mint.contains_key(&mint).then(|| self.available(&mint))
+    }
+
+    /// Доступные остатки по символам токенов
+    pub fn inventory(&self) -> HashMap<String, u64> {
+        self.accounts.iter().map(|a| (a.symbol.clone(), a.balance)).collect()
+    }
+
+    // Обновление ATA по состоянию аккаунта
+    fn apply_token_account(&self, address: &Pubkey, account: Option<&RpcAccount>, slot: u64) {
+        if let Some(mut entry) = self.accounts.get_mut(address) {
+            if slot < entry.slot {
+                return;
+            }
+            let exists = account.is_some_and(|a| a.lamports > 0);
+            if exists && !entry.exists {
+                mark_token_account(entry.mint);
+            }
+            entry.exists = exists;
+            entry.balance = token_amount(account);
+            entry.slot = slot;
+        }
+    }
+
+    fn apply_sol(&self, lamports: u64) {
+        self.sol_balance.store(lamports, Ordering::Relaxed);
+    }
+
+    /// Поиск ATA для токенов INITIAL_TOKENS и загрузка балансов через RPC
+    pub async fn discover(&self, rpc_url: &str, owner: &Pubkey) -> Result<()> {
+        let tokens: Vec<(&str, Pubkey)> = INITIAL_TOKENS
+            .iter()
+            .filter_map(|symbol| match mint_for_symbol(symbol) {
+                Some(mint) => Some((*symbol, mint)),
+                None => {
+                    warn!("wallet: минт токена {} не найден", symbol);
+                    None
+                }
+            })
+            .collect();
+
+        // Владелец минта определяет токен-программу (Token / Token-2022) и адрес ATA
+        let mints: Vec<Pubkey> = tokens.iter().map(|(_, mint)| *mint).collect();
+        let mint_accounts = get_multiple_accounts(rpc_url, &mints).await?;
+
+        let mut entries = Vec::with_capacity(tokens.len());
+        for ((symbol, mint), mint_account) in tokens.iter().zip(mint_accounts.iter()) {
+            let token_program = match mint_account {
+                Some(a) if a.owner == *TOKEN_2022_PROGRAM => *TOKEN_2022_PROGRAM,
+                _ => *TOKEN_PROGRAM,
+            };
+            entries.push(TokenAccount {
+                symbol: symbol.to_string(),
+                mint: *mint,
+                address: associated_token_address(owner, mint, &token_program),
+                exists: false,
+                balance: 0,
+                slot: 0,
+            });
+        }
+
+        let mut addresses = vec![*owner];
+        addresses.extend(entries.iter().map(|e| e.address));
+        let accounts = get_multiple_accounts(rpc_url, &addresses).await?;
+
+        self.apply_sol(accounts[0].as_ref().map(|a| a.lamports).unwrap_or(0));
+
+        for (mut entry, account) in entries.into_iter().zip(accounts.iter().skip(1)) {
+            entry.exists = account.is_some();
+            entry.balance = token_amount(account.as_ref());
+            self.by_mint.insert(entry.mint, entry.address);
+            self.accounts.insert(entry.address, entry);
+        }
+
+        set_token_accounts(self.accounts.iter().filter(|a| a.exists).map(|a| a.mint));
+        self.loaded.store(true, Ordering::Relaxed);
+
+        info!("wallet: SOL {}, ATA {} из {} ({:?})",
+            self.sol_balance(),
+            self.accounts.iter().filter(|a| a.exists).count(),
+            self.accounts.len(),
+            self.inventory());
+        Ok(())
+    }
+}
+
+/// Объем входа цепочки с учетом остатка стартового токена.
+/// До загрузки кошелька и для неотслеживаемых токенов возвращает `desired`.
+pub fn trade_amount(symbol: &str, desired: u64) -> u64 {
+    if !WALLET.is_loaded() {
+        return desired;
+    }
+    WALLET.available_by_symbol(symbol)
+        .map(|available| available.min(desired))
+        .unwrap_or(desired)
+}
+
+/// Инструкции оборачивания `lamports` SOL в wSOL
+pub fn wrap_sol_instructions(owner: &Pubkey, lamports: u64) -> Vec<Instruction> {
+    let wsol_account = associated_token_address(owner, &WSOL, &TOKEN_PROGRAM);
+    vec![
+        create_ata_idempotent_ix(owner, owner, &WSOL, &TOKEN_PROGRAM),
+        system_instruction::transfer(owner, &wsol_account, lamports),
+        sync_native_ix(&wsol_account),
+    ]
+}
+
+/// Инструкция разворачивания всего wSOL: ATA закрывается, лампорты возвращаются владельцу
+pub fn unwrap_sol_instructions(owner: &Pubkey) -> Vec<Instruction> {
+    let wsol_account = associated_token_address(owner, &WSOL, &TOKEN_PROGRAM);
+    vec![close_account_ix(&wsol_account, owner, owner)]
+}
+
+/// Сколько SOL обернуть (> 0) или развернуть (< 0), чтобы держать резерв нативного SOL.
+/// Оборачиваем излишек над резервом; разворачиваем, когда SOL упал ниже половины резерва.
+pub fn wsol_rebalance_amount(sol_balance: u64, wsol_balance: u64) -> i64 {
+    if sol_balance > NATIVE_SOL_RESERVE_LAMPORTS + MIN_WRAP_LAMPORTS {
+        return (sol_balance - NATIVE_SOL_RESERVE_LAMPORTS) as i64;
+    }
+    if sol_balance < NATIVE_SOL_RESERVE_LAMPORTS / 2 && wsol_balance > 0 {
+        let needed = NATIVE_SOL_RESERVE_LAMPORTS - sol_balance;
+        return -(needed.min(wsol_balance) as i64);
+    }
+    0
+}
+
+async fn send_and_confirm(rpc_url: &str, payer: &Keypair, instructions: &[Instruction]) -> Result<String> {
+    let blockhash = BLOCKHASH_CACHE.get(rpc_url).await?.blockhash;
+    let tx = compile_transaction(payer, instructions, &[], blockhash)?;
+    let signature = send_transaction(rpc_url, &serialize_transaction(&tx), false).await?;
+    confirm_signature(rpc_url, &signature, CONFIRM_TIMEOUT).await?;
+    Ok(signature)
+}
+
+/// Поддержание резерва нативного SOL: оборачивание излишка или частичное разворачивание
+pub async fn rebalance_wsol(rpc_url: &str, payer: &Keypair) -> Result<()> {
+    let owner = payer.pubkey();
+    let wsol_balance = WALLET.available(&WSOL);
+    let amount = wsol_rebalance_amount(WALLET.sol_balance(), wsol_balance);
+
+    if amount > 0 {
+        let signature = send_and_confirm(rpc_url, payer, &wrap_sol_instructions(&owner, amount as u64)).await?;
+        info!("wallet: обернуто {} лампортов SOL в wSOL ({})", amount, signature);
+    } else if amount < 0 {
+        // Частично развернуть нельзя: закрываем wSOL ATA и оборачиваем остаток обратно
+        let unwrap = amount.unsigned_abs();
+        let mut instructions = unwrap_sol_instructions(&owner);
+        let rewrap = wsol_balance - unwrap;
+        if rewrap > 0 {
+            instructions.extend(wrap_sol_instructions(&owner, rewrap));
+        }
+        let signature = send_and_confirm(rpc_url, payer, &instructions).await?;
+        info!("wallet: развернуто {} лампортов wSOL в SOL ({})", unwrap, signature);
+    }
+    Ok(())
+}
+
+// Подписка на изменения SOL и всех ATA кошелька. Возвращает управление при разрыве соединения.
+async fn subscribe_balances(owner: Pubkey) -> Result<()> {
+    let (ws_stream, _) = connect_async(CONFIG.helius_websocket_url.as_str()).await?;
+    let (mut write, mut read) = ws_stream.split();
+
+    // id запроса -> адрес аккаунта; id 0 - сам кошелек
+    let mut addresses = vec![owner];
+    addresses.extend(WALLET.accounts.iter().map(|a| a.address));
+
+    for (id, address) in addresses.iter().enumerate() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "accountSubscribe",
+            "params": [
+                address.to_string(),
+                { "encoding": "base64", "commitment": "processed" }
+            ]
+        });
+        write.send(Message::Text(request.to_string())).await?;
+    }
+    debug!("wallet: отправлено {} подписок на аккаунты", addresses.len());
+
+    // id подписки -> адрес аккаунта
+    let mut subscriptions: HashMap<u64, Pubkey> = HashMap::new();
+
+    while let Some(msg) = read.next().await {
+        let text = match msg? {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let json: Value = match serde_json::from_str(&text) {
+            Ok(json) => json,
+            Err(e) => {
+                warn!("wallet: некорректное сообщение подписки: {}", e);
+                continue;
+            }
+        };
+
+        // Подтверждение подписки
+        if let (Some(id), Some(subscription)) = (json["id"].as_u64(), json["result"].as_u64()) {
+            if let Some(address) = addresses.get(id as usize) {
+                subscriptions.insert(subscription, *address);
+            }
+            continue;
+        }
+
+        if json["method"].as_str() != Some("accountNotification") {
+            continue;
+        }
+
+        let params = &json["params"];
+        let address = match params["subscription"].as_u64().and_then(|s| subscriptions.get(&s)) {
+            Some(address) => *address,
+            None => continue,
+        };
+        let slot = params["result"]["context"]["slot"].as_u64().unwrap_or(0);
+        let account = parse_account(&params["result"]["value"])?;
+
+        if address == owner {
+            WALLET.apply_sol(account.as_ref().map(|a| a.lamports).unwrap_or(0));
+        } else {
+            WALLET.apply_token_account(&address, account.as_ref(), slot);
+        }
+    }
+
+    Ok(())
+}
+
+/// Запуск менеджера кошелька: поиск ATA, подписки на балансы и поддержание резерва SOL
+pub async fn run_wallet_manager() {
+    let rpc_url = CONFIG.solana_rpc_url.clone();
+    let payer = PAYER.clone();
+    let owner = payer.pubkey();
+
+    info!("wallet: кошелек {}", owner);
+
+    // Балансы: загрузка через RPC и подписка; после разрыва перечитываем пропущенные изменения
+    tokio::spawn({
+        let rpc_url = rpc_url.clone();
+        async move {
+            loop {
+                if let Err(e) = WALLET.discover(&rpc_url, &owner).await {
+                    error!("wallet: не удалось загрузить ATA и балансы: {}", e);
+                } else if let Err(e) = subscribe_balances(owner).await {
+                    warn!("wallet: подписка на балансы прервана: {}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(WALLET_RECONNECT_DELAY_SECS)).await;
+            }
+        }
+    });
+
+    // Транзакции балансировки в paper trading не отправляются, подтверждать и учитывать нечего
+    if CONFIG.paper_trading {
+        info!("wallet: paper trading - балансировка SOL / wSOL отключена");
+        return;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(WALLET_REBALANCE_INTERVAL_SECS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        if !WALLET.is_loaded() {
+            continue;
+        }
+        if let Err(e) = rebalance_wsol(&rpc_url, &payer).await {
+            error!("wallet: ошибка балансировки SOL / wSOL: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair() -> Keypair {
+        Keypair::new_from_array([3; 32])
+    }
+
+    // Аккаунт SPL токена с балансом `amount`
+    fn token_rpc_account(amount: u64) -> RpcAccount {
+        let mut data = vec![0u8; 165];
+        data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8].copy_from_slice(&amount.to_le_bytes());
+        RpcAccount { owner: *TOKEN_PROGRAM, lamports: 2_039_280, data }
+    }
+
+    #[test]
+    fn load_keypair_from_json_array() {
+        let json = serde_json::to_string(&keypair().to_bytes().to_vec()).unwrap();
+        let loaded = load_keypair(&format!("  {}\n", json)).unwrap();
+        assert_eq!(loaded.pubkey(), keypair().pubkey());
+    }
+
+    #[test]
+    fn load_keypair_from_base58() {
+        let loaded = load_keypair(&keypair().to_base58_string()).unwrap();
+        assert_eq!(loaded.pubkey(), keypair().pubkey());
+    }
+
+    #[test]
+    fn load_keypair_rejects_malformed_keys() {
+        // Только 32 байта секрета, без публичного ключа
+        let short_json = serde_json::to_string(&vec![3u8; 32]).unwrap();
+        assert!(load_keypair(&short_json).is_err());
+        assert!(load_keypair("[1, 2, 300]").is_err());
+        assert!(load_keypair("[1, 2,").is_err());
+
+        let short_base58 = Pubkey::new_from_array([3; 32]).to_string();
+        assert!(load_keypair(&short_base58).is_err());
+        assert!(load_keypair("not-a-key-0OIl").is_err());
+        assert!(load_keypair("").is_err());
+    }
+
+    #[test]
+    fn wsol_wraps_surplus_above_reserve() {
+        let reserve = NATIVE_SOL_RESERVE_LAMPORTS;
+        assert_eq!(wsol_rebalance_amount(reserve + MIN_WRAP_LAMPORTS, 0), 0);
+        assert_eq!(wsol_rebalance_amount(reserve + MIN_WRAP_LAMPORTS + 1, 0), (MIN_WRAP_LAMPORTS + 1) as i64);
+        assert_eq!(wsol_rebalance_amount(reserve * 3, 1_000), (reserve * 2) as i64);
+    }
+
+    #[test]
+    fn wsol_unwraps_below_half_reserve() {
+        let reserve = NATIVE_SOL_RESERVE_LAMPORTS;
+        // Между половиной резерва и порогом оборачивания ничего не делаем
+        assert_eq!(wsol_rebalance_amount(reserve / 2, u64::MAX), 0);
+        assert_eq!(wsol_rebalance_amount(reserve, u64::MAX), 0);
+
+        // Добираем SOL до резерва, но не больше имеющегося wSOL
+        assert_eq!(wsol_rebalance_amount(reserve / 2 - 1, u64::MAX), -((reserve / 2 + 1) as i64));
+        assert_eq!(wsol_rebalance_amount(0, 1_000), -1_000);
+        assert_eq!(wsol_rebalance_amount(0, 0), 0);
+    }
+
+    #[test]
+    fn apply_token_account_ignores_older_slots() {
+        let wallet = WalletManager::default();
+        let address = Pubkey::new_unique();
+        wallet.accounts.insert(address, TokenAccount {
+            symbol: "WALLET_TEST".to_string(),
+            mint: Pubkey::new_unique(),
+            address,
+            exists: true,
+            balance: 0,
+            slot: 0,
+        });
+
+        wallet.apply_token_account(&address, Some(&token_rpc_account(500)), 10);
+        assert_eq!(wallet.accounts.get(&address).unwrap().balance, 500);
+
+        // Уведомление из прошлого слота не откатывает баланс
+        wallet.apply_token_account(&address, Some(&token_rpc_account(100)), 9);
+        let entry = wallet.accounts.get(&address).unwrap().clone();
+        assert_eq!((entry.balance, entry.slot), (500, 10));
+
+        // Закрытие аккаунта в том же слоте применяется
+        wallet.apply_token_account(&address, None, 10);
+        let entry = wallet.accounts.get(&address).unwrap().clone();
+        assert_eq!((entry.balance, entry.exists), (0, false));
+    }
+}
@@ -1,5 +1,3 @@
 pub mod ws_data;
-pub mod ws_orca;
-pub mod ws_meteora;
-pub mod ws_raydium;
-pub mod ws_parser;
\ No newline at end of file
+pub mod ws_parser;
+pub mod ws_runner;
\ No newline at end of file
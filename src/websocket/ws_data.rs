@@ -1,80 +1,9 @@
 // src/ws_data.rs
 use serde::Deserialize;
 use serde_json::Value;
-use flume::{Sender, Receiver};
-
-// Тип ответа для Processed (без поддержки слотов)
-pub type ProcessedResponse = WebSocketResponse<NotificationParams<NotificationResultProcessed>>;
-// Тип ответа для Orca DEX (с поддержкой слотов)
-pub type OrcaFinalizedResponse = OrcaWebSocketResponse<NotificationParams<NotificationResultFinalized>>;
-
-
-//////////////////////// КАНАЛЫ ДЛЯ АСИНХРОННОГО ПОТОКА ////////////////////////
-
-
-
-// Каналы WebSocket для Orca DEX (с поддержкой слотов)
-pub struct OrcaWebSocketChannels {
-    pub program_tx: Sender<OrcaFinalizedResponse>,
-    pub slot_tx: Sender<SlotInfo>,
-}
-
-// Реализация для OrcaWebSocketChannels
-pub struct OrcaWebSocketReceivers {
-    pub program_rx: Receiver<OrcaFinalizedResponse>,
-    pub slot_rx: Receiver<SlotInfo>,
-}
-
-// Реализация для OrcaWebSocketChannels
-impl OrcaWebSocketChannels {
-    pub fn new() -> (Self, OrcaWebSocketReceivers) {
-        let (program_tx, program_rx) = flume::unbounded();
-        let (slot_tx, slot_rx) = flume::unbounded();
-        
-        (
-            Self { program_tx, slot_tx },
-            OrcaWebSocketReceivers { program_rx, slot_rx }
-        )
-    }
-}
-
-// Общие каналы WebSocket для всех DEX
-pub struct WebSocketChannels {
-    pub program_tx: Sender<ProcessedResponse>,
-}
-
-// Реализация для WebSocketChannels
-pub struct WebSocketReceivers {
-    pub program_rx: Receiver<ProcessedResponse>,
-}
-
-// Реализация для WebSocketChannels
-impl WebSocketChannels {
-    pub fn new() -> (Self, WebSocketReceivers) {
-        let (program_tx, program_rx) = flume::unbounded();
-        (
-            Self { program_tx },
-            WebSocketReceivers { program_rx }
-        )
-    }
-}
-
-// Трейт для обработки WebSocket каналов
-pub trait WebSocketHandler<T: HasNotificationFields> {
-    fn get_program_tx(&self) -> Option<&Sender<T>>;
-    fn get_slot_tx(&self) -> Option<&Sender<SlotInfo>>;
-}
-
-// Трейт для работы с уведомлениями
-#[allow(dead_code)]
-pub trait HasNotificationFields {
-    fn get_slot(&self) -> Option<SlotInfo>;
-    fn get_params(&self) -> Option<NotificationParams<NotificationResult>>;
-    fn get_notification(&self) -> Option<(u64, Value)>;
-}
-
-//////////////////////// КАНАЛЫ ДЛЯ АСИНХРОННОГО ПОТОКА ////////////////////////
 
+// Тип ответа для всех подписок (program и slot уведомления)
+pub type SubscriptionResponse = WebSocketResponse<NotificationParams<NotificationResult>>;
 
 
 // Перечисление для типов DEX
@@ -90,19 +19,12 @@ pub enum DexType {
 pub struct WebSocketResponse<P> {
     pub method: Option<String>,
     pub params: Option<P>,
-    pub result: Option<u64>,
+    /// id подписки в ответе на subscribe, true / false в ответе на unsubscribe
+    pub result: Option<Value>,
+    pub error: Option<Value>,
     pub id: Option<u64>,
 }
 
-// Структура с поддержкой слотов (специфична для Orca DEX)
-#[derive(Debug, Deserialize, Clone)]
-pub struct OrcaWebSocketResponse<P> {
-    #[serde(flatten)]
-    pub base: WebSocketResponse<P>,
-    // Слоты доступны только для Orca DEX
-    pub slot: Option<SlotInfo>,
-}
-
 // Общая структура для параметров уведомления
 #[derive(Debug, Deserialize, Clone)]
 pub struct NotificationParams<T> {
@@ -110,23 +32,14 @@ pub struct NotificationParams<T> {
     pub subscription: u64,
 }
 
-// Типы результатов уведомлений
+// Результат уведомления: данные аккаунта (programNotification / accountNotification) или новый слот
 #[derive(Debug, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum NotificationResult {
-    Finalized(NotificationResultFinalized),
-    Processed(NotificationResultProcessed),
-}
-
-// Результат уведомления для Finalized (поддерживает слоты, специфично для Orca)
-#[derive(Debug, Deserialize, Clone)]
-#[serde(untagged)]
-pub enum NotificationResultFinalized {
-    Program {
+    Account {
         context: Context,
         value: Value,
     },
-    // Слоты доступны только в Orca DEX
     Slot {
         slot: u64,
         parent: u64,
@@ -134,16 +47,6 @@ pub enum NotificationResultFinalized {
     },
 }
 
-// Результат уведомления для Processed (без поддержки слотов)
-#[derive(Debug, Deserialize, Clone)]
-#[serde(untagged)]
-pub enum NotificationResultProcessed {
-    Program {
-        context: Context,
-        value: Value,
-    },
-}
-
 // Общие структуры данных
 #[derive(Debug, Deserialize, Clone)]
 pub struct Context {
@@ -170,7 +73,7 @@ pub struct ProgramNotification {
     pub account: DataNotification,
 }
 
-// Структура для слотов (специфична для Orca DEX)
+// Структура для слотов
 #[derive(Debug, Deserialize, Clone)]
 pub struct SlotInfo {
     pub slot: u64,
@@ -178,92 +81,14 @@ pub struct SlotInfo {
     pub root: u64,
 }
 
-// Реализация WebSocketHandler для OrcaWebSocketChannels
-impl WebSocketHandler<OrcaFinalizedResponse> for OrcaWebSocketChannels {
-    fn get_program_tx(&self) -> Option<&Sender<OrcaFinalizedResponse>> {
-        Some(&self.program_tx)
-    }
-
-    fn get_slot_tx(&self) -> Option<&Sender<SlotInfo>> {
-        Some(&self.slot_tx)
-    }
-}
-
-// Реализация WebSocketHandler для WebSocketChannels
-impl WebSocketHandler<ProcessedResponse> for WebSocketChannels {
-    fn get_program_tx(&self) -> Option<&Sender<ProcessedResponse>> {
-        Some(&self.program_tx)
-    }
-
-    fn get_slot_tx(&self) -> Option<&Sender<SlotInfo>> {
-        None
-    }
-}
-
-// Реализация HasNotificationFields для OrcaFinalizedResponse
-impl HasNotificationFields for OrcaFinalizedResponse {
-    fn get_slot(&self) -> Option<SlotInfo> {
-        self.slot.clone()
-    }
-
-    fn get_params(&self) -> Option<NotificationParams<NotificationResult>> {
-        self.base.params.clone().map(|p| NotificationParams {
-            result: NotificationResult::Finalized(p.result),
-            subscription: p.subscription,
-        })
-    }
-
-    fn get_notification(&self) -> Option<(u64, Value)> {
-        self.base.params.as_ref().and_then(|p| {
-            match &p.result {
-                NotificationResultFinalized::Program { context, value } => {
-                    Some((context.slot, value.clone()))
-                }
-                _ => None
-            }
-        })
-    }
-}
-
-// Реализация HasNotificationFields для ProcessedResponse 
-impl HasNotificationFields for ProcessedResponse {
-    fn get_slot(&self) -> Option<SlotInfo> {
-        None
-    }
-
-    fn get_params(&self) -> Option<NotificationParams<NotificationResult>> {
-        self.params.clone().map(|p| NotificationParams {
-            result: NotificationResult::Processed(p.result),
-            subscription: p.subscription,
-        })
-    }
-
-    fn get_notification(&self) -> Option<(u64, Value)> {
-        self.params.as_ref().and_then(|p| {
-            match &p.result {
-                NotificationResultProcessed::Program { context, value } => {
-                    Some((context.slot, value.clone()))
-                }
-            }
-        })
-    }
-}
-
 // Трейт для проверки полей подписки
 pub trait HasSubscriptionFields {
     fn has_valid_subscription(&self) -> bool;
 }
 
-// Реализация для разных типов ответов
-impl HasSubscriptionFields for ProcessedResponse {
+// Подтверждение подписки: id запроса и id подписки без метода
+impl<P> HasSubscriptionFields for WebSocketResponse<P> {
     fn has_valid_subscription(&self) -> bool {
         self.result.is_some() && self.id.is_some() && self.method.is_none()
     }
 }
-
-// Обработка обновления аккаунтов 
-impl HasSubscriptionFields for OrcaFinalizedResponse {
-    fn has_valid_subscription(&self) -> bool {
-        self.base.result.is_some() && self.base.id.is_some() && self.base.method.is_none()
-    }
-}
\ No newline at end of file
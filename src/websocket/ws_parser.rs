@@ -6,23 +6,36 @@ use crate::data::GLOBAL_DATA;
 use solana_program::pubkey::Pubkey;
 use crate::decoder::{WhirlpoolData, RaydiumData, MeteoraData};
 use crate::decoder::{decode_base64_zstd, parse_whirlpool_data, parse_raydium_data, parse_meteora_data};
-use crate::data::{unix_timestamp, PoolState, PoolStateBase, FinalizedPoolState, ProcessedPoolState};
+use crate::data::{unix_timestamp, PoolState, PoolStateBase, FinalizedPoolState, ConfirmedPoolState, ProcessedPoolState};
 use std::time::Instant;
-use crate::websocket::ws_data::{
-    WebSocketHandler, HasSubscriptionFields,
-    HasNotificationFields, DexType,
-    SlotInfo, NotificationResultFinalized,
-    NotificationResultProcessed, NotificationResult,
-    ProgramNotification, DataNotification
-};
+use crate::websocket::ws_data::{HasSubscriptionFields, DexType, DataNotification};
 
 // Добавляем enum для типов commitment
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 pub enum PoolCommitment {
     Processed,
+    Confirmed,
     Finalized,
 }
 
+impl PoolCommitment {
+    // Все уровни commitment от самого свежего к самому надежному
+    pub const ALL: [PoolCommitment; 3] = [PoolCommitment::Processed, PoolCommitment::Confirmed, PoolCommitment::Finalized];
+
+    // Значение commitment в запросах RPC и WebSocket
+    pub fn as_param(self) -> &'static str {
+        match self {
+            PoolCommitment::Processed => "processed",
+            PoolCommitment::Confirmed => "confirmed",
+            PoolCommitment::Finalized => "finalized",
+        }
+    }
+
+    pub fn from_param(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_param() == value)
+    }
+}
+
 // Добавляем общий тип для всех пулов
 #[derive(Debug)]
 pub enum PoolData {
@@ -47,65 +60,6 @@ where
     response.has_valid_subscription()
 }
 
-// Обобщенная функция обработки обновлений
-pub async fn handle_program_update<T: HasNotificationFields + Clone>(
-    response: T, 
-    channels: &impl WebSocketHandler<T>,
-    dex: DexType
-) {
-    // info!("Handling program update");
-    let ws_receive_time = Instant::now();
-
-    if let Some(params) = response.get_params() {
-        if let Some(program_tx) = channels.get_program_tx() {
-            let _ = program_tx.send_async(response.clone()).await;
-        }
-
-        match params.result {
-            NotificationResult::Finalized(result) => match result {
-                NotificationResultFinalized::Program { context, value } => {
-                    if let Ok(program_notification) = serde_json::from_value::<ProgramNotification>(value) {
-                        let pubkey = program_notification.pubkey.parse().unwrap_or_default();
-                        process_account_data(
-                            context.slot,
-                            pubkey,
-                            &program_notification.account,
-                            ws_receive_time,
-                            PoolCommitment::Finalized,
-                            dex
-                        ).await;
-                    }
-                    // info!("Program notification finalized received at slot {}", context.slot);
-                },
-                NotificationResultFinalized::Slot { slot, parent, root } => {
-                    let slot_info = SlotInfo { slot, parent, root };
-                    if let Some(slot_tx) = channels.get_slot_tx() {
-                        let _ = slot_tx.send_async(slot_info).await;
-                    }
-                    info!("Slot notification received at slot {}", slot);
-                }
-            },
-            NotificationResult::Processed(result) => match result {
-                NotificationResultProcessed::Program { context, value } => {
-                    if let Ok(program_notification) = serde_json::from_value::<ProgramNotification>(value) {
-                        let pubkey = program_notification.pubkey.parse().unwrap_or_default();
-                        process_account_data(
-                            context.slot,
-                            pubkey,
-                            &program_notification.account,
-                            ws_receive_time,
-                            PoolCommitment::Processed,
-                            dex
-                        ).await;
-                    }
-                    // info!("Program notification processed received at slot {}", context.slot);
-                }
-            }
-        }
-    }
-}
-
-
 // Общая логика обработки данных аккаунта для
 #[allow(unused_variables)]
 pub async fn process_account_data(
@@ -142,65 +96,75 @@ pub async fn process_account_data(
                 let decode_time = processing_start.elapsed();
                 // debug!("Data decoded in {:?}", decode_time);
 
-                let parse_result: Result<PoolData, Box<dyn std::error::Error + Send + Sync>> = match dex {
-                    DexType::Orca => parse_whirlpool_data(&decompressed).map(PoolData::Whirlpool),
-                    DexType::Raydium => parse_raydium_data(&decompressed).map(PoolData::Raydium),
-                    DexType::Meteora => parse_meteora_data(&decompressed).map(PoolData::Meteora),
-                };
+                update_pool_state(slot, pubkey, &decompressed, commitment, dex);
+            },
+            Err(e) => error!("Failed to decode pool {}: {}", pubkey, e)
+        }
+    }
+}
 
-                match parse_result {
-                    Ok(pool_data) => {
-                        // info!("Pool data parsed {:?}", pool_data);
-                        let parse_time = processing_start.elapsed();
-                        // debug!("Data parsed in {:?}", parse_time);
+// Разбор данных аккаунта пула и обновление состояния для commitment
+// (уведомления WebSocket и дозагрузка через getMultipleAccounts после переподключения)
+pub fn update_pool_state(
+    slot: u64,
+    pubkey: Pubkey,
+    data: &[u8],
+    commitment: PoolCommitment,
+    dex: DexType,
+) {
+    let parse_result: Result<PoolData, Box<dyn std::error::Error + Send + Sync>> = match dex {
+        DexType::Orca => parse_whirlpool_data(data).map(PoolData::Whirlpool),
+        DexType::Raydium => parse_raydium_data(data).map(PoolData::Raydium),
+        DexType::Meteora => parse_meteora_data(data).map(PoolData::Meteora),
+    };
 
-                        match commitment {
-                            PoolCommitment::Finalized => {
-                                // info!("Updating finalized pool state for FinalizedPoolState");
-                                if let Some(states) = GLOBAL_DATA.finalized_pool_states.get_mut(&dex) {
-                                    // debug!("States {:?}", states);
-                                    if let Some(mut state) = states.get_mut(&pubkey) {
-                                        // debug!("State {:?}", state);
-                                        state.update(&pool_data, slot);
-                                        // info!("Pool PoolCommitment {:?} updated in {:?} for dex {:?}", commitment, processing_start.elapsed(), dex);
-                                    } else {
-                                        let base = PoolStateBase::from_pool_data(pubkey, &pool_data);
-                                        // debug!("Base {:?}", base);
-                                        states.insert(pubkey, FinalizedPoolState {
-                                            base,
-                                            finalized_slot: slot,
-                                            last_update_time: unix_timestamp(),
-                                        });
-                                        // info!("Pool PoolCommitment {:?} updated in {:?} for dex {:?}", commitment, processing_start.elapsed(), dex);
-                                    }
-                                }
-                            },
-                            PoolCommitment::Processed => {
-                                // info!("Updating processed pool state for ProcessedPoolState");
-                                if let Some(states) = GLOBAL_DATA.processed_pool_states.get_mut(&dex) {
-                                    // debug!("States {:?}", states);
-                                    if let Some(mut state) = states.get_mut(&pubkey) {
-                                        // debug!("State {:?}", state);
-                                        state.update(&pool_data, slot);
-                                        // info!("Pool PoolCommitment {:?} updated in {:?} for dex {:?}", commitment, processing_start.elapsed(), dex);
-                                    } else {
-                                        let base = PoolStateBase::from_pool_data(pubkey, &pool_data);
-                                        // debug!("Base {:?}", base);
-                                        states.insert(pubkey, ProcessedPoolState {
-                                            base,
-                                            processed_slot: slot,
-                                            last_update_time: unix_timestamp(),
-                                        });
-                                        // info!("Pool PoolCommitment {:?} updated in {:?} for dex {:?}", commitment, processing_start.elapsed(), dex);
-                                    }
-                                }
-                            }
+    match parse_result {
+        Ok(pool_data) => {
+            match commitment {
+                PoolCommitment::Finalized => {
+                    if let Some(states) = GLOBAL_DATA.finalized_pool_states.get_mut(&dex) {
+                        if let Some(mut state) = states.get_mut(&pubkey) {
+                            state.update(&pool_data, slot);
+                        } else {
+                            let base = PoolStateBase::from_pool_data(pubkey, &pool_data);
+                            states.insert(pubkey, FinalizedPoolState {
+                                base,
+                                finalized_slot: slot,
+                                last_update_time: unix_timestamp(),
+                            });
                         }
-                    },
-                    Err(e) => error!("Failed to parse pool {}: {}", pubkey, e)
+                    }
+                },
+                PoolCommitment::Confirmed => {
+                    if let Some(states) = GLOBAL_DATA.confirmed_pool_states.get_mut(&dex) {
+                        if let Some(mut state) = states.get_mut(&pubkey) {
+                            state.update(&pool_data, slot);
+                        } else {
+                            let base = PoolStateBase::from_pool_data(pubkey, &pool_data);
+                            states.insert(pubkey, ConfirmedPoolState {
+                                base,
+                                confirmed_slot: slot,
+                                last_update_time: unix_timestamp(),
+                            });
+                        }
+                    }
+                },
+                PoolCommitment::Processed => {
+                    if let Some(states) = GLOBAL_DATA.processed_pool_states.get_mut(&dex) {
+                        if let Some(mut state) = states.get_mut(&pubkey) {
+                            state.update(&pool_data, slot);
+                        } else {
+                            let base = PoolStateBase::from_pool_data(pubkey, &pool_data);
+                            states.insert(pubkey, ProcessedPoolState {
+                                base,
+                                processed_slot: slot,
+                                last_update_time: unix_timestamp(),
+                            });
+                        }
+                    }
                 }
-            },
-            Err(e) => error!("Failed to decode pool {}: {}", pubkey, e)
-        }
+            }
+        },
+        Err(e) => error!("Failed to parse pool {}: {}", pubkey, e)
     }
 }
\ No newline at end of file
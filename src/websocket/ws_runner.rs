@@ -0,0 +1,746 @@
+// src/websocket/ws_runner.rs
+
+// Общий запуск WebSocket подписок DEX.
+// Подписки задаются списком (программа, commitment, DEX) в DEX_SUBSCRIPTIONS.
+// Подписки одного DEX с одинаковым commitment идут через одно соединение,
+// slotSubscribe - через отдельное. Подключение, разбор сообщений и передача
+// данных аккаунтов в process_account_data общие для всех соединений, поэтому
+// новый DEX или уровень commitment - это новая строка списка. Подписки уровней,
+// не входящих в COMMITMENT_TIERS, не открываются.
+//
+// Подписка на программу несет серверные фильтры (dataSize и memcmp по дискриминатору),
+// чтобы не получать позиции, конфиги и прочие аккаунты программы. Если отслеживаемых
+// пулов DEX не больше WS_ACCOUNT_SUBSCRIBE_MAX_POOLS, вместо programSubscribe
+// соединение подписывается accountSubscribe на каждый пул.
+//
+// Каждый запрос получает уникальный id. Из подтверждения запоминается id подписки,
+// назначенный сервером; уведомления направляются по нему к своей программе и DEX.
+// При остановке (stop_dex_subscriptions) подписки снимаются *Unsubscribe.
+//
+// После обрыва соединение восстанавливается с экспоненциальной паузой и jitter,
+// подписки отправляются заново. Пока соединения нет, ребра пулов соединения в графе
+// его commitment помечены устаревшими; после переподключения пулы дозагружаются
+// через getMultipleAccounts, и только затем пометка снимается.
+//
+// Каждое соединение шлет ping раз в WS_PING_INTERVAL_SECS и следит за тишиной каждой
+// подписки (слоты идут часто, программы DEX - реже). Поток без pong или подписка без
+// уведомлений дольше ожидаемого считаются устаревшими, соединение переподключается;
+// здоровье потоков видно в GLOBAL_DATA.network_state ("current".feeds).
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use futures::{SinkExt, StreamExt};
+use futures::future::join_all;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::http::Uri;
+use tracing::{info, error, warn, debug};
+use crate::config::{
+    CONFIG, ORCA_PROGRAM_ID, METEORA_PROGRAM_ID, RAYDIUM_CLMM_PROGRAM_ID, RAYDIUM_V4_PROGRAM_ID,
+    WS_RECONNECT_BASE_MS, WS_RECONNECT_MAX_MS, WS_RECONNECT_RESET_SECS, WS_BACKFILL_RETRIES,
+    WS_PING_INTERVAL_SECS, WS_SLOT_SILENCE_SECS, WS_PROGRAM_SILENCE_SECS, WS_UNSUBSCRIBE_TIMEOUT_MS,
+    WS_SUBSCRIBE_CONFIRM_SECS, WS_ACCOUNT_SUBSCRIBE_MAX_POOLS,
+};
+use crate::data::GLOBAL_DATA;
+use crate::execution::ix_raydium::AMM_INFO_LEN;
+use crate::opportunity_book::OPPORTUNITY_BOOK;
+use crate::router::RouterEngine;
+use crate::rpc::get_multiple_accounts_with_slot;
+use crate::websocket::ws_data::{DataNotification, DexType, NotificationResult, ProgramNotification, SlotInfo, SubscriptionResponse};
+use crate::websocket::ws_parser::{self, process_account_data, update_pool_state, PoolCommitment};
+
+type WsResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+type WsWrite = futures::stream::SplitSink<
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    Message,
+>;
+
+lazy_static! {
+    // id запросов уникальны во всех соединениях и переподключениях
+    static ref NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+    // Сигнал остановки подписок
+    static ref SHUTDOWN: watch::Sender<bool> = watch::channel(false).0;
+}
+
+fn next_request_id() -> u64 {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+fn shutting_down() -> bool {
+    *SHUTDOWN.borrow()
+}
+
+/// Серверный фильтр аккаунтов программы
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountFilter {
+    DataSize(usize),
+    Memcmp { offset: usize, bytes: &'static [u8] },
+}
+
+impl AccountFilter {
+    fn to_json(self) -> Value {
+        match self {
+            AccountFilter::DataSize(size) => json!({ "dataSize": size }),
+            AccountFilter::Memcmp { offset, bytes } => {
+                #[allow(deprecated)]
+                let bytes = base64::encode(bytes);
+                json!({ "memcmp": { "offset": offset, "bytes": bytes, "encoding": "base64" } })
+            }
+        }
+    }
+}
+
+// Размеры и дискриминаторы Anchor аккаунтов пулов
+const WHIRLPOOL_LEN: usize = 653;
+const WHIRLPOOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+const CLMM_POOL_STATE_LEN: usize = 1544;
+const CLMM_POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+const LB_PAIR_LEN: usize = 904;
+const LB_PAIR_DISCRIMINATOR: [u8; 8] = [33, 11, 49, 98, 181, 101, 177, 13];
+
+const ORCA_POOL_FILTERS: &[AccountFilter] = &[
+    AccountFilter::DataSize(WHIRLPOOL_LEN),
+    AccountFilter::Memcmp { offset: 0, bytes: &WHIRLPOOL_DISCRIMINATOR },
+];
+// AMM V4 - не Anchor, дискриминатора нет
+const RAYDIUM_V4_POOL_FILTERS: &[AccountFilter] = &[AccountFilter::DataSize(AMM_INFO_LEN)];
+const RAYDIUM_CLMM_POOL_FILTERS: &[AccountFilter] = &[
+    AccountFilter::DataSize(CLMM_POOL_STATE_LEN),
+    AccountFilter::Memcmp { offset: 0, bytes: &CLMM_POOL_STATE_DISCRIMINATOR },
+];
+const METEORA_POOL_FILTERS: &[AccountFilter] = &[
+    AccountFilter::DataSize(LB_PAIR_LEN),
+    AccountFilter::Memcmp { offset: 0, bytes: &LB_PAIR_DISCRIMINATOR },
+];
+
+/// Подписка на аккаунты программы DEX
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionSpec {
+    pub program_id: &'static str,
+    pub commitment: PoolCommitment,
+    pub dex: DexType,
+    /// Серверные фильтры programSubscribe (пустой список - все аккаунты программы)
+    pub filters: &'static [AccountFilter],
+}
+
+/// Подписки на программы DEX
+pub const DEX_SUBSCRIPTIONS: &[SubscriptionSpec] = &[
+    SubscriptionSpec { program_id: ORCA_PROGRAM_ID,         commitment: PoolCommitment::Finalized, dex: DexType::Orca,    filters: ORCA_POOL_FILTERS },
+    SubscriptionSpec { program_id: ORCA_PROGRAM_ID,         commitment: PoolCommitment::Processed, dex: DexType::Orca,    filters: ORCA_POOL_FILTERS },
+    SubscriptionSpec { program_id: ORCA_PROGRAM_ID,         commitment: PoolCommitment::Confirmed, dex: DexType::Orca,    filters: ORCA_POOL_FILTERS },
+    SubscriptionSpec { program_id: RAYDIUM_V4_PROGRAM_ID,   commitment: PoolCommitment::Finalized, dex: DexType::Raydium, filters: RAYDIUM_V4_POOL_FILTERS },
+    SubscriptionSpec { program_id: RAYDIUM_CLMM_PROGRAM_ID, commitment: PoolCommitment::Finalized, dex: DexType::Raydium, filters: RAYDIUM_CLMM_POOL_FILTERS },
+    SubscriptionSpec { program_id: RAYDIUM_CLMM_PROGRAM_ID, commitment: PoolCommitment::Processed, dex: DexType::Raydium, filters: RAYDIUM_CLMM_POOL_FILTERS },
+    SubscriptionSpec { program_id: RAYDIUM_V4_PROGRAM_ID,   commitment: PoolCommitment::Processed, dex: DexType::Raydium, filters: RAYDIUM_V4_POOL_FILTERS },
+    SubscriptionSpec { program_id: RAYDIUM_CLMM_PROGRAM_ID, commitment: PoolCommitment::Confirmed, dex: DexType::Raydium, filters: RAYDIUM_CLMM_POOL_FILTERS },
+    SubscriptionSpec { program_id: RAYDIUM_V4_PROGRAM_ID,   commitment: PoolCommitment::Confirmed, dex: DexType::Raydium, filters: RAYDIUM_V4_POOL_FILTERS },
+    SubscriptionSpec { program_id: METEORA_PROGRAM_ID,      commitment: PoolCommitment::Finalized, dex: DexType::Meteora, filters: METEORA_POOL_FILTERS },
+    SubscriptionSpec { program_id: METEORA_PROGRAM_ID,      commitment: PoolCommitment::Processed, dex: DexType::Meteora, filters: METEORA_POOL_FILTERS },
+    SubscriptionSpec { program_id: METEORA_PROGRAM_ID,      commitment: PoolCommitment::Confirmed, dex: DexType::Meteora, filters: METEORA_POOL_FILTERS },
+];
+
+/// Одна подписка соединения
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subscription {
+    Program(SubscriptionSpec),
+    /// accountSubscribe на один пул
+    Account { pool: Pubkey, dex: DexType, commitment: PoolCommitment },
+    Slots,
+}
+
+impl Subscription {
+    // Запрос подписки
+    fn request(&self, id: u64) -> Value {
+        match self {
+            Subscription::Program(spec) => {
+                let mut config = json!({
+                    "encoding": "base64+zstd",
+                    "commitment": spec.commitment.as_param()
+                });
+                if !spec.filters.is_empty() {
+                    config["filters"] = spec.filters.iter().map(|f| f.to_json()).collect();
+                }
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "method": "programSubscribe",
+                    "params": [spec.program_id, config]
+                })
+            },
+            Subscription::Account { pool, commitment, .. } => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "accountSubscribe",
+                "params": [
+                    pool.to_string(),
+                    {
+                        "encoding": "base64+zstd",
+                        "commitment": commitment.as_param()
+                    }
+                ]
+            }),
+            Subscription::Slots => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "slotSubscribe",
+                "params": []
+            }),
+        }
+    }
+
+    // Запрос снятия подписки с id сервера
+    fn unsubscribe_request(&self, id: u64, subscription_id: u64) -> Value {
+        let method = match self {
+            Subscription::Program(_) => "programUnsubscribe",
+            Subscription::Account { .. } => "accountUnsubscribe",
+            Subscription::Slots => "slotUnsubscribe",
+        };
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": [subscription_id]
+        })
+    }
+
+    // Ожидаемый предел тишины подписки (отдельный пул может не меняться сколь угодно долго)
+    fn max_silence(&self) -> Option<Duration> {
+        match self {
+            Subscription::Program(_) => Some(Duration::from_secs(WS_PROGRAM_SILENCE_SECS)),
+            Subscription::Account { .. } => None,
+            Subscription::Slots => Some(Duration::from_secs(WS_SLOT_SILENCE_SECS)),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Subscription::Program(spec) => format!("{:?} {} {}", spec.dex, spec.program_id, spec.commitment.as_param()),
+            Subscription::Account { pool, dex, commitment } => format!("{:?} pool {} {}", dex, pool, commitment.as_param()),
+            Subscription::Slots => "slots".to_string(),
+        }
+    }
+}
+
+/// Подписки одного соединения
+#[derive(Debug, Clone)]
+pub struct ConnectionSpec {
+    pub name: String,
+    pub subscriptions: Vec<Subscription>,
+}
+
+impl ConnectionSpec {
+    // DEX и commitment подписок на программы (у соединения слотов - None)
+    fn target(&self) -> Option<(DexType, PoolCommitment)> {
+        self.subscriptions.iter().find_map(|subscription| match subscription {
+            Subscription::Program(spec) => Some((spec.dex, spec.commitment)),
+            Subscription::Account { dex, commitment, .. } => Some((*dex, *commitment)),
+            Subscription::Slots => None,
+        })
+    }
+
+    // Подписки сессии: при небольшом числе пулов DEX подписки на программы
+    // заменяются accountSubscribe на каждый отслеживаемый пул
+    fn session_subscriptions(&self) -> Vec<Subscription> {
+        let Some((dex, commitment)) = self.target() else {
+            return self.subscriptions.clone();
+        };
+        let pools = GLOBAL_DATA.pool_addresses(dex);
+        if pools.is_empty() || pools.len() > WS_ACCOUNT_SUBSCRIBE_MAX_POOLS {
+            return self.subscriptions.clone();
+        }
+
+        info!("WebSocket {}: {} pools, subscribing to pool accounts", self.name, pools.len());
+        self.subscriptions
+            .iter()
+            .filter(|s| !matches!(s, Subscription::Program(_)))
+            .copied()
+            .chain(pools.into_iter().map(|pool| Subscription::Account { pool, dex, commitment }))
+            .collect()
+    }
+}
+
+/// Соединения для списка подписок: по одному на DEX и активный commitment, плюс соединение слотов
+pub fn connections(specs: &[SubscriptionSpec]) -> Vec<ConnectionSpec> {
+    connections_for(specs, GLOBAL_DATA.active_commitments())
+}
+
+// Группировка подписок по соединениям для уровней commitment `active`
+fn connections_for(specs: &[SubscriptionSpec], active: &[PoolCommitment]) -> Vec<ConnectionSpec> {
+    let mut connections: Vec<ConnectionSpec> = Vec::new();
+    for spec in specs.iter().filter(|spec| active.contains(&spec.commitment)) {
+        match connections.iter_mut().find(|c| c.target() == Some((spec.dex, spec.commitment))) {
+            Some(connection) => connection.subscriptions.push(Subscription::Program(*spec)),
+            None => connections.push(ConnectionSpec {
+                name: format!("{:?} {}", spec.dex, spec.commitment.as_param()),
+                subscriptions: vec![Subscription::Program(*spec)],
+            }),
+        }
+    }
+    connections.push(ConnectionSpec {
+        name: "slots".to_string(),
+        subscriptions: vec![Subscription::Slots],
+    });
+    connections
+}
+
+// Подписки сессии: запросы, ожидающие подтверждения, и подтвержденные подписки по id сервера
+#[derive(Default)]
+struct SessionRoutes {
+    pending: HashMap<u64, (Subscription, Instant)>,
+    active: HashMap<u64, ActiveSubscription>,
+}
+
+struct ActiveSubscription {
+    subscription: Subscription,
+    last_notification: Instant,
+}
+
+impl SessionRoutes {
+    // Подписка, которая молчит дольше ожидаемого (или не подтверждена за это время)
+    fn silent(&self) -> Option<String> {
+        let confirm_timeout = Duration::from_secs(WS_SUBSCRIBE_CONFIRM_SECS);
+        if let Some((subscription, sent)) = self.pending.values().find(|(_, sent)| sent.elapsed() > confirm_timeout) {
+            return Some(format!("{} not confirmed for {:?}", subscription.name(), sent.elapsed()));
+        }
+        self.active
+            .values()
+            .find(|a| a.subscription.max_silence().is_some_and(|max| a.last_notification.elapsed() > max))
+            .map(|a| format!("no notifications on {} for {:?}", a.subscription.name(), a.last_notification.elapsed()))
+    }
+}
+
+/// Запуск всех подписок DEX_SUBSCRIPTIONS, каждое соединение в отдельной задаче
+pub fn start_dex_subscriptions() -> Vec<JoinHandle<()>> {
+    connections(DEX_SUBSCRIPTIONS)
+        .into_iter()
+        .map(|connection| tokio::spawn(async move { run_connection(&connection).await }))
+        .collect()
+}
+
+/// Остановка подписок: снятие подписок на сервере и закрытие соединений
+pub async fn stop_dex_subscriptions(handles: Vec<JoinHandle<()>>) {
+    SHUTDOWN.send_replace(true);
+    let timeout = Duration::from_millis(WS_UNSUBSCRIBE_TIMEOUT_MS * 2);
+    if tokio::time::timeout(timeout, join_all(handles)).await.is_err() {
+        warn!("WebSocket subscriptions did not stop within {:?}", timeout);
+    }
+}
+
+// Пауза перед переподключением: экспоненциальная, со случайной половиной (jitter),
+// чтобы соединения не переподключались одновременно
+fn reconnect_delay(attempt: u32) -> Duration {
+    let max_ms = WS_RECONNECT_BASE_MS
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(WS_RECONNECT_MAX_MS);
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(max_ms / 2 + random % (max_ms / 2 + 1))
+}
+
+/// Соединение с переподключением после обрыва, до остановки
+pub async fn run_connection(connection: &ConnectionSpec) {
+    let mut attempt: u32 = 0;
+    let mut reconnecting = false;
+    let mut shutdown = SHUTDOWN.subscribe();
+
+    while !shutting_down() {
+        let started = Instant::now();
+        // Сессия завершается без ошибки только при остановке
+        match run_session(connection, reconnecting).await {
+            Ok(()) => break,
+            Err(e) => {
+                error!("WebSocket {} error: {}", connection.name, e);
+                GLOBAL_DATA.update_feed_health(&connection.name, false, Some(e.to_string()));
+            }
+        }
+
+        // Обновления пулов, пока соединения нет, теряются
+        mark_stale(connection);
+
+        // Долго проработавшее соединение переподключаем без накопленной паузы
+        if started.elapsed() >= Duration::from_secs(WS_RECONNECT_RESET_SECS) {
+            attempt = 0;
+        }
+        let delay = reconnect_delay(attempt);
+        attempt = attempt.saturating_add(1);
+        warn!("WebSocket {}: reconnecting in {:?} (attempt {})", connection.name, delay, attempt);
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown.changed() => {}
+        }
+        reconnecting = true;
+    }
+    info!("WebSocket {} stopped", connection.name);
+}
+
+// Одно подключение: подписка, дозагрузка после переподключения и обработка сообщений до обрыва
+async fn run_session(connection: &ConnectionSpec, backfill: bool) -> WsResult<()> {
+    info!("Starting WebSocket {} subscriptions", connection.name);
+    let mut shutdown = SHUTDOWN.subscribe();
+
+    let url = CONFIG.helius_websocket_url.parse::<Uri>()?;
+    debug!("Connecting to WebSocket URL");
+
+    let (ws_stream, _) = connect_async(url).await?;
+    info!("Successfully connected to WebSocket server");
+
+    let (mut write, mut read) = ws_stream.split();
+
+    // Отправляем подписки, каждую со своим id запроса
+    let mut routes = SessionRoutes::default();
+    for subscription in connection.session_subscriptions() {
+        let id = next_request_id();
+        let request = subscription.request(id);
+        debug!("Sending {} request {}", request["method"], id);
+        write.send(Message::Text(request.to_string())).await?;
+        routes.pending.insert(id, (subscription, Instant::now()));
+    }
+    info!("Successfully sent all subscription requests");
+
+    // Подписки уже активны: уведомления копятся в сокете и применятся после дозагрузки
+    if backfill {
+        backfill_pools(connection).await?;
+
+        // Подтверждения лежат непрочитанными, пока идет дозагрузка: отсчет таймаута
+        // подтверждения начинаем с момента, когда начинаем читать сокет
+        let now = Instant::now();
+        for (_, sent) in routes.pending.values_mut() {
+            *sent = now;
+        }
+    }
+
+    // Ping держит соединение и выявляет полуоткрытый TCP: без pong до следующего ping
+    // поток мертв
+    let mut ping_interval = tokio::time::interval(Duration::from_secs(WS_PING_INTERVAL_SECS));
+    ping_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_ping: Option<Instant> = None;
+    let mut last_pong = Instant::now();
+    let mut healthy = false;
+
+    // Обработка сообщений
+    loop {
+        tokio::select! {
+            msg = read.next() => {
+                let Some(msg) = msg else {
+                    return Err("connection closed".into());
+                };
+                // Ошибка чтения - соединение разорвано, переподключаемся
+                match msg? {
+                    Message::Text(text) => {
+                        let notified = handle_message(connection, &mut routes, &text).await?;
+                        // Первое уведомление сессии - поток снова жив
+                        if notified && !healthy {
+                            healthy = true;
+                            GLOBAL_DATA.update_feed_health(&connection.name, true, None);
+                        }
+                    }
+                    Message::Pong(_) => last_pong = Instant::now(),
+                    _ => {}
+                }
+            }
+            _ = ping_interval.tick() => {
+                if last_ping.is_some_and(|ping| last_pong < ping) {
+                    return Err(format!("no pong within {}s", WS_PING_INTERVAL_SECS).into());
+                }
+                if let Some(reason) = routes.silent() {
+                    return Err(reason.into());
+                }
+                write.send(Message::Ping(Vec::new())).await?;
+                last_ping = Some(Instant::now());
+            }
+            _ = shutdown.changed() => {
+                unsubscribe(connection, &mut write, &mut read, &routes).await;
+                return Ok(());
+            }
+        }
+    }
+}
+
+// Снятие подписок сессии и закрытие соединения. Ответы ждем не дольше WS_UNSUBSCRIBE_TIMEOUT_MS.
+async fn unsubscribe<R>(connection: &ConnectionSpec, write: &mut WsWrite, read: &mut R, routes: &SessionRoutes)
+where
+    R: futures::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    let mut waiting: Vec<u64> = Vec::new();
+    for (subscription_id, active) in &routes.active {
+        let id = next_request_id();
+        let request = active.subscription.unsubscribe_request(id, *subscription_id);
+        if write.send(Message::Text(request.to_string())).await.is_ok() {
+            waiting.push(id);
+        }
+    }
+
+    let confirmations = async {
+        while !waiting.is_empty() {
+            let Some(Ok(Message::Text(text))) = read.next().await else {
+                break;
+            };
+            if let Ok(response) = serde_json::from_str::<SubscriptionResponse>(&text) {
+                waiting.retain(|id| response.id != Some(*id));
+            }
+        }
+    };
+    if tokio::time::timeout(Duration::from_millis(WS_UNSUBSCRIBE_TIMEOUT_MS), confirmations).await.is_err() {
+        warn!("WebSocket {}: unsubscribe not confirmed in time", connection.name);
+    }
+
+    let _ = write.send(Message::Close(None)).await;
+    info!("WebSocket {}: unsubscribed {} subscriptions", connection.name, routes.active.len());
+}
+
+// Пометка пулов соединения устаревшими и удаление возможностей через них
+fn mark_stale(connection: &ConnectionSpec) {
+    let Some((dex, commitment)) = connection.target() else {
+        return;
+    };
+    let pools = GLOBAL_DATA.pool_addresses(dex);
+    GLOBAL_DATA.set_pools_stale(commitment, &pools, true);
+    for pool in &pools {
+        OPPORTUNITY_BOOK.remove_pool(pool, commitment);
+    }
+    warn!("WebSocket {}: {} pools marked stale", connection.name, pools.len());
+}
+
+// Дозагрузка состояния пулов соединения и снятие пометки устаревших
+async fn backfill_pools(connection: &ConnectionSpec) -> WsResult<()> {
+    let Some((dex, commitment)) = connection.target() else {
+        return Ok(());
+    };
+    let pools = GLOBAL_DATA.pool_addresses(dex);
+
+    let mut retry = 0;
+    let accounts = loop {
+        match get_multiple_accounts_with_slot(&CONFIG.solana_rpc_url, &pools, commitment.as_param()).await {
+            Ok(result) => break result,
+            Err(e) if retry + 1 < WS_BACKFILL_RETRIES => {
+                warn!("WebSocket {}: backfill failed: {}", connection.name, e);
+                tokio::time::sleep(reconnect_delay(retry)).await;
+                retry += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    // Каждый пул получает слот своей пачки; уведомления старее этого слота
+    // затем отбрасываются в *PoolState::update
+    let mut max_slot = 0;
+    for (pool, (slot, account)) in pools.iter().zip(&accounts) {
+        max_slot = max_slot.max(*slot);
+        if let Some(account) = account {
+            update_pool_state(*slot, *pool, &account.data, commitment, dex);
+        }
+    }
+
+    GLOBAL_DATA.set_pools_stale(commitment, &pools, false);
+    // Цепочки, чьи пулы не изменились за время обрыва, пересчитываются здесь
+    for pool in &pools {
+        RouterEngine::update_affected_chains(*pool, commitment);
+    }
+    info!("WebSocket {}: backfilled {} pools up to slot {}", connection.name, pools.len(), max_slot);
+    Ok(())
+}
+
+// Разбор сообщения и передача данных. Возвращает true, если это уведомление подписки;
+// ошибка - подписка отклонена сервером.
+async fn handle_message(connection: &ConnectionSpec, routes: &mut SessionRoutes, text: &str) -> WsResult<bool> {
+    let json = match serde_json::from_str::<Value>(text) {
+        Ok(json) => json,
+        Err(e) => {
+            error!("Failed to parse JSON: {}", e);
+            return Ok(false);
+        }
+    };
+    let response = match ws_parser::parse_ws_message::<SubscriptionResponse>(json).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to parse WebSocket message: {}", e);
+            return Ok(false);
+        }
+    };
+
+    // Ответ на запрос подписки: запоминаем id подписки сервера
+    if let Some(id) = response.id {
+        let Some((subscription, _)) = routes.pending.remove(&id) else {
+            debug!("Response to unknown request {}", id);
+            return Ok(false);
+        };
+        if let Some(error) = response.error {
+            return Err(format!("{} rejected: {}", subscription.name(), error).into());
+        }
+        if !ws_parser::is_subscription_success(&response) {
+            return Err(format!("{} not confirmed: {}", subscription.name(), text).into());
+        }
+        let Some(subscription_id) = response.result.as_ref().and_then(Value::as_u64) else {
+            return Err(format!("{}: invalid subscription id: {}", subscription.name(), text).into());
+        };
+        info!("Subscribed {} on {} with id {}", subscription.name(), connection.name, subscription_id);
+        routes.active.insert(subscription_id, ActiveSubscription { subscription, last_notification: Instant::now() });
+        return Ok(false);
+    }
+
+    let Some(params) = response.params else {
+        debug!("Received message without params");
+        return Ok(false);
+    };
+
+    // Уведомление направляется по id подписки
+    let Some(active) = routes.active.get_mut(&params.subscription) else {
+        debug!("Notification for unknown subscription {} on {}", params.subscription, connection.name);
+        return Ok(false);
+    };
+    active.last_notification = Instant::now();
+
+    match (active.subscription, response.method.as_deref(), params.result) {
+        (Subscription::Program(spec), Some("programNotification"), NotificationResult::Account { context, value }) => {
+            if let Ok(program_notification) = serde_json::from_value::<ProgramNotification>(value) {
+                let pubkey = program_notification.pubkey.parse().unwrap_or_default();
+                process_account_data(
+                    context.slot,
+                    pubkey,
+                    &program_notification.account,
+                    Instant::now(),
+                    spec.commitment,
+                    spec.dex
+                ).await;
+            }
+            Ok(true)
+        },
+        // В accountNotification адреса нет - пул известен по подписке
+        (Subscription::Account { pool, dex, commitment }, Some("accountNotification"), NotificationResult::Account { context, value }) => {
+            if let Ok(account) = serde_json::from_value::<DataNotification>(value) {
+                process_account_data(context.slot, pool, &account, Instant::now(), commitment, dex).await;
+            }
+            Ok(true)
+        },
+        (Subscription::Slots, Some("slotNotification"), NotificationResult::Slot { slot, parent, root }) => {
+            handle_slot(SlotInfo { slot, parent, root });
+            Ok(true)
+        },
+        (subscription, method, _) => {
+            warn!("Unexpected notification {:?} for {}", method, subscription.name());
+            Ok(false)
+        },
+    }
+}
+
+// Проверка и обновление состояния сети по новому слоту
+fn handle_slot(slot_info: SlotInfo) {
+    if slot_info.slot < slot_info.parent {
+        warn!("Invalid slot sequence detected: slot {} is less than parent {}",
+              slot_info.slot, slot_info.parent);
+    }
+
+    if slot_info.parent.saturating_sub(slot_info.root) > 150 {
+        warn!("Large gap between parent and root slots: parent={}, root={}",
+              slot_info.parent, slot_info.root);
+    }
+
+    GLOBAL_DATA.update_network_state(slot_info);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(connections: &[ConnectionSpec]) -> Vec<&str> {
+        connections.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn connections_group_by_dex_and_active_commitment() {
+        let connections = connections_for(DEX_SUBSCRIPTIONS, &[PoolCommitment::Processed, PoolCommitment::Confirmed]);
+
+        assert_eq!(names(&connections), vec![
+            "Orca processed", "Orca confirmed",
+            "Raydium processed", "Raydium confirmed",
+            "Meteora processed", "Meteora confirmed",
+            "slots",
+        ]);
+
+        // AMM V4 и CLMM одного commitment идут через одно соединение
+        let raydium = &connections[2];
+        assert_eq!(raydium.target(), Some((DexType::Raydium, PoolCommitment::Processed)));
+        let programs: Vec<&str> = raydium.subscriptions.iter().map(|s| match s {
+            Subscription::Program(spec) => spec.program_id,
+            other => panic!("unexpected subscription {:?}", other),
+        }).collect();
+        assert_eq!(programs, vec![RAYDIUM_CLMM_PROGRAM_ID, RAYDIUM_V4_PROGRAM_ID]);
+
+        let slots = connections.last().unwrap();
+        assert_eq!(slots.subscriptions, vec![Subscription::Slots]);
+        assert_eq!(slots.target(), None);
+    }
+
+    #[test]
+    fn connections_skip_inactive_commitments() {
+        let connections = connections_for(DEX_SUBSCRIPTIONS, &[PoolCommitment::Finalized]);
+        assert_eq!(names(&connections), vec!["Orca finalized", "Raydium finalized", "Meteora finalized", "slots"]);
+        assert_eq!(connections[1].subscriptions.len(), 2);
+    }
+
+    #[test]
+    fn program_subscribe_request_carries_filters() {
+        let spec = DEX_SUBSCRIPTIONS
+            .iter()
+            .find(|s| s.dex == DexType::Orca && s.commitment == PoolCommitment::Processed)
+            .unwrap();
+
+        assert_eq!(Subscription::Program(*spec).request(7), json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "programSubscribe",
+            "params": [
+                ORCA_PROGRAM_ID,
+                {
+                    "encoding": "base64+zstd",
+                    "commitment": "processed",
+                    "filters": [
+                        { "dataSize": 653 },
+                        { "memcmp": { "offset": 0, "bytes": "P5XRDOGAYwk=", "encoding": "base64" } }
+                    ]
+                }
+            ]
+        }));
+    }
+
+    #[test]
+    fn program_subscribe_request_without_discriminator() {
+        let spec = DEX_SUBSCRIPTIONS
+            .iter()
+            .find(|s| s.program_id == RAYDIUM_V4_PROGRAM_ID && s.commitment == PoolCommitment::Confirmed)
+            .unwrap();
+        let request = Subscription::Program(*spec).request(8);
+        assert_eq!(request["params"][1]["filters"], json!([{ "dataSize": AMM_INFO_LEN }]));
+
+        // Без фильтров поле filters не передается
+        let unfiltered = SubscriptionSpec { filters: &[], ..*spec };
+        assert!(Subscription::Program(unfiltered).request(9)["params"][1].get("filters").is_none());
+    }
+
+    #[test]
+    fn account_and_slot_subscribe_requests() {
+        let pool = Pubkey::new_from_array([5; 32]);
+        let account = Subscription::Account { pool, dex: DexType::Meteora, commitment: PoolCommitment::Confirmed };
+        assert_eq!(account.request(10), json!({
+            "jsonrpc": "2.0",
+            "id": 10,
+            "method": "accountSubscribe",
+            "params": [pool.to_string(), { "encoding": "base64+zstd", "commitment": "confirmed" }]
+        }));
+
+        assert_eq!(Subscription::Slots.request(11), json!({
+            "jsonrpc": "2.0",
+            "id": 11,
+            "method": "slotSubscribe",
+            "params": []
+        }));
+        assert_eq!(Subscription::Slots.unsubscribe_request(12, 99)["method"], "slotUnsubscribe");
+    }
+}